@@ -119,14 +119,20 @@ pub(super) async fn get_pdu(&self, event_id: OwnedEventId) -> Result {
 			.await;
 	}
 
+	let rejection = self
+		.services
+		.pdu_metadata
+		.event_rejection(&event_id)
+		.await;
+
 	match pdu_json {
 		| Err(_) => return Err!("PDU not found locally."),
 		| Ok(json) => {
 			let text = serde_json::to_string_pretty(&json)?;
-			let msg = if outlier {
-				"Outlier (Rejected / Soft Failed) PDU found in our database"
-			} else {
-				"PDU found in our database"
+			let msg = match rejection {
+				| Ok(reason) => format!("Rejected PDU found in our database: {reason}"),
+				| Err(_) if outlier => "Outlier PDU found in our database".into(),
+				| Err(_) => "PDU found in our database".into(),
 			};
 			write!(self, "{msg}\n```json\n{text}\n```",)
 		},
@@ -659,6 +665,8 @@ pub(super) async fn force_set_room_state_from_server(
 		.await?;
 
 	info!("Forcing new room state");
+	let state_lock = self.services.state.mutex.lock(&*room_id).await;
+	let gc_guard = self.services.state_compressor.gc_guard().await;
 	let HashSetCompressStateEvent {
 		shortstatehash: short_state_hash,
 		added,
@@ -669,13 +677,13 @@ pub(super) async fn force_set_room_state_from_server(
 		.save_state(room_id.clone().as_ref(), new_room_state)
 		.await?;
 
-	let state_lock = self.services.state.mutex.lock(&*room_id).await;
-
 	self.services
 		.state
 		.force_state(room_id.clone().as_ref(), short_state_hash, added, removed, &state_lock)
 		.await?;
 
+	drop(gc_guard);
+
 	info!(
 		"Updating joined counts for room just in case (e.g. we may have found a difference in \
 		 the room's m.room.member state"
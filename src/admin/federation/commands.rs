@@ -1,6 +1,12 @@
+use std::{
+	fmt::Write,
+	time::{Duration, SystemTime},
+};
+
 use futures::StreamExt;
 use ruma::{OwnedRoomId, OwnedServerName, OwnedUserId};
-use tuwunel_core::{Err, Result};
+use tuwunel_core::{Err, Result, utils::time::pretty};
+use tuwunel_service::federation::ServerPolicy;
 
 use crate::{admin_command, get_room_info};
 
@@ -16,6 +22,268 @@ pub(super) async fn enable_room(&self, room_id: OwnedRoomId) -> Result {
 	self.write_str("Room enabled.").await
 }
 
+#[admin_command]
+pub(super) async fn reset_backoff(&self, server_name: OwnedServerName) -> Result {
+	if self.services.globals.server_is_ours(&server_name) {
+		return Err!("Cannot reset the backoff for our own server.");
+	}
+
+	let health = self
+		.services
+		.sending
+		.destination_health(&server_name)
+		.await;
+
+	self.services
+		.sending
+		.reset_backoff(&server_name)?;
+
+	match health {
+		| Ok(health) if health.failures > 0 =>
+			self.write_str(&format!(
+				"Cleared backoff for {server_name} after {} failed attempts.",
+				health.failures
+			))
+			.await,
+		| _ =>
+			self.write_str(&format!("{server_name} was not backing off; flushed its queue."))
+				.await,
+	}
+}
+
+#[admin_command]
+pub(super) async fn enable_allowlist(&self) -> Result {
+	self.services
+		.federation
+		.set_allowlist_enabled(true);
+	self.write_str("Federation allow-list enabled.")
+		.await
+}
+
+#[admin_command]
+pub(super) async fn disable_allowlist(&self) -> Result {
+	self.services
+		.federation
+		.set_allowlist_enabled(false);
+	self.write_str("Federation allow-list disabled.")
+		.await
+}
+
+#[admin_command]
+pub(super) async fn allow(&self, server_name: OwnedServerName) -> Result {
+	let mut policy = self
+		.services
+		.federation
+		.policy(&server_name)
+		.await;
+	policy.allowed = true;
+	self.services
+		.federation
+		.set_policy(&server_name, &policy);
+
+	self.write_str(&format!("Added {server_name} to the federation allow-list."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn disallow(&self, server_name: OwnedServerName) -> Result {
+	let mut policy = self
+		.services
+		.federation
+		.policy(&server_name)
+		.await;
+	if !policy.allowed {
+		return Err!("{server_name} is not on the federation allow-list.");
+	}
+
+	policy.allowed = false;
+	self.services
+		.federation
+		.set_policy(&server_name, &policy);
+
+	self.write_str(&format!("Removed {server_name} from the federation allow-list."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn set_policy(
+	&self,
+	server_name: OwnedServerName,
+	no_media: bool,
+	no_edus: bool,
+	no_invites: bool,
+	read_only: bool,
+) -> Result {
+	if self.services.globals.server_is_ours(&server_name) {
+		return Err!("Cannot set a federation policy for our own server.");
+	}
+
+	let policy = ServerPolicy {
+		allowed: self
+			.services
+			.federation
+			.policy(&server_name)
+			.await
+			.allowed,
+		no_media,
+		no_edus,
+		no_invites,
+		read_only,
+	};
+
+	self.services
+		.federation
+		.set_policy(&server_name, &policy);
+
+	self.write_str(&format!("Federation policy for {server_name}: {policy:?}"))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn list_policies(&self) -> Result {
+	let allowlist = if self.services.federation.allowlist_enabled() {
+		"enabled"
+	} else {
+		"disabled"
+	};
+
+	let policies: Vec<_> = self
+		.services
+		.federation
+		.policies()
+		.map(|(server_name, policy)| format!("{server_name}: {policy:?}"))
+		.collect()
+		.await;
+
+	let list = policies.join("\n");
+	self.write_str(&format!(
+		"Federation allow-list is {allowlist}.\n\nServers with a policy ({}):\n```\n{list}\n```",
+		policies.len()
+	))
+	.await
+}
+
+#[admin_command]
+pub(super) async fn signing_key_report(&self, refresh: bool) -> Result {
+	if refresh {
+		let refreshed = self.services.server_keys.refresh_keys().await;
+		self.write_str(&format!("Refreshed signing keys of {refreshed} servers.\n\n"))
+			.await?;
+	}
+
+	let window = Duration::from_secs(self.services.config.signing_key_refresh_window);
+	let statuses = self.services.server_keys.key_status().await;
+	let total = statuses.len();
+
+	let mut out = String::new();
+	let mut reported = 0_usize;
+	for status in statuses {
+		let expires_in = status
+			.valid_until_ts
+			.and_then(|ts| ts.to_system_time())
+			.map(|ts| {
+				ts.duration_since(SystemTime::now())
+					.unwrap_or_default()
+			});
+
+		if status.error.is_none() && expires_in.is_some_and(|expires_in| expires_in > window) {
+			continue;
+		}
+
+		let state = match expires_in {
+			| None => "no keys".to_owned(),
+			| Some(_) if status.is_expired() => "expired".to_owned(),
+			| Some(expires_in) => format!("expires in {}", pretty(expires_in)),
+		};
+
+		reported = reported.saturating_add(1);
+		writeln!(
+			out,
+			"{} {state}{}",
+			status.server,
+			status
+				.error
+				.map(|error| format!(" (last refresh: {error})"))
+				.unwrap_or_default(),
+		)?;
+	}
+
+	if reported == 0 {
+		return self
+			.write_str(&format!("Signing keys of all {total} servers are valid."))
+			.await;
+	}
+
+	self.write_str(&format!(
+		"Servers with expired or unverifiable signing keys ({reported} of \
+		 {total}):\n```\n{out}```"
+	))
+	.await
+}
+
+#[admin_command]
+pub(super) async fn txn_log(
+	&self,
+	server_name: Option<OwnedServerName>,
+	limit: usize,
+	json: bool,
+) -> Result {
+	if !self.services.federation.txn_log_enabled() {
+		return Err!(
+			"The federation transaction log is disabled; set federation_txn_log_capacity."
+		);
+	}
+
+	let mut entries = self
+		.services
+		.federation
+		.txn_log(server_name.as_deref());
+
+	let skip = entries.len().saturating_sub(limit);
+	let entries: Vec<_> = entries.drain(skip..).rev().collect();
+
+	if json {
+		let json = serde_json::to_string_pretty(&entries)?;
+		return self
+			.write_str(&format!("```json\n{json}\n```"))
+			.await;
+	}
+
+	let mut out = String::new();
+	for entry in &entries {
+		let failed = entry
+			.pdu_results
+			.values()
+			.filter(|result| result.is_err())
+			.count();
+
+		writeln!(
+			out,
+			"{} {:?} {} txn={} pdus={} (failed {failed}) edus={} {}ms{}",
+			entry.ts.get(),
+			entry.direction,
+			entry.server,
+			entry.txn_id,
+			entry.pdus,
+			entry.edus,
+			entry.latency_ms,
+			entry
+				.error
+				.as_ref()
+				.map(|error| format!(" error: {error}"))
+				.unwrap_or_default(),
+		)?;
+	}
+
+	let dropped = match self.services.federation.txn_log_dropped() {
+		| 0 => String::new(),
+		| dropped => format!("\n{dropped} transactions were not written to the log file."),
+	};
+
+	self.write_str(&format!("{} transactions:\n```\n{out}```{dropped}", entries.len()))
+		.await
+}
+
 #[admin_command]
 pub(super) async fn incoming_federation(&self) -> Result {
 	Err!("This command is temporarily disabled")
@@ -35,6 +35,81 @@ pub(super) enum FederationCommand {
 		server_name: OwnedServerName,
 	},
 
+	/// - Clears the federation backoff for a server and retries anything queued
+	///   for it immediately
+	ResetBackoff {
+		server_name: OwnedServerName,
+	},
+
+	/// - Enables the federation allow-list; only servers on it are federated
+	///   with
+	EnableAllowlist,
+
+	/// - Disables the federation allow-list
+	DisableAllowlist,
+
+	/// - Adds a server to the federation allow-list
+	Allow {
+		server_name: OwnedServerName,
+	},
+
+	/// - Removes a server from the federation allow-list
+	Disallow {
+		server_name: OwnedServerName,
+	},
+
+	/// - Sets the federation restrictions for a server, replacing any
+	///   previously set; with no flags the restrictions are cleared
+	SetPolicy {
+		server_name: OwnedServerName,
+
+		/// Never fetch media from the server
+		#[arg(long)]
+		no_media: bool,
+
+		/// Ignore presence and typing EDUs from the server and send it none
+		#[arg(long)]
+		no_edus: bool,
+
+		/// Reject invites sent by the server
+		#[arg(long)]
+		no_invites: bool,
+
+		/// Accept no events, joins, knocks or invites from the server
+		#[arg(long)]
+		read_only: bool,
+	},
+
+	/// - Lists the allow-list state and all servers with a federation policy
+	ListPolicies,
+
+	/// - Shows recently logged inbound and outbound federation transactions
+	///
+	/// Requires `federation_txn_log_capacity` to be set. Without a server
+	/// name the transactions with all servers are shown.
+	TxnLog {
+		server_name: Option<OwnedServerName>,
+
+		/// Maximum number of transactions to show, newest first
+		#[arg(short, long, default_value("20"))]
+		limit: usize,
+
+		/// Dump the transactions as JSON
+		#[arg(long)]
+		json: bool,
+	},
+
+	/// - Reports servers in rooms with local users whose signing keys are
+	///   expired or could not be fetched
+	///
+	/// Servers whose keys expire within `signing_key_refresh_window` are
+	/// included as well.
+	SigningKeyReport {
+		/// Refresh the signing keys before reporting
+		#[arg(long)]
+		refresh: bool,
+	},
+
 	/// - Lists all the rooms we share/track with the specified *remote* user
 	RemoteUserInRooms {
 		user_id: OwnedUserId,
@@ -1,7 +1,7 @@
 use clap::Subcommand;
 use futures::StreamExt;
 use ruma::{OwnedServerName, OwnedUserId};
-use tuwunel_core::{Err, Result};
+use tuwunel_core::{Err, Result, utils::ReadyExt};
 use tuwunel_service::sending::Destination;
 
 use crate::Context;
@@ -59,6 +59,22 @@ pub(crate) enum SendingCommand {
 	GetLatestEduCount {
 		server_name: OwnedServerName,
 	},
+
+	/// - Queries the persisted delivery health and backoff of federation
+	///   destinations; all destinations with an entry if none is given
+	DestinationHealth {
+		server_name: Option<OwnedServerName>,
+	},
+
+	/// - Queries the persisted delivery health and backoff of pushers; all
+	///   pushers with an entry if no user is given
+	PusherHealth {
+		user_id: Option<OwnedUserId>,
+	},
+
+	/// - Delivery counters since startup and the number of destinations and
+	///   pushers currently backing off
+	HealthMetrics,
 }
 
 /// All the getters and iterators in key_value/sending.rs
@@ -236,5 +252,83 @@ pub(super) async fn process(subcommand: SendingCommand, context: &Context<'_>) -
 				))
 				.await
 		},
+		| SendingCommand::DestinationHealth { server_name: Some(server_name) } => {
+			let timer = tokio::time::Instant::now();
+			let results = services
+				.sending
+				.destination_health(&server_name)
+				.await;
+			let query_time = timer.elapsed();
+
+			context
+				.write_str(&format!(
+					"Query completed in {query_time:?}:\n\n```rs\n{results:#?}\n```"
+				))
+				.await
+		},
+		| SendingCommand::DestinationHealth { server_name: None } => {
+			let timer = tokio::time::Instant::now();
+			let results: Vec<_> = services
+				.sending
+				.destinations_health()
+				.map(|(server_name, health)| (server_name.to_owned(), health))
+				.collect()
+				.await;
+			let query_time = timer.elapsed();
+
+			context
+				.write_str(&format!(
+					"Query completed in {query_time:?}:\n\n```rs\n{results:#?}\n```"
+				))
+				.await
+		},
+		| SendingCommand::PusherHealth { user_id } => {
+			let timer = tokio::time::Instant::now();
+			let results: Vec<_> = services
+				.sending
+				.pushers_health()
+				.ready_filter(|((pusher_user, _), _)| {
+					user_id
+						.as_deref()
+						.is_none_or(|user_id| user_id == *pusher_user)
+				})
+				.map(|((user_id, pushkey), health)| {
+					(user_id.to_owned(), pushkey.to_owned(), health)
+				})
+				.collect()
+				.await;
+			let query_time = timer.elapsed();
+
+			context
+				.write_str(&format!(
+					"Query completed in {query_time:?}:\n\n```rs\n{results:#?}\n```"
+				))
+				.await
+		},
+		| SendingCommand::HealthMetrics => {
+			let timer = tokio::time::Instant::now();
+			let destinations_backing_off = services
+				.sending
+				.destinations_health()
+				.ready_filter(|(_, health)| health.is_backing_off())
+				.count()
+				.await;
+			let pushers_backing_off = services
+				.sending
+				.pushers_health()
+				.ready_filter(|(_, health)| health.is_backing_off())
+				.count()
+				.await;
+			let query_time = timer.elapsed();
+
+			let metrics = &services.sending.metrics;
+			context
+				.write_str(&format!(
+					"Query completed in \
+					 {query_time:?}:\n\n```rs\n{metrics:#?}\n```\n\nDestinations backing off: \
+					 {destinations_backing_off}\nPushers backing off: {pushers_backing_off}"
+				))
+				.await
+		},
 	}
 }
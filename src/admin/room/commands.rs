@@ -1,5 +1,5 @@
 use futures::StreamExt;
-use ruma::OwnedRoomId;
+use ruma::{MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomId, UInt};
 use tuwunel_core::{Err, Result};
 
 use crate::{PAGE_SIZE, admin_command, get_room_info};
@@ -84,3 +84,87 @@ pub(super) async fn delete_room(&self, room_id: OwnedRoomId, force: bool) -> Res
 
 	Ok(())
 }
+
+#[admin_command]
+pub(super) async fn purge_history(
+	&self,
+	room_id: OwnedRoomId,
+	before_event: Option<OwnedEventId>,
+	before_ts: Option<u64>,
+) -> Result {
+	let until = match (before_event, before_ts) {
+		| (Some(event_id), None) =>
+			self.services
+				.timeline
+				.get_pdu_count_in_room(&room_id, &event_id)
+				.await?,
+		| (None, Some(ts)) => {
+			let ts = MilliSecondsSinceUnixEpoch(UInt::try_from(ts)?);
+			self.services
+				.timeline
+				.first_count_since(&room_id, ts)
+				.await
+		},
+		| _ => return Err!("Specify exactly one of --before-event or --before-ts."),
+	};
+
+	let state_lock = self.services.state.mutex.lock(&room_id).await;
+
+	let purged = self
+		.services
+		.delete
+		.purge_history(&room_id, until, &state_lock)
+		.await?;
+
+	self.write_str(&format!("Purged {purged} events from the history of {room_id}."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn backfill(&self, room_id: OwnedRoomId) -> Result {
+	if !self.services.metadata.exists(&room_id).await {
+		return Err!("Room does not exist in the database.");
+	}
+
+	let stats = self
+		.services
+		.backfill
+		.backfill_room(&room_id)
+		.await?;
+
+	self.write_str(&format!(
+		"Backfilled {room_id}: {} gaps found, {} events fetched with {} requests.",
+		stats.gaps, stats.fetched, stats.requests
+	))
+	.await
+}
+
+#[admin_command]
+pub(super) async fn list_partial_state(&self) -> Result {
+	let rooms: Vec<_> = self
+		.services
+		.partial_state
+		.partial_rooms()
+		.map(|(room_id, partial)| {
+			format!(
+				"{room_id}\tjoined at {} via {} servers",
+				partial.event_id,
+				partial.servers.len()
+			)
+		})
+		.collect()
+		.await;
+
+	if rooms.is_empty() {
+		return self
+			.write_str("No rooms are awaiting their full state.")
+			.await;
+	}
+
+	self.write_str(&format!(
+		"Rooms with partial state ({}):\n```\n{}\n```",
+		rooms.len(),
+		rooms.join("\n")
+	))
+	.await
+}
@@ -5,7 +5,7 @@
 mod moderation;
 
 use clap::Subcommand;
-use ruma::OwnedRoomId;
+use ruma::{OwnedEventId, OwnedRoomId};
 use tuwunel_core::Result;
 
 use self::{
@@ -64,4 +64,35 @@ pub(super) enum RoomCommand {
 		#[arg(short, long)]
 		force: bool,
 	},
+
+	/// - Purge the history of a room preceding an event or a timestamp
+	///
+	/// Non-state events are removed from the database. The room's state and
+	/// forward extremities are kept so the room continues to function.
+	PurgeHistory {
+		room_id: OwnedRoomId,
+
+		/// Purge the events preceding this event
+		#[arg(long)]
+		before_event: Option<OwnedEventId>,
+
+		/// Purge the events sent before this timestamp, in milliseconds since
+		/// the unix epoch
+		#[arg(long)]
+		before_ts: Option<u64>,
+	},
+
+	/// - Backfill the entire history of a room
+	///
+	/// Events missing anywhere in the room's timeline are fetched from other
+	/// servers in the room and the timeline is extended back to the room's
+	/// creation. A room whose history was purged is again included in
+	/// automatic backfill.
+	Backfill {
+		room_id: OwnedRoomId,
+	},
+
+	/// - List rooms joined with partial state whose full state is still being
+	///   fetched
+	ListPartialState,
 }
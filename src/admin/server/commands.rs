@@ -84,6 +84,58 @@ pub(super) async fn clear_caches(&self) -> Result {
 	self.write_str("Done.").await
 }
 
+#[admin_command]
+pub(super) async fn list_signing_keys(&self) -> Result {
+	let (active_key_id, active_key) = self.services.server_keys.active_verify_key();
+	let mut out = format!("Active:\n- {active_key_id} {}\n", active_key.key.encode());
+
+	let old_verify_keys = self.services.server_keys.old_verify_keys();
+	if !old_verify_keys.is_empty() {
+		out.push_str("\nRetired:\n");
+		for (key_id, old) in old_verify_keys {
+			writeln!(out, "- {key_id} {} (expired {:?})", old.key.encode(), old.expired_ts)?;
+		}
+	}
+
+	self.write_str(&out).await
+}
+
+#[admin_command]
+pub(super) async fn rotate_signing_key(&self) -> Result {
+	let key_id = self.services.server_keys.rotate_keypair()?;
+
+	self.write_str(&format!("Generated and activated new signing key {key_id}."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn import_signing_key(&self, path: PathBuf) -> Result {
+	let key_id = self
+		.services
+		.server_keys
+		.import_keypair(&path)
+		.await?;
+
+	self.write_str(&format!("Imported and activated signing key {key_id}."))
+		.await
+}
+
+#[admin_command]
+pub(super) async fn collect_state_garbage(&self, dry_run: bool) -> Result {
+	let stats = self
+		.services
+		.state_compressor
+		.collect_garbage(dry_run)
+		.await?;
+
+	let verb = if dry_run { "Would delete" } else { "Deleted" };
+	self.write_str(&format!(
+		"{verb} {} of {} states ({} referenced).",
+		stats.deleted, stats.states, stats.referenced,
+	))
+	.await
+}
+
 #[admin_command]
 pub(super) async fn list_backups(&self) -> Result {
 	self.services
@@ -46,6 +46,29 @@ pub(super) enum ServerCommand {
 	/// - List database backups
 	ListBackups,
 
+	/// - List this server's active and retired signing keys
+	ListSigningKeys,
+
+	/// - Generate a new signing key and make it active
+	///
+	/// The previously active key remains published in `old_verify_keys`.
+	RotateSigningKey,
+
+	/// - Import a Synapse-format signing key file and make it active
+	///
+	/// The previously active key remains published in `old_verify_keys`.
+	ImportSigningKey {
+		/// Path to the signing key file
+		path: PathBuf,
+	},
+
+	/// - Delete unreferenced room states
+	CollectStateGarbage {
+		/// Only report what would be deleted
+		#[arg(long)]
+		dry_run: bool,
+	},
+
 	/// - Send a message to the admin room.
 	AdminNotice {
 		message: Vec<String>,
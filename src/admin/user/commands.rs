@@ -250,6 +250,50 @@ pub(super) async fn reset_password(&self, username: String, password: Option<Str
 	.await
 }
 
+#[admin_command]
+pub(super) async fn allow_cross_signing_reset(&self, user_id: String) -> Result {
+	let user_id = parse_active_local_user_id(self.services, &user_id).await?;
+
+	self.services
+		.users
+		.allow_cross_signing_reset(&user_id);
+
+	let window = self
+		.services
+		.server
+		.config
+		.cross_signing_reset_window;
+
+	write!(
+		self,
+		"{user_id} can replace their cross-signing keys without authentication for the next \
+		 {window} seconds."
+	)
+	.await
+}
+
+#[admin_command]
+pub(super) async fn approve_cross_signing_reset(&self, code: String) -> Result {
+	let user_id = self
+		.services
+		.users
+		.approve_cross_signing_reset(&code)
+		.await?;
+
+	let window = self
+		.services
+		.server
+		.config
+		.cross_signing_reset_window;
+
+	write!(
+		self,
+		"{user_id} can replace their cross-signing keys without authentication for the next \
+		 {window} seconds."
+	)
+	.await
+}
+
 #[admin_command]
 pub(super) async fn deactivate_all(&self, no_leave_rooms: bool, force: bool) -> Result {
 	if self.body.len() < 2
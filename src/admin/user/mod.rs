@@ -26,6 +26,25 @@ pub(super) enum UserCommand {
 		password: Option<String>,
 	},
 
+	/// - Allow a user to replace their cross-signing keys without
+	///   authentication
+	///
+	/// For users who cannot authenticate with a password, e.g. after losing
+	/// their recovery key. The approval lasts `cross_signing_reset_window`
+	/// seconds.
+	AllowCrossSigningReset {
+		user_id: String,
+	},
+
+	/// - Approve a cross-signing reset requested by a user
+	///
+	/// Users without a password are given a code to request replacing their
+	/// cross-signing keys. Approving the code allows the user who requested it
+	/// to replace their keys for `cross_signing_reset_window` seconds.
+	ApproveCrossSigningReset {
+		code: String,
+	},
+
 	/// - Deactivate a user
 	///
 	/// User will be removed from all rooms by default.
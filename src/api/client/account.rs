@@ -1,14 +1,26 @@
-use axum::extract::State;
+use axum::{
+	extract::{Path, State},
+	response::{Html, IntoResponse},
+};
 use axum_client_ip::InsecureClientIp;
 use futures::{FutureExt, StreamExt};
-use ruma::api::client::account::{
-	ThirdPartyIdRemovalStatus, change_password, deactivate, get_3pids,
-	request_3pid_management_token_via_email, request_3pid_management_token_via_msisdn, whoami,
+use ruma::{
+	api::client::account::{
+		ThirdPartyIdRemovalStatus, add_3pid, change_password, deactivate, delete_3pid, get_3pids,
+		request_3pid_management_token_via_email, request_3pid_management_token_via_msisdn,
+		whoami,
+	},
+	thirdparty::Medium,
 };
 use tuwunel_core::{Err, Result, info, utils::ReadyExt};
 
 use crate::{Ruma, router::auth_uiaa};
 
+const EMAIL_VALIDATION_PAGE: &str = "<!DOCTYPE html><html><head><title>Confirm email \
+                                     address</title></head><body><p>Add this email address to \
+                                     your account?</p><form method=\"post\"><button \
+                                     type=\"submit\">Confirm</button></form></body></html>";
+
 /// # `POST /_matrix/client/r0/account/password`
 ///
 /// Changes the password of this account.
@@ -122,16 +134,72 @@ pub(crate) async fn deactivate_route(
 ///
 /// Get a list of third party identifiers associated with this account.
 ///
-/// - Currently always returns empty list
+/// - Only validated email addresses are supported
 pub(crate) async fn third_party_route(
+	State(services): State<crate::State>,
 	body: Ruma<get_3pids::v3::Request>,
 ) -> Result<get_3pids::v3::Response> {
-	let _sender_user = body
-		.sender_user
-		.as_ref()
-		.expect("user is authenticated");
+	let threepids = services.users.threepids(body.sender_user()).await;
+
+	Ok(get_3pids::v3::Response::new(threepids))
+}
+
+/// # `POST /_matrix/client/v3/account/3pid/add`
+///
+/// Adds an email address validated through the link emailed by
+/// `requestToken` to this account.
+///
+/// - Requires UIAA to verify user password
+pub(crate) async fn add_3pid_route(
+	State(services): State<crate::State>,
+	body: Ruma<add_3pid::v3::Request>,
+) -> Result<add_3pid::v3::Response> {
+	let ref sender_user = auth_uiaa(&services, &body).await?;
+
+	services
+		.users
+		.add_threepid(sender_user, &body.client_secret, &body.sid)
+		.await?;
+
+	info!("User {sender_user} added an email address.");
+
+	Ok(add_3pid::v3::Response {})
+}
+
+/// # `POST /_matrix/client/v3/account/3pid/delete`
+///
+/// Removes an email address from this account, along with any email pusher
+/// sending notifications to it.
+pub(crate) async fn delete_3pid_route(
+	State(services): State<crate::State>,
+	body: Ruma<delete_3pid::v3::Request>,
+) -> Result<delete_3pid::v3::Response> {
+	let sender_user = body.sender_user();
+
+	if body.medium != Medium::Email {
+		return Err!(Request(ThreepidNotFound("Only email addresses are supported.")));
+	}
+
+	services
+		.users
+		.remove_threepid(sender_user, &body.address)
+		.await?;
 
-	Ok(get_3pids::v3::Response::new(Vec::new()))
+	if services
+		.pusher
+		.get_pusher(sender_user, &body.address)
+		.await
+		.is_ok()
+	{
+		services
+			.pusher
+			.delete_pusher(sender_user, &body.address)
+			.await;
+	}
+
+	Ok(delete_3pid::v3::Response {
+		id_server_unbind_result: ThirdPartyIdRemovalStatus::NoSupport,
+	})
 }
 
 /// # `POST /_matrix/client/v3/account/3pid/email/requestToken`
@@ -139,12 +207,45 @@ pub(crate) async fn third_party_route(
 /// "This API should be used to request validation tokens when adding an email
 /// address to an account"
 ///
-/// - 403 signals that The homeserver does not allow the third party identifier
-///   as a contact option.
+/// - Emails a validation link through the configured SMTP relay
+/// - Emails are rate limited per address, per client and for the server
+/// - 403 signals that the homeserver does not send email.
+#[tracing::instrument(skip_all, fields(%client), name = "request_email_token")]
 pub(crate) async fn request_3pid_management_token_via_email_route(
-	_body: Ruma<request_3pid_management_token_via_email::v3::Request>,
+	State(services): State<crate::State>,
+	InsecureClientIp(client): InsecureClientIp,
+	body: Ruma<request_3pid_management_token_via_email::v3::Request>,
 ) -> Result<request_3pid_management_token_via_email::v3::Response> {
-	Err!(Request(ThreepidDenied("Third party identifiers are not implemented")))
+	let sid = services
+		.users
+		.request_email_validation(&body.client_secret, &body.email, body.send_attempt, client)
+		.await?;
+
+	Ok(request_3pid_management_token_via_email::v3::Response::new(sid))
+}
+
+/// # `GET /_tuwunel/email/validate/{sid}/{token}`
+///
+/// Page linked from validation emails asking the user to confirm the address.
+/// Nothing is changed by merely following the link, which mail scanners do.
+pub(crate) async fn email_validation_page_route() -> impl IntoResponse {
+	Html(EMAIL_VALIDATION_PAGE)
+}
+
+/// # `POST /_tuwunel/email/validate/{sid}/{token}`
+///
+/// Confirms the email address of a `requestToken` session, after which the
+/// client can add it to the account.
+pub(crate) async fn validate_email_route(
+	State(services): State<crate::State>,
+	Path((sid, token)): Path<(String, String)>,
+) -> Result<impl IntoResponse> {
+	services
+		.users
+		.validate_email(&sid, &token)
+		.await?;
+
+	Ok("Your email address is confirmed. You can return to your client to finish adding it.")
 }
 
 /// # `POST /_matrix/client/v3/account/3pid/msisdn/requestToken`
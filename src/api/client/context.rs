@@ -16,7 +16,10 @@
 
 use crate::{
 	Ruma,
-	client::message::{event_filter, ignored_filter, lazy_loading_witness, visibility_filter},
+	client::message::{
+		bundle_aggregations, event_filter, ignored_filter, lazy_loading_witness,
+		visibility_filter,
+	},
 };
 
 const LIMIT_MAX: usize = 100;
@@ -91,6 +94,7 @@ pub(crate) async fn get_context_route(
 		.wide_filter_map(|item| ignored_filter(&services, item, sender_user))
 		.wide_filter_map(|item| visibility_filter(&services, item, sender_user))
 		.take(limit / 2)
+		.wide_then(|item| bundle_aggregations(&services, item, sender_user))
 		.collect();
 
 	let events_after = services
@@ -101,6 +105,7 @@ pub(crate) async fn get_context_route(
 		.wide_filter_map(|item| ignored_filter(&services, item, sender_user))
 		.wide_filter_map(|item| visibility_filter(&services, item, sender_user))
 		.take(limit.div_ceil(2))
+		.wide_then(|item| bundle_aggregations(&services, item, sender_user))
 		.collect();
 
 	let (base_event, events_before, events_after): (_, Vec<_>, Vec<_>) =
@@ -108,6 +113,12 @@ pub(crate) async fn get_context_route(
 			.boxed()
 			.await;
 
+	let base_event: OptionFuture<_> = base_event
+		.map(|item| bundle_aggregations(&services, item, sender_user))
+		.into();
+
+	let base_event = base_event.await;
+
 	let lazy_loading_context = lazy_loading::Context {
 		user_id: sender_user,
 		device_id: Some(sender_device),
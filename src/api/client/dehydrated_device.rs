@@ -0,0 +1,157 @@
+use axum::extract::State;
+use futures::StreamExt;
+use ruma::api::client::dehydrated_device::{
+	delete_dehydrated_device, get_dehydrated_device, get_events, put_dehydrated_device,
+};
+use tuwunel_core::{Err, Result, debug_warn, err};
+
+use crate::Ruma;
+
+/// # `PUT /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Replaces the dehydrated device of the sender, uploading its keys.
+pub(crate) async fn put_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<put_dehydrated_device::unstable::Request>,
+) -> Result<put_dehydrated_device::unstable::Response> {
+	let sender_user = body.sender_user();
+	let device_id = &body.device_id;
+
+	let device_keys = body.device_keys.deserialize().map_err(|e| {
+		err!(Request(BadJson("Invalid device keys JSON uploaded by client: {e}")))
+	})?;
+
+	if device_keys.user_id != sender_user || device_keys.device_id != *device_id {
+		return Err!(Request(Unknown("Device keys do not belong to the dehydrated device.")));
+	}
+
+	services
+		.users
+		.set_dehydrated_device(
+			sender_user,
+			device_id,
+			body.initial_device_display_name.clone(),
+			body.device_data.clone(),
+		)
+		.await?;
+
+	services
+		.users
+		.add_device_keys(sender_user, device_id, &body.device_keys)
+		.await;
+
+	for (key_id, one_time_key) in body
+		.one_time_keys
+		.iter()
+		.take(services.config.one_time_key_limit)
+	{
+		if one_time_key.deserialize().is_err() {
+			debug_warn!(?key_id, "Invalid one time key JSON for dehydrated device, skipping");
+			continue;
+		}
+
+		services
+			.users
+			.add_one_time_key(sender_user, device_id, key_id, one_time_key)
+			.await?;
+	}
+
+	for (key_id, fallback_key) in &body.fallback_keys {
+		if fallback_key.deserialize().is_err() {
+			debug_warn!(?key_id, "Invalid fallback key JSON for dehydrated device, skipping");
+			continue;
+		}
+
+		services
+			.users
+			.add_fallback_key(sender_user, device_id, key_id, fallback_key)
+			.await;
+	}
+
+	Ok(put_dehydrated_device::unstable::Response { device_id: device_id.clone() })
+}
+
+/// # `GET /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Returns the dehydrated device of the sender.
+pub(crate) async fn get_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_dehydrated_device::unstable::Request>,
+) -> Result<get_dehydrated_device::unstable::Response> {
+	let dehydrated = services
+		.users
+		.dehydrated_device(body.sender_user())
+		.await
+		.map_err(|_| err!(Request(NotFound("No dehydrated device found."))))?;
+
+	Ok(get_dehydrated_device::unstable::Response {
+		device_id: dehydrated.device_id,
+		device_data: dehydrated.device_data,
+	})
+}
+
+/// # `DELETE /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device`
+///
+/// Removes the dehydrated device of the sender along with its keys and
+/// pending to-device events.
+pub(crate) async fn delete_dehydrated_device_route(
+	State(services): State<crate::State>,
+	body: Ruma<delete_dehydrated_device::unstable::Request>,
+) -> Result<delete_dehydrated_device::unstable::Response> {
+	let device_id = services
+		.users
+		.remove_dehydrated_device(body.sender_user())
+		.await
+		.map_err(|_| err!(Request(NotFound("No dehydrated device found."))))?;
+
+	Ok(delete_dehydrated_device::unstable::Response { device_id })
+}
+
+/// # `POST /_matrix/client/unstable/org.matrix.msc3814.v1/dehydrated_device/{deviceId}/events`
+///
+/// Paginates the to-device events sent to the dehydrated device of the
+/// sender. Events before `next_batch` are removed, having been received by
+/// the client.
+pub(crate) async fn get_dehydrated_events_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_events::unstable::Request>,
+) -> Result<get_events::unstable::Response> {
+	let sender_user = body.sender_user();
+	let device_id = &body.device_id;
+
+	let dehydrated = services
+		.users
+		.dehydrated_device(sender_user)
+		.await
+		.map_err(|_| err!(Request(NotFound("No dehydrated device found."))))?;
+
+	if dehydrated.device_id != *device_id {
+		return Err!(Request(Forbidden("Device is not the dehydrated device of this user.")));
+	}
+
+	let since: Option<u64> = body
+		.next_batch
+		.as_deref()
+		.map(str::parse)
+		.transpose()
+		.map_err(|_| err!(Request(InvalidParam("Invalid next_batch token."))))?;
+
+	if since.is_some() {
+		services
+			.users
+			.remove_to_device_events(sender_user, device_id, since)
+			.await;
+	}
+
+	let next_batch = services.globals.current_count();
+	let events = services
+		.users
+		.get_to_device_events(sender_user, device_id, since, Some(next_batch))
+		.collect()
+		.await;
+
+	Ok(get_events::unstable::Response {
+		events,
+		next_batch: Some(next_batch.to_string()),
+	})
+}
@@ -1,6 +1,10 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
 
-use axum::extract::State;
+use axum::{
+	Form,
+	extract::{Path, State},
+	response::{Html, IntoResponse},
+};
 use futures::{StreamExt, stream::FuturesUnordered};
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, OneTimeKeyAlgorithm, OwnedDeviceId, OwnedUserId,
@@ -16,19 +20,44 @@
 	encryption::CrossSigningKey,
 	serde::Raw,
 };
+use serde::Deserialize;
 use serde_json::json;
 use tuwunel_core::{
 	Err, Result, debug, debug_error, debug_warn, err, result::NotFound, utils::json,
 };
 use tuwunel_service::{Services, users::parse_master_key};
 
-use crate::{Ruma, router::auth_uiaa};
+use crate::{Ruma, client::ldap_verify, router::auth_uiaa_cross_signing_reset};
+
+/// Shows the code a server admin approves the reset with.
+const CROSS_SIGNING_RESET_PAGE: &str =
+	"<!DOCTYPE html><html><head><title>Reset cross-signing keys</title></head><body><p>Your \
+	 client is asking to replace your cross-signing keys. Other users and your other sessions \
+	 will have to verify you again.</p><p>An administrator of this server has to approve it. \
+	 Give them this code:</p><pre>{token}</pre></body></html>";
+
+/// Asks LDAP users for their password to confirm the reset, with the code for
+/// an admin as an alternative.
+const CROSS_SIGNING_RESET_CONFIRM_PAGE: &str =
+	"<!DOCTYPE html><html><head><title>Reset cross-signing keys</title></head><body><p>Your \
+	 client is asking to replace your cross-signing keys. Other users and your other sessions \
+	 will have to verify you again.</p><form method=\"post\"><label>Enter your password to \
+	 confirm it was you: <input type=\"password\" name=\"password\" \
+	 autocomplete=\"current-password\" required></label> <button \
+	 type=\"submit\">Confirm</button></form><p>An administrator of this server can also approve \
+	 it with this code:</p><pre>{token}</pre></body></html>";
+
+#[derive(Deserialize)]
+pub(crate) struct CrossSigningResetConfirmation {
+	password: String,
+}
 
 /// # `POST /_matrix/client/r0/keys/upload`
 ///
 /// Publish end-to-end encryption keys for the sender device.
 ///
 /// - Adds one time keys
+/// - Replaces the fallback key of each algorithm uploaded
 /// - If there are no device keys yet: Adds device keys (TODO: merge with
 ///   existing keys?)
 pub(crate) async fn upload_keys_route(
@@ -62,6 +91,27 @@ pub(crate) async fn upload_keys_route(
 			.await?;
 	}
 
+	for (key_id, fallback_key) in &body.fallback_keys {
+		if fallback_key
+			.deserialize()
+			.inspect_err(|e| {
+				debug_warn!(
+					?key_id,
+					?fallback_key,
+					"Invalid fallback key JSON submitted by client, skipping: {e}"
+				);
+			})
+			.is_err()
+		{
+			continue;
+		}
+
+		services
+			.users
+			.add_fallback_key(sender_user, sender_device, key_id, fallback_key)
+			.await;
+	}
+
 	if let Some(device_keys) = &body.device_keys {
 		let deser_device_keys = device_keys.deserialize().map_err(|e| {
 			err!(Request(BadJson(debug_warn!(
@@ -181,8 +231,15 @@ pub(crate) async fn upload_signing_keys_route(
 			// Some of the keys weren't found, so we let them upload
 			debug!("Skipping UIA in accordance with MSC3967, user had no existing keys");
 		},
+		| _ if services
+			.users
+			.cross_signing_reset_allowed(body.sender_user())
+			.await =>
+		{
+			debug!("Skipping UIA, the user approved replacing their cross-signing keys");
+		},
 		| _ => {
-			let authed_user = auth_uiaa(&services, &body).await?;
+			let authed_user = auth_uiaa_cross_signing_reset(&services, &body).await?;
 			assert_eq!(
 				body.sender_user(),
 				authed_user,
@@ -203,9 +260,95 @@ pub(crate) async fn upload_signing_keys_route(
 		)
 		.await?;
 
+	// an approved reset replaces the keys once
+	services
+		.users
+		.clear_cross_signing_reset(body.sender_user());
+
 	Ok(upload_signing_keys::v3::Response {})
 }
 
+/// # `GET /_tuwunel/cross_signing/reset/{token}`
+///
+/// Page linked from the `m.oauth` stage of `upload_signing_keys_route`. LDAP
+/// users confirm replacing their cross-signing keys with their password; the
+/// page also shows the code a server admin can approve it with.
+pub(crate) async fn cross_signing_reset_page_route(
+	State(services): State<crate::State>,
+	Path(token): Path<String>,
+) -> Result<impl IntoResponse> {
+	let Some(user_id) = services
+		.users
+		.cross_signing_reset_user(&token)
+		.await
+	else {
+		return Err!(Request(NotFound("Unknown or expired cross-signing reset link.")));
+	};
+
+	let page = if can_confirm_cross_signing_reset(&services, &user_id).await {
+		CROSS_SIGNING_RESET_CONFIRM_PAGE
+	} else {
+		CROSS_SIGNING_RESET_PAGE
+	};
+
+	Ok(Html(page.replace("{token}", &token)))
+}
+
+/// # `POST /_tuwunel/cross_signing/reset/{token}`
+///
+/// Approves replacing the cross-signing keys once the user who requested it
+/// confirms with their LDAP password. A wrong password removes the link, so
+/// the reset has to be requested again from the client.
+pub(crate) async fn confirm_cross_signing_reset_route(
+	State(services): State<crate::State>,
+	Path(token): Path<String>,
+	Form(form): Form<CrossSigningResetConfirmation>,
+) -> Result<impl IntoResponse> {
+	let Some(user_id) = services
+		.users
+		.cross_signing_reset_user(&token)
+		.await
+	else {
+		return Err!(Request(NotFound("Unknown or expired cross-signing reset link.")));
+	};
+
+	if !can_confirm_cross_signing_reset(&services, &user_id).await {
+		return Err!(Request(Forbidden(
+			"An administrator of this server has to approve this reset."
+		)));
+	}
+
+	if let Err(e) = ldap_verify(&services, &user_id, &form.password).await {
+		debug_warn!(%user_id, "Failed to confirm cross-signing reset: {e}");
+		services
+			.users
+			.remove_cross_signing_reset_link(&token);
+
+		return Err!(Request(Forbidden(
+			"Wrong password. Start replacing your keys again from your client."
+		)));
+	}
+
+	services
+		.users
+		.approve_cross_signing_reset(&token)
+		.await?;
+
+	Ok("Replacing your cross-signing keys is approved. You can return to your client to finish.")
+}
+
+/// Whether the user can confirm a cross-signing reset themselves, which needs
+/// a login separate from the access token that requested it.
+async fn can_confirm_cross_signing_reset(services: &Services, user_id: &UserId) -> bool {
+	cfg!(feature = "ldap")
+		&& services.config.ldap.enable
+		&& services
+			.users
+			.origin(user_id)
+			.await
+			.is_ok_and(|origin| origin == "ldap")
+}
+
 async fn check_for_new_keys(
 	services: crate::State,
 	user_id: &UserId,
@@ -25,8 +25,24 @@ pub(crate) async fn invite_user_route(
 	banned_room_check(&services, sender_user, Some(room_id), room_id.server_name(), client)
 		.await?;
 
-	let invite_user::v3::InvitationRecipient::UserId { user_id } = &body.recipient else {
-		return Err!(Request(ThreepidDenied("Third party identifiers are not implemented")));
+	let user_id = match &body.recipient {
+		| invite_user::v3::InvitationRecipient::UserId { user_id } => user_id,
+		| invite_user::v3::InvitationRecipient::ThirdPartyId(invite_3pid) => {
+			services
+				.membership
+				.invite_third_party(
+					sender_user,
+					room_id,
+					&invite_3pid.id_server,
+					&invite_3pid.id_access_token,
+					&invite_3pid.medium,
+					&invite_3pid.address,
+				)
+				.boxed()
+				.await?;
+
+			return Ok(invite_user::v3::Response {});
+		},
 	};
 
 	let sender_ignored_recipient = services
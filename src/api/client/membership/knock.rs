@@ -472,6 +472,7 @@ async fn knock_room_helper_remote(
 		.await;
 
 	debug!("Saving compressed state");
+	let gc_guard = services.state_compressor.gc_guard().await;
 	let HashSetCompressStateEvent {
 		shortstatehash: statehash_before_knock,
 		added,
@@ -534,6 +535,8 @@ async fn knock_room_helper_remote(
 		.state
 		.set_room_state(room_id, statehash_after_knock, &state_lock);
 
+	drop(gc_guard);
+
 	Ok(())
 }
 
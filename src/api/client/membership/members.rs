@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use axum::extract::State;
 use futures::{FutureExt, StreamExt, pin_mut};
 use ruma::{
@@ -24,6 +26,10 @@
 
 use crate::Ruma;
 
+/// Time a member list request waits for the full state of a room joined with
+/// partial state before answering from the partial state.
+const PARTIAL_STATE_WAIT: Duration = Duration::from_secs(30);
+
 /// # `POST /_matrix/client/r0/rooms/{roomId}/members`
 ///
 /// Lists all joined users in a room (TODO: at a specific point in time, with a
@@ -44,6 +50,11 @@ pub(crate) async fn get_member_events_route(
 		)));
 	}
 
+	services
+		.partial_state
+		.wait_full_state(&body.room_id, PARTIAL_STATE_WAIT)
+		.await;
+
 	let membership = body.membership.as_ref();
 	let not_membership = body.not_membership.as_ref();
 	Ok(get_member_events::v3::Response {
@@ -87,6 +98,11 @@ pub(crate) async fn joined_members_route(
 		return Err!(Request(Forbidden("You aren't a member of the room.")));
 	}
 
+	services
+		.partial_state
+		.wait_full_state(&body.room_id, PARTIAL_STATE_WAIT)
+		.await;
+
 	Ok(joined_members::v3::Response {
 		joined: services
 			.state_accessor
@@ -124,6 +124,7 @@ pub(crate) async fn get_message_events_route(
 		.wide_filter_map(|item| ignored_filter(&services, item, sender_user))
 		.wide_filter_map(|item| visibility_filter(&services, item, sender_user))
 		.take(limit)
+		.wide_then(|item| bundle_aggregations(&services, item, sender_user))
 		.collect()
 		.await;
 
@@ -294,6 +295,23 @@ pub(crate) async fn visibility_filter(
 		.then_some(item)
 }
 
+/// Adds the bundled aggregations of the relations to the event.
+#[inline]
+pub(crate) async fn bundle_aggregations(
+	services: &Services,
+	(count, mut pdu): PdusIterItem,
+	user_id: &UserId,
+) -> PdusIterItem {
+	services
+		.pdu_metadata
+		.add_bundled_aggregations(user_id, count, &mut pdu)
+		.await
+		.log_err()
+		.ok();
+
+	(count, pdu)
+}
+
 #[inline]
 pub(crate) fn event_filter(item: PdusIterItem, filter: &RoomEventFilter) -> Option<PdusIterItem> {
 	let (_, pdu) = &item;
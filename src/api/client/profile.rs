@@ -7,11 +7,8 @@
 };
 use ruma::{
 	OwnedRoomId,
-	api::{
-		client::profile::{
-			get_avatar_url, get_display_name, get_profile, set_avatar_url, set_display_name,
-		},
-		federation,
+	api::client::profile::{
+		get_avatar_url, get_display_name, get_profile, set_avatar_url, set_display_name,
 	},
 	presence::PresenceState,
 };
@@ -59,43 +56,19 @@ pub(crate) async fn set_displayname_route(
 ///
 /// Returns the displayname of the user.
 ///
-/// - If user is on another server and we do not have a local copy already fetch
-///   displayname over federation
+/// - If user is on another server, answer from the remote profile cache,
+///   fetching the profile over federation when it is stale
 pub(crate) async fn get_displayname_route(
 	State(services): State<crate::State>,
 	body: Ruma<get_display_name::v3::Request>,
 ) -> Result<get_display_name::v3::Response> {
 	if !services.globals.user_is_local(&body.user_id) {
-		// Create and update our local copy of the user
-		if let Ok(response) = services
-			.sending
-			.send_federation_request(
-				body.user_id.server_name(),
-				federation::query::get_profile_information::v1::Request {
-					user_id: body.user_id.clone(),
-					field: None, // we want the full user's profile to update locally too
-				},
-			)
+		if let Ok(profile) = services
+			.remote_profile
+			.profile(&body.user_id)
 			.await
 		{
-			if !services.users.exists(&body.user_id).await {
-				services
-					.users
-					.create(&body.user_id, None, None)
-					.await?;
-			}
-
-			services
-				.users
-				.set_displayname(&body.user_id, response.displayname.clone());
-			services
-				.users
-				.set_avatar_url(&body.user_id, response.avatar_url.clone());
-			services
-				.users
-				.set_blurhash(&body.user_id, response.blurhash.clone());
-
-			return Ok(get_display_name::v3::Response { displayname: response.displayname });
+			return Ok(get_display_name::v3::Response { displayname: profile.displayname });
 		}
 	}
 
@@ -160,45 +133,21 @@ pub(crate) async fn set_avatar_url_route(
 ///
 /// Returns the `avatar_url` and `blurhash` of the user.
 ///
-/// - If user is on another server and we do not have a local copy already fetch
-///   `avatar_url` and blurhash over federation
+/// - If user is on another server, answer from the remote profile cache,
+///   fetching the profile over federation when it is stale
 pub(crate) async fn get_avatar_url_route(
 	State(services): State<crate::State>,
 	body: Ruma<get_avatar_url::v3::Request>,
 ) -> Result<get_avatar_url::v3::Response> {
 	if !services.globals.user_is_local(&body.user_id) {
-		// Create and update our local copy of the user
-		if let Ok(response) = services
-			.sending
-			.send_federation_request(
-				body.user_id.server_name(),
-				federation::query::get_profile_information::v1::Request {
-					user_id: body.user_id.clone(),
-					field: None, // we want the full user's profile to update locally as well
-				},
-			)
+		if let Ok(profile) = services
+			.remote_profile
+			.profile(&body.user_id)
 			.await
 		{
-			if !services.users.exists(&body.user_id).await {
-				services
-					.users
-					.create(&body.user_id, None, None)
-					.await?;
-			}
-
-			services
-				.users
-				.set_displayname(&body.user_id, response.displayname.clone());
-			services
-				.users
-				.set_avatar_url(&body.user_id, response.avatar_url.clone());
-			services
-				.users
-				.set_blurhash(&body.user_id, response.blurhash.clone());
-
 			return Ok(get_avatar_url::v3::Response {
-				avatar_url: response.avatar_url,
-				blurhash: response.blurhash,
+				avatar_url: profile.avatar_url,
+				blurhash: profile.blurhash,
 			});
 		}
 	}
@@ -222,65 +171,30 @@ pub(crate) async fn get_avatar_url_route(
 ///
 /// Returns the displayname, avatar_url, blurhash, and tz of the user.
 ///
-/// - If user is on another server and we do not have a local copy already,
-///   fetch profile over federation.
+/// - If user is on another server, answer from the remote profile cache,
+///   fetching the profile over federation when it is stale
 pub(crate) async fn get_profile_route(
 	State(services): State<crate::State>,
 	body: Ruma<get_profile::v3::Request>,
 ) -> Result<get_profile::v3::Response> {
 	if !services.globals.user_is_local(&body.user_id) {
-		// Create and update our local copy of the user
-		if let Ok(response) = services
-			.sending
-			.send_federation_request(
-				body.user_id.server_name(),
-				federation::query::get_profile_information::v1::Request {
-					user_id: body.user_id.clone(),
-					field: None,
-				},
-			)
+		if let Ok(profile) = services
+			.remote_profile
+			.profile(&body.user_id)
 			.await
 		{
-			if !services.users.exists(&body.user_id).await {
-				services
-					.users
-					.create(&body.user_id, None, None)
-					.await?;
-			}
-
-			services
-				.users
-				.set_displayname(&body.user_id, response.displayname.clone());
-			services
-				.users
-				.set_avatar_url(&body.user_id, response.avatar_url.clone());
-			services
-				.users
-				.set_blurhash(&body.user_id, response.blurhash.clone());
-			services
-				.users
-				.set_timezone(&body.user_id, response.tz.clone());
-
-			for (profile_key, profile_key_value) in &response.custom_profile_fields {
-				services.users.set_profile_key(
-					&body.user_id,
-					profile_key,
-					Some(profile_key_value.clone()),
-				);
-			}
-
 			let canonical_fields = [
-				("avatar_url", response.avatar_url.map(Into::into)),
-				("blurhash", response.blurhash),
-				("displayname", response.displayname),
-				("tz", response.tz),
+				("avatar_url", profile.avatar_url.map(Into::into)),
+				("blurhash", profile.blurhash),
+				("displayname", profile.displayname),
+				("tz", profile.tz),
 			];
 
 			let response = canonical_fields
 				.into_iter()
 				.filter_map(|(key, val)| val.map(|val| (key, val)))
 				.map(|(key, val)| (key.to_owned(), val.into()))
-				.chain(response.custom_profile_fields.into_iter());
+				.chain(profile.custom_profile_fields.into_iter());
 
 			return Ok(response.collect::<get_profile::v3::Response>());
 		}
@@ -1,10 +1,13 @@
-use axum::extract::State;
+use axum::{
+	extract::{Path, State},
+	response::{Html, IntoResponse},
+};
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue,
 	api::client::{
 		error::ErrorKind,
 		push::{
-			delete_pushrule, get_pushers, get_pushrule, get_pushrule_actions,
+			delete_pushrule, get_notifications, get_pushers, get_pushrule, get_pushrule_actions,
 			get_pushrule_enabled, get_pushrules_all, get_pushrules_global_scope, set_pusher,
 			set_pushrule, set_pushrule_actions, set_pushrule_enabled,
 		},
@@ -23,6 +26,14 @@
 
 use crate::Ruma;
 
+const LIMIT_MAX: usize = 100;
+const LIMIT_DEFAULT: usize = 50;
+
+const EMAIL_UNSUBSCRIBE_PAGE: &str = "<!DOCTYPE html><html><head><title>Unsubscribe</title></\
+                                      head><body><p>Stop receiving email notifications at this \
+                                      address?</p><form method=\"post\"><button \
+                                      type=\"submit\">Unsubscribe</button></form></body></html>";
+
 /// # `GET /_matrix/client/r0/pushrules/`
 ///
 /// Retrieves the push rules event for this user.
@@ -473,6 +484,43 @@ pub(crate) async fn get_pushers_route(
 	})
 }
 
+/// # `GET /_matrix/client/v3/notifications`
+///
+/// Lists the events which notified the sender user, newest first.
+///
+/// - `only=highlight` lists only the events which highlighted
+pub(crate) async fn get_notifications_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_notifications::v3::Request>,
+) -> Result<get_notifications::v3::Response> {
+	let sender_user = body.sender_user();
+
+	let from = body
+		.from
+		.as_deref()
+		.map(str::parse)
+		.transpose()
+		.map_err(|_| err!(Request(InvalidParam("Invalid from token."))))?;
+
+	let limit: usize = body
+		.limit
+		.and_then(|limit| limit.try_into().ok())
+		.unwrap_or(LIMIT_DEFAULT)
+		.min(LIMIT_MAX);
+
+	let only_highlight = body.only.as_deref() == Some("highlight");
+
+	let (notifications, next) = services
+		.pusher
+		.notifications(sender_user, from, limit, only_highlight)
+		.await;
+
+	Ok(get_notifications::v3::Response {
+		next_token: next.as_ref().map(ToString::to_string),
+		notifications,
+	})
+}
+
 /// # `POST /_matrix/client/r0/pushers/set`
 ///
 /// Adds a pusher for the sender user.
@@ -492,6 +540,29 @@ pub(crate) async fn set_pushers_route(
 	Ok(set_pusher::v3::Response::new())
 }
 
+/// # `GET /_tuwunel/email/unsubscribe/{token}`
+///
+/// Page linked from notification emails asking the user to confirm removing
+/// the email pusher. Nothing is changed by merely following the link, which
+/// mail scanners do.
+pub(crate) async fn email_unsubscribe_page_route() -> impl IntoResponse {
+	Html(EMAIL_UNSUBSCRIBE_PAGE)
+}
+
+/// # `POST /_tuwunel/email/unsubscribe/{token}`
+///
+/// Removes the email pusher a notification email with this unsubscribe link
+/// was sent to. Also serves one-click unsubscribe (RFC 8058) from mail
+/// clients.
+pub(crate) async fn email_unsubscribe_route(
+	State(services): State<crate::State>,
+	Path(token): Path<String>,
+) -> Result<impl IntoResponse> {
+	services.pusher.unsubscribe_email(&token).await?;
+
+	Ok("You will no longer receive email notifications at this address.")
+}
+
 /// user somehow has bad push rules, these must always exist per spec.
 /// so recreate it and return server default silently
 async fn recreate_push_rules_and_return(
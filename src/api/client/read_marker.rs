@@ -45,7 +45,8 @@ pub(crate) async fn set_read_marker_route(
 	if body.private_read_receipt.is_some() || body.read_receipt.is_some() {
 		services
 			.user
-			.reset_notification_counts(sender_user, &body.room_id);
+			.reset_notification_counts(sender_user, &body.room_id)
+			.await;
 	}
 
 	if let Some(event) = &body.read_receipt {
@@ -114,7 +115,8 @@ pub(crate) async fn create_receipt_route(
 	) {
 		services
 			.user
-			.reset_notification_counts(sender_user, &body.room_id);
+			.reset_thread_notification_counts(sender_user, &body.room_id, &body.thread)
+			.await;
 	}
 
 	match body.receipt_type {
@@ -148,7 +150,7 @@ pub(crate) async fn create_receipt_route(
 						sender_user.to_owned(),
 						ruma::events::receipt::Receipt {
 							ts: Some(MilliSecondsSinceUnixEpoch::now()),
-							thread: ReceiptThread::Unthreaded,
+							thread: body.thread.clone(),
 						},
 					)]),
 				)]),
@@ -12,7 +12,7 @@
 	events::{TimelineEventType, relation::RelationType},
 };
 use tuwunel_core::{
-	Result, at,
+	Err, Result, at, err,
 	matrix::{
 		event::{Event, RelationTypeEqual},
 		pdu::PduCount,
@@ -98,6 +98,127 @@ pub(crate) async fn get_relating_events_route(
 	.await
 }
 
+pub(crate) mod get_aggregations {
+	use ruma::{
+		OwnedEventId, OwnedRoomId, UInt,
+		api::{Metadata, request, response},
+		metadata,
+	};
+	use serde::{Deserialize, Serialize};
+
+	const METADATA: Metadata = metadata! {
+		method: GET,
+		rate_limited: false,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_matrix/client/unstable/rooms/{room_id}/aggregations/{event_id}",
+		}
+	};
+
+	#[request]
+	pub struct Request {
+		/// The room containing the event.
+		#[ruma_api(path)]
+		pub room_id: OwnedRoomId,
+
+		/// The event whose reactions are counted.
+		#[ruma_api(path)]
+		pub event_id: OwnedEventId,
+
+		/// The `next_batch` token of a previous response.
+		#[serde(skip_serializing_if = "Option::is_none")]
+		#[ruma_api(query)]
+		pub from: Option<String>,
+
+		/// The maximum number of keys to return.
+		#[serde(skip_serializing_if = "Option::is_none")]
+		#[ruma_api(query)]
+		pub limit: Option<UInt>,
+	}
+
+	#[response]
+	pub struct Response {
+		/// The reaction keys with their counts, most used first.
+		pub chunk: Vec<Aggregation>,
+
+		/// The token to request the following keys with, if there are more.
+		#[serde(skip_serializing_if = "Option::is_none")]
+		pub next_batch: Option<String>,
+	}
+
+	/// The number of users who reacted with a key.
+	#[derive(Clone, Debug, Deserialize, Serialize)]
+	pub struct Aggregation {
+		#[serde(rename = "type")]
+		pub kind: String,
+
+		pub key: String,
+
+		pub count: u64,
+	}
+}
+
+/// # `GET /_matrix/client/unstable/rooms/{roomId}/aggregations/{eventId}`
+///
+/// Returns the keys of the reactions to an event with the number of users
+/// who reacted with each, most used first. An implementation of the
+/// aggregations endpoint of [MSC2675](https://github.com/matrix-org/matrix-spec-proposals/pull/2675).
+pub(crate) async fn get_aggregations_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_aggregations::Request>,
+) -> Result<get_aggregations::Response> {
+	let sender_user = body.sender_user();
+	let (room_id, event_id) = (&body.room_id, &body.event_id);
+
+	let visible = services
+		.state_accessor
+		.user_can_see_event(sender_user, room_id, event_id)
+		.await;
+
+	let in_room = services
+		.timeline
+		.get_pdu(event_id)
+		.await
+		.is_ok_and(|pdu| pdu.room_id == *room_id);
+
+	if !visible || !in_room {
+		return Err!(Request(NotFound("Event not found.")));
+	}
+
+	let from: usize = body
+		.from
+		.as_deref()
+		.map(str::parse)
+		.transpose()
+		.map_err(|_| err!(Request(InvalidParam("Invalid from token."))))?
+		.unwrap_or(0);
+
+	// Use limit or else 30, with maximum 100
+	let limit: usize = body
+		.limit
+		.map(TryInto::try_into)
+		.flat_ok()
+		.unwrap_or(30)
+		.min(100);
+
+	let annotations = services.pdu_metadata.annotations(event_id).await;
+	let next = from.saturating_add(limit);
+	let next_batch = (annotations.len() > next).then(|| next.to_string());
+
+	let chunk = annotations
+		.into_iter()
+		.skip(from)
+		.take(limit)
+		.map(|(key, count)| get_aggregations::Aggregation {
+			kind: TimelineEventType::Reaction.to_string(),
+			key,
+			count,
+		})
+		.collect();
+
+	Ok(get_aggregations::Response { chunk, next_batch })
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn paginate_relations_with_filter(
 	services: &Services,
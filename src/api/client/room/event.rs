@@ -1,7 +1,7 @@
 use axum::extract::State;
-use futures::{FutureExt, TryFutureExt, future::try_join};
+use futures::{FutureExt, TryFutureExt, future::try_join3};
 use ruma::api::client::room::get_room_event;
-use tuwunel_core::{Err, Event, Result, err};
+use tuwunel_core::{Err, Event, Result, err, utils::result::LogErr};
 
 use crate::{Ruma, client::is_ignored_pdu};
 
@@ -20,12 +20,17 @@ pub(crate) async fn get_room_event_route(
 		.get_pdu(event_id)
 		.map_err(|_| err!(Request(NotFound("Event {} not found.", event_id))));
 
+	let count = services
+		.timeline
+		.get_pdu_count(event_id)
+		.map_err(|_| err!(Request(NotFound("Event {} not found.", event_id))));
+
 	let visible = services
 		.state_accessor
 		.user_can_see_event(body.sender_user(), room_id, event_id)
 		.map(Ok);
 
-	let (mut event, visible) = try_join(event, visible).await?;
+	let (mut event, count, visible) = try_join3(event, count, visible).await?;
 
 	if !visible || is_ignored_pdu(services, &event, body.sender_user()).await {
 		return Err!(Request(Forbidden("You don't have permission to view this event.")));
@@ -37,6 +42,12 @@ pub(crate) async fn get_room_event_route(
 	);
 
 	event.add_age().ok();
+	services
+		.pdu_metadata
+		.add_bundled_aggregations(body.sender_user(), count, &mut event)
+		.await
+		.log_err()
+		.ok();
 
 	Ok(get_room_event::v3::Response { event: event.into_format() })
 }
@@ -2,6 +2,7 @@
 mod create;
 mod event;
 mod initial_sync;
+mod purge;
 mod summary;
 mod upgrade;
 
@@ -10,6 +11,7 @@
 	create::create_room_route,
 	event::get_room_event_route,
 	initial_sync::room_initial_sync_route,
+	purge::purge_history_route,
 	summary::{get_room_summary, get_room_summary_legacy},
 	upgrade::upgrade_room_route,
 };
@@ -0,0 +1,91 @@
+use axum::extract::State;
+use tuwunel_core::{Err, Result, info};
+
+use crate::Ruma;
+
+pub(crate) mod purge_history {
+	use ruma::{
+		MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomId,
+		api::{Metadata, request, response},
+		metadata,
+	};
+
+	const METADATA: Metadata = metadata! {
+		method: POST,
+		rate_limited: false,
+		authentication: AccessToken,
+		history: {
+			unstable => "/_tuwunel/admin/v1/rooms/{room_id}/purge_history",
+		}
+	};
+
+	#[request]
+	pub struct Request {
+		/// The room to purge the history of.
+		#[ruma_api(path)]
+		pub room_id: OwnedRoomId,
+
+		/// Purge the events preceding this event.
+		#[serde(skip_serializing_if = "Option::is_none")]
+		pub before_event_id: Option<OwnedEventId>,
+
+		/// Purge the events sent before this timestamp.
+		#[serde(skip_serializing_if = "Option::is_none")]
+		pub before_ts: Option<MilliSecondsSinceUnixEpoch>,
+	}
+
+	#[response]
+	pub struct Response {
+		/// The number of events purged.
+		pub purged: usize,
+	}
+}
+
+/// # `POST /_tuwunel/admin/v1/rooms/{room_id}/purge_history`
+///
+/// Tuwunel-specific admin API to purge the history of a room preceding an
+/// event or a timestamp. Non-state events are removed while the room's state
+/// and forward extremities are kept. Requires a server admin's access token.
+pub(crate) async fn purge_history_route(
+	State(services): State<crate::State>,
+	body: Ruma<purge_history::Request>,
+) -> Result<purge_history::Response> {
+	let sender_user = body.sender_user();
+	let room_id = &body.room_id;
+
+	if !services.admin.user_is_admin(sender_user).await {
+		return Err!(Request(Forbidden("Only server admins can purge room history.")));
+	}
+
+	if services.admin.is_admin_room(room_id).await {
+		return Err!(Request(Forbidden("Cannot purge the history of the admin room.")));
+	}
+
+	let until = match (&body.before_event_id, body.before_ts) {
+		| (Some(event_id), None) =>
+			services
+				.timeline
+				.get_pdu_count_in_room(room_id, event_id)
+				.await?,
+		| (None, Some(ts)) =>
+			services
+				.timeline
+				.first_count_since(room_id, ts)
+				.await,
+		| _ =>
+			return Err!(Request(InvalidParam(
+				"Exactly one of before_event_id or before_ts is required."
+			))),
+	};
+
+	let state_lock = services.state.mutex.lock(room_id).await;
+
+	let purged = services
+		.delete
+		.purge_history(room_id, until, &state_lock)
+		.await?;
+
+	info!(%sender_user, "Purged {purged} events from the history of {room_id}");
+
+	Ok(purge_history::Response { purged })
+}
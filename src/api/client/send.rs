@@ -1,7 +1,10 @@
 use std::collections::BTreeMap;
 
 use axum::extract::State;
-use ruma::{api::client::message::send_message_event, events::MessageLikeEventType};
+use ruma::{
+	api::client::message::send_message_event,
+	events::{MessageLikeEventType, reaction::ReactionEventContent},
+};
 use serde_json::from_str;
 use tuwunel_core::{Err, Result, err, matrix::pdu::PduBuilder, utils};
 
@@ -62,6 +65,20 @@ pub(crate) async fn send_message_event_route(
 		});
 	}
 
+	// A user can react to an event with the same key only once.
+	if body.event_type == MessageLikeEventType::Reaction {
+		if let Ok(ReactionEventContent { relates_to, .. }) = from_str(body.body.body.json().get())
+		{
+			if services
+				.pdu_metadata
+				.has_annotated(&relates_to.event_id, &relates_to.key, sender_user)
+				.await
+			{
+				return Err!(Request(InvalidParam("Can't send the same reaction twice.")));
+			}
+		}
+	}
+
 	let mut unsigned = BTreeMap::new();
 	unsigned.insert("transaction_id".to_owned(), body.txn_id.to_string().into());
 
@@ -16,23 +16,9 @@ pub(super) async fn ldap_login(
 	lowercased_user_id: &UserId,
 	password: &str,
 ) -> Result<OwnedUserId> {
-	let (user_dn, is_ldap_admin) = match services.config.ldap.bind_dn.as_ref() {
-		| Some(bind_dn) if bind_dn.contains("{username}") =>
-			(bind_dn.replace("{username}", lowercased_user_id.localpart()), false),
-		| _ => {
-			debug!("Searching user in LDAP");
-
-			let dns = services.users.search_ldap(user_id).await?;
-			if dns.len() >= 2 {
-				return Err!(Ldap("LDAP search returned two or more results"));
-			}
-
-			let Some((user_dn, is_admin)) = dns.first() else {
-				return password_login(services, user_id, lowercased_user_id, password).await;
-			};
-
-			(user_dn.clone(), *is_admin)
-		},
+	let Some((user_dn, is_ldap_admin)) = user_dn(services, user_id, lowercased_user_id).await?
+	else {
+		return password_login(services, user_id, lowercased_user_id, password).await;
 	};
 
 	let user_id = services
@@ -75,3 +61,37 @@ pub(super) async fn ldap_login(
 
 	Ok(user_id)
 }
+
+/// Checks the password of a user through the configured LDAP server, without
+/// the account changes a login makes.
+#[tracing::instrument(skip_all, fields(%user_id), name = "ldap")]
+pub(crate) async fn ldap_verify(services: &Services, user_id: &UserId, password: &str) -> Result {
+	let Some((user_dn, _)) = user_dn(services, user_id, user_id).await? else {
+		return Err!(Ldap("User is not found in LDAP"));
+	};
+
+	services.users.auth_ldap(&user_dn, password).await
+}
+
+/// Returns the DN of the user in the LDAP and whether they are an admin there,
+/// or None if the search does not find them.
+async fn user_dn(
+	services: &Services,
+	user_id: &UserId,
+	lowercased_user_id: &UserId,
+) -> Result<Option<(String, bool)>> {
+	match services.config.ldap.bind_dn.as_ref() {
+		| Some(bind_dn) if bind_dn.contains("{username}") =>
+			Ok(Some((bind_dn.replace("{username}", lowercased_user_id.localpart()), false))),
+		| _ => {
+			debug!("Searching user in LDAP");
+
+			let dns = services.users.search_ldap(user_id).await?;
+			if dns.len() >= 2 {
+				return Err!(Ldap("LDAP search returned two or more results"));
+			}
+
+			Ok(dns.into_iter().next())
+		},
+	}
+}
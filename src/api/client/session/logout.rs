@@ -2,7 +2,7 @@
 use axum_client_ip::InsecureClientIp;
 use futures::StreamExt;
 use ruma::api::client::session::{logout, logout_all};
-use tuwunel_core::Result;
+use tuwunel_core::{Result, utils::ReadyExt};
 
 use crate::Ruma;
 
@@ -38,6 +38,7 @@ pub(crate) async fn logout_route(
 ///   last seen ts)
 /// - Forgets all to-device events
 /// - Triggers device list updates
+/// - Keeps the dehydrated device, if any
 ///
 /// Note: This is equivalent to calling [`GET
 /// /_matrix/client/r0/logout`](fn.logout_route.html) from each device of this
@@ -48,9 +49,17 @@ pub(crate) async fn logout_all_route(
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<logout_all::v3::Request>,
 ) -> Result<logout_all::v3::Response> {
+	let dehydrated = services
+		.users
+		.dehydrated_device(body.sender_user())
+		.await
+		.ok()
+		.map(|dehydrated| dehydrated.device_id);
+
 	services
 		.users
 		.all_device_ids(body.sender_user())
+		.ready_filter(|&device_id| Some(device_id) != dehydrated.as_deref())
 		.for_each(|device_id| {
 			services
 				.users
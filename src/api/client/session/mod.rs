@@ -26,6 +26,7 @@
 
 use self::{ldap::ldap_login, password::password_login};
 pub(crate) use self::{
+	ldap::ldap_verify,
 	logout::{logout_all_route, logout_route},
 	refresh::refresh_token_route,
 	token::login_token_route,
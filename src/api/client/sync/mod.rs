@@ -6,11 +6,12 @@
 use tuwunel_core::{
 	Error, PduCount, Result,
 	matrix::pdu::PduEvent,
-	utils::stream::{BroadbandExt, ReadyExt},
+	utils::stream::{BroadbandExt, ReadyExt, WidebandExt},
 };
 use tuwunel_service::Services;
 
 pub(crate) use self::{v3::sync_events_route, v5::sync_events_v5_route};
+use crate::client::bundle_aggregations;
 
 async fn load_timeline(
 	services: &Services,
@@ -41,6 +42,7 @@ async fn load_timeline(
 	let timeline_pdus: Vec<_> = non_timeline_pdus
 		.by_ref()
 		.take(limit)
+		.wide_then(|item| bundle_aggregations(services, item, sender_user))
 		.collect()
 		.map(|mut pdus: Vec<_>| {
 			pdus.reverse();
@@ -63,6 +63,7 @@
 		lazy_loading,
 		lazy_loading::{Options, Witness},
 		short::{ShortEventId, ShortStateHash, ShortStateKey},
+		user,
 	},
 };
 
@@ -218,6 +219,13 @@ async fn build_empty_response(
 			.count_one_time_keys(body.sender_user(), body.sender_device())
 			.await,
 
+		device_unused_fallback_key_types: Some(
+			services
+				.users
+				.unused_fallback_key_types(body.sender_user(), body.sender_device())
+				.await,
+		),
+
 		..sync_events::v3::Response::new(next_batch.to_string())
 	}
 }
@@ -377,9 +385,14 @@ async fn build_sync_events(
 		.get_to_device_events(sender_user, sender_device, Some(since), Some(next_batch))
 		.collect::<Vec<_>>();
 
-	let device_one_time_keys_count = services
-		.users
-		.count_one_time_keys(sender_user, sender_device);
+	let device_one_time_keys_count = join(
+		services
+			.users
+			.count_one_time_keys(sender_user, sender_device),
+		services
+			.users
+			.unused_fallback_key_types(sender_user, sender_device),
+	);
 
 	// Remove all to-device events the device received *last time*
 	let remove_to_device_events =
@@ -390,7 +403,7 @@ async fn build_sync_events(
 	let (
 		account_data,
 		keys_changed,
-		device_one_time_keys_count,
+		(device_one_time_keys_count, device_unused_fallback_key_types),
 		((), to_device_events, presence_updates),
 		(
 			(joined_rooms, mut device_list_updates, left_encrypted_users),
@@ -439,8 +452,7 @@ async fn build_sync_events(
 			changed: device_list_updates.into_iter().collect(),
 		},
 		device_one_time_keys_count,
-		// Fallback keys are not yet supported
-		device_unused_fallback_key_types: None,
+		device_unused_fallback_key_types: Some(device_unused_fallback_key_types),
 		next_batch: next_batch.to_string(),
 		presence: Presence { events: presence_events },
 		rooms: Rooms {
@@ -558,6 +570,7 @@ async fn handle_left_room(
 			redacts: None,
 			hashes: EventHash::default(),
 			signatures: None,
+			rejected: false,
 		};
 
 		return Ok(Some(LeftRoom {
@@ -698,6 +711,19 @@ async fn load_joined_room(
 	)
 	.await?;
 
+	// State fetched for a room joined with partial state is not carried by any
+	// event; when it came after the timeline it is only in the current state.
+	let resync_count = services
+		.partial_state
+		.resync_count(room_id)
+		.await
+		.ok();
+
+	let resynced_after_timeline =
+		resync_count.is_some_and(|count| PduCount::Normal(count) > last_timeline_count);
+
+	let resynced_since_last_sync = resync_count.is_some_and(|count| count > since);
+
 	let since_shortstatehash = services
 		.timeline
 		.prev_shortstatehash(room_id, PduCount::Normal(since).saturating_add(1))
@@ -705,6 +731,7 @@ async fn load_joined_room(
 
 	let horizon_shortstatehash: OptionFuture<_> = timeline_pdus
 		.first()
+		.filter(|_| !resynced_after_timeline)
 		.map(at!(0))
 		.map(|count| {
 			services
@@ -718,7 +745,19 @@ async fn load_joined_room(
 		.timeline
 		.get_shortstatehash(room_id, last_timeline_count)
 		.inspect_err(inspect_debug_log)
-		.or_else(|_| services.state.get_room_shortstatehash(room_id))
+		.map(|shortstatehash| {
+			shortstatehash
+				.ok()
+				.filter(|_| !resynced_after_timeline)
+		})
+		.then(async |shortstatehash| match shortstatehash {
+			| Some(shortstatehash) => Ok(shortstatehash),
+			| None =>
+				services
+					.state
+					.get_room_shortstatehash(room_id)
+					.await,
+		})
 		.map_err(|_| err!(Database(error!("Room {room_id} has no state"))));
 
 	let receipt_events = services
@@ -837,7 +876,7 @@ async fn load_joined_room(
 		services,
 		sender_user,
 		room_id,
-		full_state || initial,
+		full_state || initial || resynced_since_last_sync,
 		since_shortstatehash,
 		horizon_shortstatehash,
 		current_shortstatehash,
@@ -893,6 +932,17 @@ async fn load_joined_room(
 		})
 		.into();
 
+	// Threads are counted apart from the main timeline when the client asks
+	// for it (MSC3773)
+	let thread_notification_counts: OptionFuture<_> = (send_notification_counts
+		&& filter.room.timeline.unread_thread_notifications)
+		.then(|| {
+			services
+				.user
+				.thread_notification_counts(sender_user, room_id)
+		})
+		.into();
+
 	let private_read_event: OptionFuture<_> = last_privateread_update
 		.gt(&since)
 		.then(|| {
@@ -988,17 +1038,39 @@ async fn load_joined_room(
 	let (
 		(room_events, account_data_events),
 		(typing_events, private_read_event),
-		(notification_count, highlight_count),
+		(notification_count, highlight_count, thread_notification_counts),
 		(device_list_updates, left_encrypted_users),
 	) = join4(
 		join(room_events, account_data_events),
 		join(typing_events, private_read_event),
-		join(notification_count, highlight_count),
+		join3(notification_count, highlight_count, thread_notification_counts),
 		device_list_updates,
 	)
 	.boxed()
 	.await;
 
+	let thread_notification_counts = thread_notification_counts.unwrap_or_default();
+	let (thread_notifications, thread_highlights) =
+		user::thread_totals(&thread_notification_counts);
+
+	let notification_count =
+		notification_count.map(|count| count.saturating_sub(ruma_from_u64(thread_notifications)));
+
+	let highlight_count =
+		highlight_count.map(|count| count.saturating_sub(ruma_from_u64(thread_highlights)));
+
+	let unread_thread_notifications = thread_notification_counts
+		.into_iter()
+		.map(|(thread_id, (notification_count, highlight_count))| {
+			let counts = UnreadNotificationsCount {
+				highlight_count: Some(ruma_from_u64(highlight_count)),
+				notification_count: Some(ruma_from_u64(notification_count)),
+			};
+
+			(thread_id, counts)
+		})
+		.collect();
+
 	let is_in_timeline = |event: &PduEvent| {
 		room_events
 			.iter()
@@ -1048,7 +1120,7 @@ async fn load_joined_room(
 				.collect(),
 		},
 		unread_notifications: UnreadNotificationsCount { highlight_count, notification_count },
-		unread_thread_notifications: BTreeMap::new(),
+		unread_thread_notifications,
 	};
 
 	Ok((joined_room, device_list_updates, left_encrypted_users))
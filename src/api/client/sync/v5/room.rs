@@ -23,11 +23,13 @@
 	matrix::{Event, StateKey, pdu::PduCount},
 	ref_at,
 	utils::{
-		BoolExt, IterStream, ReadyExt, TryFutureExtExt, math::usize_from_ruma, result::FlatOk,
+		BoolExt, IterStream, ReadyExt, TryFutureExtExt,
+		math::{ruma_from_u64, usize_from_ruma},
+		result::FlatOk,
 		stream::BroadbandExt,
 	},
 };
-use tuwunel_service::{Services, sync::Room};
+use tuwunel_service::{Services, rooms::user, sync::Room};
 
 use super::{super::load_timeline, Connection, SyncInfo, WindowRoom};
 use crate::client::ignored_filter;
@@ -249,6 +251,10 @@ pub(super) async fn handle(
 		.user
 		.last_notification_read(sender_user, room_id);
 
+	let thread_notification_counts = services
+		.user
+		.thread_notification_counts(sender_user, room_id);
+
 	let timeline = timeline_pdus
 		.iter()
 		.stream()
@@ -260,16 +266,33 @@ pub(super) async fn handle(
 	let meta = join3(room_name, room_avatar, is_dm);
 	let events = join4(timeline, num_live, required_state, invite_state);
 	let member_counts = join(joined_count, invited_count);
-	let notification_counts = join3(highlight_count, notification_count, last_read_count);
+	let notification_counts =
+		join4(highlight_count, notification_count, last_read_count, thread_notification_counts);
 	let (
 		(room_name, room_avatar, is_dm),
 		(timeline, num_live, required_state, invite_state),
 		(joined_count, invited_count),
-		(highlight_count, notification_count, _last_notification_read),
+		(
+			highlight_count,
+			notification_count,
+			_last_notification_read,
+			thread_notification_counts,
+		),
 	) = join4(meta, events, member_counts, notification_counts)
 		.boxed()
 		.await;
 
+	// Thread counts are kept out of the room counts as in /sync; the sliding sync
+	// room response has no field to return them apart.
+	let (thread_notifications, thread_highlights) =
+		user::thread_totals(&thread_notification_counts);
+
+	let notification_count =
+		notification_count.map(|count| count.saturating_sub(ruma_from_u64(thread_notifications)));
+
+	let highlight_count =
+		highlight_count.map(|count| count.saturating_sub(ruma_from_u64(thread_highlights)));
+
 	let (heroes, hero_name, heroes_avatar) = calculate_heroes(
 		services,
 		sender_user,
@@ -1,9 +1,13 @@
 use std::collections::BTreeMap;
 
-use axum::{Json, extract::State, response::IntoResponse};
+use axum::{
+	Json,
+	extract::{Path, State},
+	response::IntoResponse,
+};
 use futures::StreamExt;
 use ruma::api::client::discovery::get_supported_versions;
-use tuwunel_core::Result;
+use tuwunel_core::{Result, err};
 
 use crate::Ruma;
 
@@ -71,6 +75,20 @@ pub(crate) async fn tuwunel_server_version() -> Result<impl IntoResponse> {
 	})))
 }
 
+/// # `GET /.well-known/acme-challenge/{token}`
+///
+/// Answers a pending ACME http-01 challenge while a certificate for direct TLS
+/// is being provisioned.
+pub(crate) async fn acme_challenge_route(
+	State(services): State<crate::State>,
+	Path(token): Path<String>,
+) -> Result<impl IntoResponse> {
+	services
+		.acme
+		.http01_key_authorization(&token)
+		.ok_or_else(|| err!(Request(NotFound("No pending challenge for this token."))))
+}
+
 /// # `GET /_tuwunel/local_user_count`
 ///
 /// Tuwunel-specific API to return the amount of users registered on this
@@ -17,7 +17,10 @@
 
 use self::handler::RouterExt;
 pub(super) use self::{
-	args::Args as Ruma, auth::auth_uiaa, response::RumaResponse, state::State,
+	args::Args as Ruma,
+	auth::{auth_uiaa, auth_uiaa_cross_signing_reset},
+	response::RumaResponse,
+	state::State,
 };
 use crate::{client, server};
 
@@ -44,6 +47,8 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::change_password_route)
 		.ruma_route(&client::deactivate_route)
 		.ruma_route(&client::third_party_route)
+		.ruma_route(&client::add_3pid_route)
+		.ruma_route(&client::delete_3pid_route)
 		.ruma_route(&client::request_3pid_management_token_via_email_route)
 		.ruma_route(&client::request_3pid_management_token_via_msisdn_route)
 		.ruma_route(&client::check_registration_token_validity)
@@ -163,6 +168,10 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::update_device_route)
 		.ruma_route(&client::delete_device_route)
 		.ruma_route(&client::delete_devices_route)
+		.ruma_route(&client::put_dehydrated_device_route)
+		.ruma_route(&client::get_dehydrated_device_route)
+		.ruma_route(&client::delete_dehydrated_device_route)
+		.ruma_route(&client::get_dehydrated_events_route)
 		.ruma_route(&client::get_tags_route)
 		.ruma_route(&client::update_tag_route)
 		.ruma_route(&client::delete_tag_route)
@@ -171,11 +180,13 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::get_key_changes_route)
 		.ruma_route(&client::get_pushers_route)
 		.ruma_route(&client::set_pushers_route)
+		.ruma_route(&client::get_notifications_route)
 		.ruma_route(&client::upgrade_room_route)
 		.ruma_route(&client::get_threads_route)
 		.ruma_route(&client::get_relating_events_with_rel_type_and_event_type_route)
 		.ruma_route(&client::get_relating_events_with_rel_type_route)
 		.ruma_route(&client::get_relating_events_route)
+		.ruma_route(&client::get_aggregations_route)
 		.ruma_route(&client::get_hierarchy_route)
 		.ruma_route(&client::get_mutual_rooms_route)
 		.ruma_route(&client::get_room_summary)
@@ -186,6 +197,21 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 		.ruma_route(&client::well_known_support)
 		.ruma_route(&client::well_known_client)
 		.route("/_tuwunel/server_version", get(client::tuwunel_server_version))
+		.route("/.well-known/acme-challenge/{token}", get(client::acme_challenge_route))
+		.route(
+			"/_tuwunel/email/unsubscribe/{token}",
+			get(client::email_unsubscribe_page_route).post(client::email_unsubscribe_route),
+		)
+		.route(
+			"/_tuwunel/email/validate/{sid}/{token}",
+			get(client::email_validation_page_route).post(client::validate_email_route),
+		)
+		.route(
+			"/_tuwunel/cross_signing/reset/{token}",
+			get(client::cross_signing_reset_page_route)
+				.post(client::confirm_cross_signing_reset_route),
+		)
+		.ruma_route(&client::purge_history_route)
 		.ruma_route(&client::room_initial_sync_route)
 		.route("/client/server.json", get(client::syncv3_client_server_json));
 
@@ -215,6 +241,8 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 			.ruma_route(&server::create_join_event_v1_route)
 			.ruma_route(&server::create_join_event_v2_route)
 			.ruma_route(&server::create_invite_route)
+			.ruma_route(&server::exchange_third_party_invite_route)
+			.ruma_route(&server::third_party_invite_onbind_route)
 			.ruma_route(&server::get_devices_route)
 			.ruma_route(&server::get_room_information_route)
 			.ruma_route(&server::get_profile_information_route)
@@ -226,6 +254,12 @@ pub fn build(router: Router<State>, server: &Server) -> Router<State> {
 			.ruma_route(&server::get_content_route)
 			.ruma_route(&server::get_content_thumbnail_route)
 			.route("/_tuwunel/local_user_count", get(client::tuwunel_local_user_count));
+
+		if config.allow_notary {
+			router = router
+				.ruma_route(&server::get_remote_server_keys_route)
+				.ruma_route(&server::get_remote_server_keys_batch_route);
+		}
 	} else {
 		router = router
 			.route("/_matrix/federation/{*path}", any(federation_disabled))
@@ -30,13 +30,16 @@
 			},
 			voip::get_turn_server_info,
 		},
-		federation::openid::get_openid_userinfo,
+		federation::{
+			discovery::{get_remote_server_keys, get_remote_server_keys_batch},
+			openid::get_openid_userinfo,
+		},
 	},
 };
 use tuwunel_core::{Err, Error, Result, is_less_than, utils::result::LogDebugErr};
 use tuwunel_service::{Services, appservice::RegistrationInfo};
 
-pub(crate) use self::uiaa::auth_uiaa;
+pub(crate) use self::uiaa::{auth_uiaa, auth_uiaa_cross_signing_reset};
 use self::{appservice::auth_appservice, server::auth_server};
 use super::request::Request;
 
@@ -130,6 +133,17 @@ pub(super) async fn auth(
 
 		| (ServerSignatures, Token::None) => Ok(auth_server(services, request, json_body).await?),
 
+		// Key queries are unauthenticated, unless only some servers may query us
+		// as a notary.
+		| (AuthScheme::None, Token::None)
+			if is_notary_query(metadata)
+				&& !services
+					.server
+					.config
+					.notary_allowed_servers
+					.is_empty() =>
+			Ok(auth_server(services, request, json_body).await?),
+
 		| (AccessToken, Appservice(info)) => Ok(auth_appservice(services, request, info).await?),
 
 		| (AccessToken | AppserviceToken, Token::None) => match metadata {
@@ -163,6 +177,14 @@ pub(super) async fn auth(
 	}
 }
 
+fn is_notary_query(metadata: &Metadata) -> bool {
+	matches!(
+		metadata,
+		&get_remote_server_keys::v2::Request::METADATA
+			| &get_remote_server_keys_batch::v2::Request::METADATA
+	)
+}
+
 fn check_auth_still_required(services: &Services, metadata: &Metadata, token: &Token) -> Result {
 	debug_assert_eq!(
 		metadata.authentication,
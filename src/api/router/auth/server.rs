@@ -19,7 +19,7 @@ pub(super) async fn auth_server(
 	type Value = CanonicalJsonValue;
 
 	let x_matrix = parse_x_matrix(request).await?;
-	auth_server_checks(services, &x_matrix)?;
+	auth_server_checks(services, &x_matrix).await?;
 
 	let destination = services.globals.server_name();
 	let origin = &x_matrix.origin;
@@ -86,7 +86,7 @@ pub(super) async fn auth_server(
 	})
 }
 
-fn auth_server_checks(services: &Services, x_matrix: &XMatrix) -> Result {
+async fn auth_server_checks(services: &Services, x_matrix: &XMatrix) -> Result {
 	if !services.server.config.allow_federation {
 		return Err!(Config("allow_federation", "Federation is disabled."));
 	}
@@ -97,11 +97,7 @@ fn auth_server_checks(services: &Services, x_matrix: &XMatrix) -> Result {
 	}
 
 	let origin = &x_matrix.origin;
-	if services
-		.config
-		.forbidden_remote_server_names
-		.is_match(origin.host())
-	{
+	if !services.federation.is_allowed(origin).await {
 		return Err!(Request(Forbidden(debug_warn!(
 			"Federation requests from {origin} denied."
 		))));
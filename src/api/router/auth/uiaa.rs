@@ -5,8 +5,12 @@
 		client::uiaa::{AuthData, AuthFlow, AuthType, Jwt, UiaaInfo},
 	},
 };
+use serde_json::{json, value::to_raw_value};
 use tuwunel_core::{Err, Error, Result, err, utils};
-use tuwunel_service::{Services, uiaa::SESSION_ID_LENGTH};
+use tuwunel_service::{
+	Services,
+	uiaa::{OAUTH_STAGE, SESSION_ID_LENGTH},
+};
 
 use crate::{Ruma, client::jwt};
 
@@ -19,11 +23,76 @@ pub(crate) async fn auth_uiaa<T>(services: &Services, body: &Ruma<T>) -> Result<
 		AuthFlow::new([AuthType::Jwt].into()),
 	];
 
-	let mut uiaainfo = UiaaInfo {
+	let uiaainfo = UiaaInfo {
 		flows: flows.into(),
 		..Default::default()
 	};
 
+	authenticate(services, body, uiaainfo).await
+}
+
+/// Like `auth_uiaa`, but users without a password are offered an `m.oauth`
+/// stage instead, completed once the user confirms the cross-signing reset at
+/// the given URL with their LDAP password, or a server admin approves it.
+pub(crate) async fn auth_uiaa_cross_signing_reset<T>(
+	services: &Services,
+	body: &Ruma<T>,
+) -> Result<OwnedUserId>
+where
+	T: IncomingRequest + Send + Sync,
+{
+	let sender_user = body
+		.sender_user
+		.as_deref()
+		.ok_or_else(|| err!(Request(MissingToken("Missing access token."))))?;
+
+	if services.users.has_password(sender_user).await {
+		return auth_uiaa(services, body).await;
+	}
+
+	let flows = [
+		AuthFlow::new([AuthType::Jwt].into()),
+		AuthFlow::new([AuthType::from(OAUTH_STAGE)].into()),
+	];
+
+	// The link is only created for a new session; later requests of the session
+	// are checked against the stored session which already has it.
+	let new_session = body
+		.json_body
+		.as_ref()
+		.and_then(CanonicalJsonValue::as_object)
+		.is_some_and(|body| !body.contains_key("auth"));
+
+	let params = if new_session {
+		let url = services
+			.users
+			.cross_signing_reset_url(sender_user)
+			.await?;
+
+		Some(to_raw_value(&json!({
+			OAUTH_STAGE: { "url": url },
+		}))?)
+	} else {
+		None
+	};
+
+	let uiaainfo = UiaaInfo {
+		flows: flows.into(),
+		params,
+		..Default::default()
+	};
+
+	authenticate(services, body, uiaainfo).await
+}
+
+async fn authenticate<T>(
+	services: &Services,
+	body: &Ruma<T>,
+	mut uiaainfo: UiaaInfo,
+) -> Result<OwnedUserId>
+where
+	T: IncomingRequest + Send + Sync,
+{
 	match body
 		.json_body
 		.as_ref()
@@ -1,7 +1,7 @@
 use axum::extract::State;
 use futures::{FutureExt, future::try_join};
 use ruma::{MilliSecondsSinceUnixEpoch, OwnedRoomId, api::federation::event::get_event};
-use tuwunel_core::{Result, err};
+use tuwunel_core::{Err, Result, err};
 
 use super::AccessCheck;
 use crate::Ruma;
@@ -22,6 +22,14 @@ pub(crate) async fn get_event_route(
 		.await
 		.map_err(|_| err!(Request(NotFound("Event not found."))))?;
 
+	if services
+		.pdu_metadata
+		.is_event_rejected(&body.event_id)
+		.await
+	{
+		return Err!(Request(NotFound("Event not found.")));
+	}
+
 	let room_id: OwnedRoomId = event
 		.get("room_id")
 		.and_then(|val| val.as_str())
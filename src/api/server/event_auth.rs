@@ -57,6 +57,10 @@ pub(crate) async fn get_event_authorization_route(
 		.event_ids_iter(room_id, once(body.event_id.borrow()))
 		.ready_filter_map(Result::ok)
 		.broad_filter_map(async |id| {
+			if services.pdu_metadata.is_event_rejected(&id).await {
+				return None;
+			}
+
 			let pdu = services.timeline.get_pdu_json(&id).await.ok()?;
 
 			let pdu = services
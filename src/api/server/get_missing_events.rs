@@ -53,7 +53,12 @@ pub(crate) async fn get_missing_events_route(
 			continue;
 		};
 
-		if body.earliest_events.contains(&queued_events[i]) {
+		if body.earliest_events.contains(&queued_events[i])
+			|| services
+				.pdu_metadata
+				.is_event_rejected(&queued_events[i])
+				.await
+		{
 			i = i.saturating_add(1);
 			continue;
 		}
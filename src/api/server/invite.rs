@@ -69,6 +69,11 @@ pub(crate) async fn create_invite_route(
 		return Err!(Request(Forbidden("Server is banned on this homeserver.")));
 	}
 
+	let policy = services.federation.policy(body.origin()).await;
+	if policy.no_invites || policy.read_only {
+		return Err!(Request(Forbidden("Invites from this server are not accepted.")));
+	}
+
 	let mut signed_event = utils::to_canonical_object(&body.event)
 		.map_err(|_| err!(Request(InvalidParam("Invite event is invalid."))))?;
 
@@ -1,18 +1,32 @@
 use std::{
+	collections::BTreeMap,
 	mem::take,
 	time::{Duration, SystemTime},
 };
 
 use axum::{Json, extract::State, response::IntoResponse};
+use futures::StreamExt;
 use ruma::{
-	MilliSecondsSinceUnixEpoch, Signatures,
+	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, ServerName, Signatures,
 	api::{
 		OutgoingResponse,
-		federation::discovery::{OldVerifyKey, ServerSigningKeys, get_server_keys},
+		federation::discovery::{
+			OldVerifyKey, ServerSigningKeys, get_remote_server_keys,
+			get_remote_server_keys_batch, get_server_keys,
+		},
 	},
 	serde::Raw,
 };
-use tuwunel_core::{Result, utils::timepoint_from_now};
+use tuwunel_core::{
+	Err, Result, debug_warn,
+	utils::{
+		stream::{BroadbandExt, IterStream},
+		timepoint_from_now,
+	},
+};
+use tuwunel_service::Services;
+
+use crate::Ruma;
 
 /// # `GET /_matrix/key/v2/server`
 ///
@@ -25,6 +39,11 @@
 pub(crate) async fn get_server_keys_route(
 	State(services): State<crate::State>,
 ) -> Result<impl IntoResponse> {
+	Ok(Json(server_keys(&services).await?))
+}
+
+/// Our own keys, signed.
+async fn server_keys(services: &Services) -> Result<CanonicalJsonObject> {
 	let server_name = services.globals.server_name();
 	let active_key_id = services.server_keys.active_key_id();
 	let mut all_keys = services
@@ -33,14 +52,18 @@ pub(crate) async fn get_server_keys_route(
 		.await;
 
 	let verify_keys = all_keys
-		.remove_entry(active_key_id)
+		.remove_entry(&active_key_id)
 		.expect("active verify_key is missing");
 
-	let old_verify_keys = all_keys
+	// Keys we retired are published with the time they were retired; any other
+	// keys known for our name are reported as expiring now.
+	let mut old_verify_keys: BTreeMap<_, _> = all_keys
 		.into_iter()
 		.map(|(id, key)| (id, OldVerifyKey::new(expires_ts(), key.key)))
 		.collect();
 
+	old_verify_keys.extend(services.server_keys.old_verify_keys());
+
 	let server_key = ServerSigningKeys {
 		verify_keys: [verify_keys].into(),
 		old_verify_keys,
@@ -57,7 +80,7 @@ pub(crate) async fn get_server_keys_route(
 
 	services.server_keys.sign_json(&mut response)?;
 
-	Ok(Json(response))
+	Ok(response)
 }
 
 fn valid_until_ts() -> MilliSecondsSinceUnixEpoch {
@@ -82,3 +105,87 @@ pub(crate) async fn get_server_keys_deprecated_route(
 ) -> impl IntoResponse {
 	get_server_keys_route(State(services)).await
 }
+
+/// # `GET /_matrix/key/v2/query/{serverName}`
+///
+/// Gets the public signing keys of another server, acting as a notary.
+pub(crate) async fn get_remote_server_keys_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_remote_server_keys::v2::Request>,
+) -> Result<get_remote_server_keys::v2::Response> {
+	notary_check(&services, body.origin.as_deref())?;
+
+	let server_keys =
+		notary_server_keys(&services, &body.server_name, body.minimum_valid_until_ts).await?;
+
+	Ok(get_remote_server_keys::v2::Response { server_keys: vec![server_keys] })
+}
+
+/// # `POST /_matrix/key/v2/query`
+///
+/// Gets the public signing keys of other servers in a batch, acting as a
+/// notary. Servers whose keys can't be obtained are left out of the response.
+pub(crate) async fn get_remote_server_keys_batch_route(
+	State(services): State<crate::State>,
+	body: Ruma<get_remote_server_keys_batch::v2::Request>,
+) -> Result<get_remote_server_keys_batch::v2::Response> {
+	notary_check(&services, body.origin.as_deref())?;
+
+	if body.server_keys.len() > services.config.trusted_server_batch_size {
+		return Err!(Request(InvalidParam(
+			"Too many servers requested; at most {} are allowed.",
+			services.config.trusted_server_batch_size
+		)));
+	}
+
+	let server_keys = body
+		.server_keys
+		.iter()
+		.stream()
+		.broad_filter_map(async |(server, criteria)| {
+			let minimum_valid_until_ts = criteria
+				.values()
+				.filter_map(|criteria| criteria.minimum_valid_until_ts)
+				.max()
+				.unwrap_or_else(MilliSecondsSinceUnixEpoch::now);
+
+			notary_server_keys(&services, server, minimum_valid_until_ts)
+				.await
+				.inspect_err(|e| debug_warn!(%server, "Failed to obtain keys: {e}"))
+				.ok()
+		})
+		.collect()
+		.await;
+
+	Ok(get_remote_server_keys_batch::v2::Response { server_keys })
+}
+
+fn notary_check(services: &Services, origin: Option<&ServerName>) -> Result {
+	let allowed = &services.config.notary_allowed_servers;
+	if !allowed.is_empty() && !origin.is_some_and(|origin| allowed.is_match(origin.host())) {
+		return Err!(Request(Forbidden("This server is not a notary for you.")));
+	}
+
+	Ok(())
+}
+
+/// The keys of a server signed by it and by us.
+async fn notary_server_keys(
+	services: &Services,
+	server: &ServerName,
+	minimum_valid_until_ts: MilliSecondsSinceUnixEpoch,
+) -> Result<Raw<ServerSigningKeys>> {
+	let server_keys = if services.globals.server_is_ours(server) {
+		server_keys(services).await?
+	} else {
+		let mut server_keys = services
+			.server_keys
+			.notary_keys(server, minimum_valid_until_ts)
+			.await?;
+
+		services.server_keys.sign_json(&mut server_keys)?;
+		server_keys
+	};
+
+	Ok(Raw::from_json(serde_json::value::to_raw_value(&server_keys)?))
+}
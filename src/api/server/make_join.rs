@@ -29,6 +29,14 @@ pub(crate) async fn create_join_event_template_route(
 		return Err!(Request(NotFound("Room is unknown to this server.")));
 	}
 
+	if services
+		.partial_state
+		.is_partial(&body.room_id)
+		.await
+	{
+		return Err!(Request(NotFound("Room is not fully joined by this server yet.")));
+	}
+
 	if body.user_id.server_name() != body.origin() {
 		return Err!(Request(BadJson("Not allowed to join on behalf of another server/user.")));
 	}
@@ -39,6 +47,15 @@ pub(crate) async fn create_join_event_template_route(
 		.acl_check(body.origin(), &body.room_id)
 		.await?;
 
+	if services
+		.federation
+		.policy(body.origin())
+		.await
+		.read_only
+	{
+		return Err!(Request(Forbidden("Joins from this server are not accepted.")));
+	}
+
 	if services
 		.config
 		.forbidden_remote_server_names
@@ -31,6 +31,15 @@ pub(crate) async fn create_knock_event_template_route(
 		.acl_check(body.origin(), &body.room_id)
 		.await?;
 
+	if services
+		.federation
+		.policy(body.origin())
+		.await
+		.read_only
+	{
+		return Err!(Request(Forbidden("Knocks from this server are not accepted.")));
+	}
+
 	if services
 		.config
 		.forbidden_remote_server_names
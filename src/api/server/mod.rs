@@ -18,6 +18,7 @@
 pub(super) mod send_leave;
 pub(super) mod state;
 pub(super) mod state_ids;
+pub(super) mod third_party_invite;
 pub(super) mod user;
 pub(super) mod version;
 pub(super) mod well_known;
@@ -42,6 +43,7 @@
 pub(super) use send_leave::*;
 pub(super) use state::*;
 pub(super) use state_ids::*;
+pub(super) use third_party_invite::*;
 pub(super) use user::*;
 pub(super) use version::*;
 pub(super) use well_known::*;
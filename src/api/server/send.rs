@@ -35,6 +35,7 @@
 };
 use tuwunel_service::{
 	Services,
+	federation::{Direction, TxnLogEntry},
 	sending::{EDU_LIMIT, PDU_LIMIT},
 };
 
@@ -78,6 +79,38 @@ pub(crate) async fn send_transaction_message_route(
 		)));
 	}
 
+	// The origin is evidently reachable; lift any backoff we hold against it.
+	services
+		.sending
+		.mark_reachable(body.origin())
+		.await
+		.log_err()
+		.ok();
+
+	// Only one transaction from an origin is processed at a time; a retry of a
+	// transaction already processed is answered with the original response.
+	let _origin_lock = services
+		.transaction_ids
+		.mutex_federation
+		.lock(body.origin())
+		.await;
+
+	if let Some(response) = services
+		.transaction_ids
+		.existing_federation_txnid(body.origin(), &body.transaction_id)
+	{
+		debug!(id = ?body.transaction_id, origin = ?body.origin(), "Duplicate txn");
+		return Ok(response);
+	}
+
+	let policy = services.federation.policy(body.origin()).await;
+	if policy.read_only {
+		return Err!(Request(Forbidden(debug_warn!(
+			"Transactions from {} are not accepted.",
+			body.origin()
+		))));
+	}
+
 	let txn_start_time = Instant::now();
 	trace!(
 		pdus = body.pdus.len(),
@@ -102,9 +135,12 @@ pub(crate) async fn send_transaction_message_route(
 		.map(|edu| edu.json().get())
 		.map(serde_json::from_str)
 		.filter_map(Result::ok)
+		.filter(|edu| !(policy.no_edus && matches!(edu, Edu::Presence(_) | Edu::Typing(_))))
 		.stream();
 
-	let results = handle(&services, &client, body.origin(), txn_start_time, pdus, edus).await?;
+	let results = handle(&services, &client, body.origin(), txn_start_time, pdus, edus)
+		.await
+		.inspect_err(|e| log_txn(&services, &body, txn_start_time, None, Some(e)))?;
 
 	debug!(
 		pdus = body.pdus.len(),
@@ -122,12 +158,45 @@ pub(crate) async fn send_transaction_message_route(
 		}
 	}
 
-	Ok(send_transaction_message::v1::Response {
+	let response = send_transaction_message::v1::Response {
 		pdus: results
 			.into_iter()
 			.map(|(e, r)| (e, r.map_err(error::sanitized_message)))
 			.collect(),
-	})
+	};
+
+	services
+		.transaction_ids
+		.add_federation_txnid(body.origin(), &body.transaction_id, &response);
+
+	log_txn(&services, &body, txn_start_time, Some(&response), None);
+
+	Ok(response)
+}
+
+fn log_txn(
+	services: &Services,
+	body: &Ruma<send_transaction_message::v1::Request>,
+	started: Instant,
+	response: Option<&send_transaction_message::v1::Response>,
+	error: Option<&Error>,
+) {
+	if !services.federation.txn_log_enabled() {
+		return;
+	}
+
+	let mut entry =
+		TxnLogEntry::new(Direction::Inbound, body.origin(), body.transaction_id.clone())
+			.latency(started.elapsed());
+
+	entry.pdus = body.pdus.len();
+	entry.edus = body.edus.len();
+	entry.pdu_results = response
+		.map(|response| response.pdus.clone())
+		.unwrap_or_default();
+	entry.error = error.map(ToString::to_string);
+
+	services.federation.log_txn(entry);
 }
 
 async fn handle(
@@ -40,6 +40,10 @@ async fn create_join_event(
 		.acl_check(origin, room_id)
 		.await?;
 
+	if services.federation.policy(origin).await.read_only {
+		return Err!(Request(Forbidden("Joins from this server are not accepted.")));
+	}
+
 	// We need to return the state prior to joining, let's keep a reference to that
 	// here
 	let shortstatehash = services
@@ -65,6 +65,15 @@ pub(crate) async fn create_knock_event_v1_route(
 		.acl_check(body.origin(), &body.room_id)
 		.await?;
 
+	if services
+		.federation
+		.policy(body.origin())
+		.await
+		.read_only
+	{
+		return Err!(Request(Forbidden("Knocks from this server are not accepted.")));
+	}
+
 	let room_version_id = services
 		.state
 		.get_room_version(&body.room_id)
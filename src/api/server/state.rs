@@ -7,7 +7,7 @@
 };
 use ruma::{OwnedEventId, api::federation::event::get_room_state};
 use tuwunel_core::{
-	Result, at, err,
+	Err, Result, at, err,
 	utils::{
 		future::TryExtExt,
 		stream::{IterStream, TryBroadbandExt},
@@ -33,6 +33,14 @@ pub(crate) async fn get_room_state_route(
 	.check()
 	.await?;
 
+	if services
+		.partial_state
+		.is_partial(&body.room_id)
+		.await
+	{
+		return Err!(Request(NotFound("Full state of the room is not yet known.")));
+	}
+
 	let shortstatehash = services
 		.state
 		.pdu_shortstatehash(&body.event_id)
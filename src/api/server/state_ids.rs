@@ -3,7 +3,7 @@
 use axum::extract::State;
 use futures::{FutureExt, StreamExt, TryStreamExt, future::try_join};
 use ruma::{OwnedEventId, api::federation::event::get_room_state_ids};
-use tuwunel_core::{Result, at, err};
+use tuwunel_core::{Err, Result, at, err};
 
 use super::AccessCheck;
 use crate::Ruma;
@@ -25,6 +25,14 @@ pub(crate) async fn get_room_state_ids_route(
 	.check()
 	.await?;
 
+	if services
+		.partial_state
+		.is_partial(&body.room_id)
+		.await
+	{
+		return Err!(Request(NotFound("Full state of the room is not yet known.")));
+	}
+
 	let shortstatehash = services
 		.state
 		.pdu_shortstatehash(&body.event_id)
@@ -0,0 +1,83 @@
+use axum::extract::State;
+use ruma::{
+	api::federation::thirdparty::{bind_callback, exchange_invite},
+	events::StateEventType,
+};
+use tuwunel_core::{Err, Result, debug_warn};
+
+use crate::Ruma;
+
+/// # `PUT /_matrix/federation/v1/exchange_third_party_invite/{roomId}`
+///
+/// Exchanges a third-party invite sent by one of our users for an invite of
+/// the user the address was bound to.
+pub(crate) async fn exchange_third_party_invite_route(
+	State(services): State<crate::State>,
+	body: Ruma<exchange_invite::v1::Request>,
+) -> Result<exchange_invite::v1::Response> {
+	if body.kind != StateEventType::RoomMember {
+		return Err!(Request(InvalidParam("Only m.room.member events can be exchanged.")));
+	}
+
+	if body.state_key.server_name() != body.origin() {
+		return Err!(Request(Forbidden(
+			"Invited user does not belong to the requesting server."
+		)));
+	}
+
+	services
+		.event_handler
+		.acl_check(body.origin(), &body.room_id)
+		.await?;
+
+	services
+		.membership
+		.exchange_third_party_invite(
+			&body.sender,
+			&body.state_key,
+			&body.room_id,
+			body.content.clone(),
+		)
+		.await?;
+
+	Ok(exchange_invite::v1::Response {})
+}
+
+/// # `PUT /_matrix/federation/v1/3pid/onbind`
+///
+/// Called by an identity server when an address with pending third-party
+/// invites is bound to one of our users.
+pub(crate) async fn third_party_invite_onbind_route(
+	State(services): State<crate::State>,
+	body: Ruma<bind_callback::v1::Request>,
+) -> Result<bind_callback::v1::Response> {
+	if !services.globals.user_is_local(&body.mxid) {
+		return Err!(Request(InvalidParam("User does not belong to this server.")));
+	}
+
+	for invite in &body.invites {
+		if invite.mxid != body.mxid {
+			continue;
+		}
+
+		if let Err(e) = services
+			.membership
+			.third_party_invite_bound(
+				&invite.sender,
+				&invite.mxid,
+				&invite.room_id,
+				&invite.address,
+				&invite.signed,
+			)
+			.await
+		{
+			debug_warn!(
+				room_id = %invite.room_id,
+				sender = %invite.sender,
+				"Failed to exchange third-party invite: {e}"
+			);
+		}
+	}
+
+	Ok(bind_callback::v1::Response {})
+}
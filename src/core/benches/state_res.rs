@@ -457,8 +457,7 @@ fn to_pdu_event<S>(
 		depth: uint!(0),
 		hashes: EventHash::default(),
 		signatures: None,
-		//#[cfg(test)]
-		//rejected: false,
+		rejected: false,
 	}
 }
 
@@ -72,6 +72,25 @@ pub fn check(config: &Config) -> Result {
 		warn!("Configuration item `listening` is set to `false`. Cannot hear anyone.");
 	}
 
+	if config.tls.acme_directory.is_some()
+		&& (config.tls.certs.is_none() || config.tls.key.is_none())
+	{
+		return Err!(Config(
+			"tls.acme_directory",
+			"ACME requires the tls.certs and tls.key paths to store the certificate and key."
+		));
+	}
+
+	if config.tls.acme_directory.is_some()
+		&& !matches!(config.tls.acme_challenge.as_str(), "tls-alpn-01" | "http-01")
+	{
+		return Err!(Config(
+			"tls.acme_challenge",
+			"Unsupported ACME challenge {:?}; expected \"tls-alpn-01\" or \"http-01\".",
+			config.tls.acme_challenge
+		));
+	}
+
 	if config.unix_socket_path.is_none() {
 		config.get_bind_addrs().iter().for_each(|addr| {
 			use std::path::Path;
@@ -252,6 +252,13 @@ pub struct Config {
 	#[serde(default = "default_roomid_spacehierarchy_cache_capacity")]
 	pub roomid_spacehierarchy_cache_capacity: u32,
 
+	/// Number of events whose relations are cached for bundling edits,
+	/// threads and references into them when they are served to clients.
+	///
+	/// default: varies by system
+	#[serde(default = "default_relations_cache_capacity")]
+	pub relations_cache_capacity: u32,
+
 	/// Minimum timeout a client can request for long-polling sync. Requests
 	/// will be clamped up to this value if smaller.
 	///
@@ -517,6 +524,27 @@ pub struct Config {
 	#[serde(default = "default_pusher_idle_timeout")]
 	pub pusher_idle_timeout: u64,
 
+	/// Time to wait before sending a push notification, so further events for
+	/// the same pusher are sent along in one batch (milliseconds). Of several
+	/// events in a room, only the latest (or the latest highlight) is pushed.
+	///
+	/// default: 500
+	#[serde(default = "default_pusher_coalesce_window_ms")]
+	pub pusher_coalesce_window_ms: u64,
+
+	/// Notification gateway pusher minimum retry backoff (seconds). Failed
+	/// notifications are retried after this time, growing with each failure.
+	///
+	/// default: 10
+	#[serde(default = "default_pusher_retry_backoff_min")]
+	pub pusher_retry_backoff_min: u64,
+
+	/// Notification gateway pusher retry backoff limit (seconds).
+	///
+	/// default: 3600
+	#[serde(default = "default_pusher_retry_backoff_limit")]
+	pub pusher_retry_backoff_limit: u64,
+
 	/// Maximum time to receive a request from a client (seconds).
 	///
 	/// default: 75
@@ -547,6 +575,30 @@ pub struct Config {
 	#[serde(default = "default_sender_shutdown_timeout")]
 	pub sender_shutdown_timeout: u64,
 
+	/// How long the responses to inbound federation transactions are
+	/// remembered (seconds). A transaction retried by the remote within this
+	/// time is answered from the cache instead of being processed again.
+	///
+	/// default: 1800
+	#[serde(default = "default_federation_txn_cache_lifetime")]
+	pub federation_txn_cache_lifetime: u64,
+
+	/// Number of recent inbound and outbound federation transactions kept in
+	/// memory for inspection with the `federation txn-log` admin command. Set
+	/// to 0 to disable the transaction log.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub federation_txn_log_capacity: usize,
+
+	/// Path to a file to which every logged federation transaction is
+	/// appended as a line of JSON. Only used when
+	/// `federation_txn_log_capacity` is non-zero. Transactions are left out
+	/// of the file while more than that many are waiting to be written.
+	///
+	/// example: "/var/log/tuwunel/federation.jsonl"
+	pub federation_txn_log_path: Option<PathBuf>,
+
 	/// Enables registration. If set to false, no users can register on this
 	/// server.
 	///
@@ -702,6 +754,15 @@ pub struct Config {
 	)]
 	pub allow_inbound_profile_lookup_federation_requests: bool,
 
+	/// Seconds for which the profiles of remote users are cached before
+	/// being queried from their server again. A member event changing the
+	/// displayname or avatar of a user has their profile queried again on the
+	/// next lookup.
+	///
+	/// default: 86400
+	#[serde(default = "default_remote_profile_cache_ttl")]
+	pub remote_profile_cache_ttl: u64,
+
 	/// Allow standard users to create rooms. Appservices and admins are always
 	/// allowed to create rooms
 	#[serde(default = "true_fn")]
@@ -805,8 +866,8 @@ pub struct Config {
 	/// Servers listed here will be used to gather public keys of other servers
 	/// (notary trusted key servers).
 	///
-	/// Currently, tuwunel doesn't support inbound batched key requests, so
-	/// this list should only contain other Synapse servers.
+	/// Other tuwunel servers can only be listed here if they have
+	/// `allow_notary` enabled.
 	///
 	/// example: ["matrix.org", "tchncs.de"]
 	///
@@ -850,6 +911,46 @@ pub struct Config {
 	#[serde(default = "default_trusted_server_batch_size")]
 	pub trusted_server_batch_size: usize,
 
+	/// Interval in seconds between runs of the signing key refresh worker. The
+	/// worker fetches the signing keys of servers in rooms with local users
+	/// which we don't have yet or which expire within
+	/// `signing_key_refresh_window`, so verifying their events does not stall
+	/// on a key fetch. Keys are requested from the trusted_servers in batches
+	/// first, then from the servers themselves unless
+	/// only_query_trusted_key_servers is set. Set this value to 0 to disable
+	/// the worker.
+	///
+	/// default: 3600
+	#[serde(default = "default_signing_key_refresh_interval")]
+	pub signing_key_refresh_interval: u64,
+
+	/// Signing keys expiring within this many seconds are refreshed by the
+	/// signing key refresh worker.
+	///
+	/// default: 86400
+	#[serde(default = "default_signing_key_refresh_window")]
+	pub signing_key_refresh_window: u64,
+
+	/// Act as a notary (trusted key server) for other servers by answering
+	/// `/_matrix/key/v2/query` requests for the signing keys of any server.
+	/// Keys are served from our cache, fetched from their origin when missing
+	/// or expiring too soon, and signed with our own key.
+	///
+	/// default: false
+	#[serde(default)]
+	pub allow_notary: bool,
+
+	/// List of server names via regex patterns which may query us as a
+	/// notary. When set, key queries must carry federation authentication,
+	/// which tuwunel sends but other implementations may not. When empty any
+	/// server may query.
+	///
+	/// example: ["^satellite\.example\.com$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub notary_allowed_servers: RegexSet,
+
 	/// Max log level for tuwunel. Allows debug, info, warn, or error.
 	///
 	/// See also:
@@ -939,6 +1040,22 @@ pub struct Config {
 	#[serde(default = "default_access_token_ttl")]
 	pub access_token_ttl: u64,
 
+	/// Time in seconds a user may replace their cross-signing keys without
+	/// user-interactive authentication once the reset has been approved.
+	///
+	/// Users without a local password (e.g. provisioned through LDAP) cannot
+	/// complete password authentication. They are offered a link to a page
+	/// instead, where LDAP users confirm the reset with their LDAP password.
+	/// The page also shows a code, which an admin approves with the
+	/// `!admin users approve-cross-signing-reset` command; admins can also
+	/// allow a user directly with `!admin users allow-cross-signing-reset`.
+	/// Links are valid for the same time. The approval ends once the keys
+	/// were replaced.
+	///
+	/// default: 600
+	#[serde(default = "default_cross_signing_reset_window")]
+	pub cross_signing_reset_window: u64,
+
 	/// Static TURN username to provide the client if not using a shared secret
 	/// ("turn_secret"), It is recommended to use a shared secret over static
 	/// credentials.
@@ -1368,6 +1485,56 @@ pub struct Config {
 	#[serde(default)]
 	pub suppress_push_when_active: bool,
 
+	/// Number of notifying events kept for each user to be listed by the
+	/// notifications endpoint. Older ones are removed in batches as new ones
+	/// arrive, so up to 64 more may be kept for a while. Set to 0 to keep none.
+	///
+	/// default: 500
+	#[serde(default = "default_notification_history_max")]
+	pub notification_history_max: usize,
+
+	/// SMTP relay to send emails through, as a URL. "smtps://host" connects
+	/// with TLS, "smtp://host?tls=required" upgrades the connection with
+	/// STARTTLS, and "smtp://host:25" sends mail unencrypted, which is only
+	/// suitable for a local mail server. Email pushers and email addresses on
+	/// accounts are only available when this is set, and email pushers are
+	/// only accepted for addresses the user validated and added to their
+	/// account.
+	///
+	/// example: "smtps://smtp.example.com"
+	pub smtp_relay: Option<String>,
+
+	/// Username to authenticate to the SMTP relay with. Mail is sent without
+	/// authentication when this is not set.
+	///
+	/// example: "matrix@example.com"
+	pub smtp_username: Option<String>,
+
+	/// Password to authenticate to the SMTP relay with.
+	///
+	/// display: sensitive
+	pub smtp_password: Option<String>,
+
+	/// Path to a file containing the password to authenticate to the SMTP
+	/// relay with. This takes priority over "smtp_password".
+	///
+	/// example: "/etc/tuwunel/.smtp_password"
+	pub smtp_password_file: Option<PathBuf>,
+
+	/// Sender address of email notifications. Defaults to
+	/// "noreply@<server_name>".
+	///
+	/// example: "matrix@example.com"
+	pub email_from: Option<String>,
+
+	/// Time a user must have been inactive before the highlights they haven't
+	/// read are emailed to them, and before a highlight is included in an
+	/// email (seconds).
+	///
+	/// default: 600
+	#[serde(default = "default_email_notification_delay")]
+	pub email_notification_delay: u64,
+
 	/// Allow receiving incoming read receipts from remote servers.
 	#[serde(default = "true_fn")]
 	pub allow_incoming_read_receipts: bool,
@@ -1534,6 +1701,26 @@ pub struct Config {
 	#[serde(default, with = "serde_regex")]
 	pub forbidden_remote_server_names: RegexSet,
 
+	/// Federate only with servers on the allow-list: those matching
+	/// `allowed_remote_server_names` and those allowed at runtime with the
+	/// `federation allow` admin command. Servers matching
+	/// `forbidden_remote_server_names` are always denied.
+	///
+	/// This is the initial setting; once changed with the
+	/// `federation enable-allowlist` or `federation disable-allowlist` admin
+	/// commands the setting stored in the database takes precedence.
+	#[serde(default)]
+	pub federation_allowlist: bool,
+
+	/// List of server names via regex patterns which are allowed to federate
+	/// with us when `federation_allowlist` is enabled.
+	///
+	/// example: ["^partner\.example\.com$", "\.example\.org$"]
+	///
+	/// default: []
+	#[serde(default, with = "serde_regex")]
+	pub allowed_remote_server_names: RegexSet,
+
 	/// List of forbidden server names via regex patterns that we will block all
 	/// outgoing federated room directory requests for. Useful for preventing
 	/// our users from wandering into bad servers or spaces.
@@ -1727,6 +1914,12 @@ pub struct Config {
 	#[serde(default)]
 	pub block_non_admin_invites: bool,
 
+	/// Contact the identity servers named in third-party invites over plain
+	/// HTTP instead of HTTPS. Only intended for testing against a local
+	/// identity server.
+	#[serde(default)]
+	pub identity_server_allow_http: bool,
+
 	/// Allow admins to enter commands in rooms other than "#admins" (admin
 	/// room) by prefixing your message with "\!admin" or "\\!admin" followed up
 	/// a normal tuwunel admin command. The reply will be publicly visible to
@@ -2054,6 +2247,51 @@ pub struct Config {
 	#[serde(default = "default_one_time_key_limit")]
 	pub one_time_key_limit: usize,
 
+	/// Interval in seconds between runs of the state garbage collector. The
+	/// collector deletes room states which are no longer referenced by any
+	/// event or room; new room states only wait while those are deleted. It
+	/// can also be run on demand with the `server collect-state-garbage` admin
+	/// command. Set this value to 0 to disable the periodic run.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub state_gc_interval: u64,
+
+	/// Interval in seconds between runs of the backfill worker. The worker
+	/// looks for gaps in the recent history of rooms with local users, such as
+	/// those left by `max_fetch_prev_events`, and fetches the missing events
+	/// from other servers in the room. A complete backfill of a room can be
+	/// run on demand with the `rooms backfill` admin command. Set this value
+	/// to 0 to disable the worker.
+	///
+	/// default: 0
+	#[serde(default)]
+	pub backfill_interval: u64,
+
+	/// Number of the most recent events of each room the backfill worker
+	/// inspects for missing previous events.
+	///
+	/// default: 200
+	#[serde(default = "default_backfill_scan_depth")]
+	pub backfill_scan_depth: usize,
+
+	/// Maximum number of federation requests the backfill worker makes on
+	/// each run.
+	///
+	/// default: 20
+	#[serde(default = "default_backfill_max_requests")]
+	pub backfill_max_requests: usize,
+
+	/// Join remote rooms with partial state (MSC3706). The resident server is
+	/// asked to omit room members from the `send_join` response, so the join
+	/// completes and the timeline is available without waiting for the full
+	/// state of large rooms. The remaining state is fetched in the
+	/// background; until then the member list of the room may be incomplete.
+	///
+	/// default: false
+	#[serde(default)]
+	pub partial_state_joins: bool,
+
 	// external structure; separate section
 	#[serde(default)]
 	pub blurhashing: BlurhashConfig,
@@ -2081,6 +2319,10 @@ pub struct Config {
 pub struct TlsConfig {
 	/// Path to a valid TLS certificate file.
 	///
+	/// The certificate and key are reloaded without a restart when either file
+	/// is modified or the configuration is reloaded. A pair which fails to
+	/// load or whose key does not match the certificate is not swapped in.
+	///
 	/// example: "/path/to/my/certificate.crt"
 	pub certs: Option<String>,
 
@@ -2092,6 +2334,49 @@ pub struct TlsConfig {
 	/// Whether to listen and allow for HTTP and HTTPS connections (insecure!)
 	#[serde(default)]
 	pub dual_protocol: bool,
+
+	/// URL of an ACME directory to automatically obtain and renew the
+	/// certificate from. When set, the issued certificate and its key are
+	/// written to the `certs` and `key` paths above, and the ACME account is
+	/// stored in the database. Requires tuwunel to be built with direct TLS
+	/// support ("direct_tls").
+	///
+	/// To test against a local ACME server such as Pebble, its root
+	/// certificate must be trusted by the system.
+	///
+	/// example: "https://acme-v02.api.letsencrypt.org/directory"
+	pub acme_directory: Option<Url>,
+
+	/// Domain names to request the certificate for. Defaults to the
+	/// server_name when empty.
+	///
+	/// default: []
+	#[serde(default)]
+	pub acme_domains: Vec<String>,
+
+	/// Contact URLs registered with the ACME account, for example
+	/// "mailto:admin@example.com".
+	///
+	/// default: []
+	#[serde(default)]
+	pub acme_contact: Vec<String>,
+
+	/// Challenge used to prove control of the domains to the ACME server.
+	///
+	/// "tls-alpn-01" is answered by the TLS listener itself, which must be
+	/// reachable on port 443. "http-01" is answered at
+	/// `/.well-known/acme-challenge/`, which must be reachable over plain HTTP
+	/// on port 80 (e.g. using `dual_protocol` or a port forward).
+	///
+	/// default: "tls-alpn-01"
+	#[serde(default = "default_acme_challenge")]
+	pub acme_challenge: String,
+
+	/// Number of days before the certificate expires at which it is renewed.
+	///
+	/// default: 30
+	#[serde(default = "default_acme_renew_days")]
+	pub acme_renew_days: u64,
 }
 
 #[allow(rustdoc::broken_intra_doc_links, rustdoc::bare_urls)]
@@ -2638,6 +2923,8 @@ fn default_stateinfo_cache_capacity() -> u32 { parallelism_scaled_u32(100) }
 
 fn default_roomid_spacehierarchy_cache_capacity() -> u32 { parallelism_scaled_u32(1000) }
 
+fn default_relations_cache_capacity() -> u32 { parallelism_scaled_u32(10_000) }
+
 fn default_dns_cache_entries() -> u32 { 32768 }
 
 fn default_dns_min_ttl() -> u64 { 60 * 180 }
@@ -2686,6 +2973,12 @@ fn default_appservice_idle_timeout() -> u64 { 300 }
 
 fn default_pusher_idle_timeout() -> u64 { 15 }
 
+fn default_pusher_coalesce_window_ms() -> u64 { 500 }
+
+fn default_pusher_retry_backoff_min() -> u64 { 10 }
+
+fn default_pusher_retry_backoff_limit() -> u64 { 3600 }
+
 fn default_max_fetch_prev_events() -> u16 { 192_u16 }
 
 fn default_tracing_flame_filter() -> String {
@@ -2720,12 +3013,18 @@ pub fn default_log() -> String {
 #[must_use]
 pub fn default_log_span_events() -> String { "none".into() }
 
+fn default_notification_history_max() -> usize { 500 }
+
+fn default_email_notification_delay() -> u64 { 600 }
+
 fn default_notification_push_path() -> String { "/_matrix/push/v1/notify".to_owned() }
 
 fn default_openid_token_ttl() -> u64 { 60 * 60 }
 
 fn default_login_token_ttl() -> u64 { 2 * 60 * 1000 }
 
+fn default_cross_signing_reset_window() -> u64 { 10 * 60 }
+
 fn default_turn_ttl() -> u64 { 60 * 60 * 24 }
 
 fn default_presence_idle_timeout_s() -> u64 { 5 * 60 }
@@ -2848,6 +3147,12 @@ fn parallelism_scaled(val: usize) -> usize { val.saturating_mul(sys::available_p
 
 fn default_trusted_server_batch_size() -> usize { 256 }
 
+fn default_remote_profile_cache_ttl() -> u64 { 86400 }
+
+fn default_signing_key_refresh_interval() -> u64 { 3600 }
+
+fn default_signing_key_refresh_window() -> u64 { 86400 }
+
 fn default_db_pool_workers() -> usize {
 	sys::available_parallelism()
 		.saturating_mul(4)
@@ -2874,6 +3179,8 @@ fn default_client_shutdown_timeout() -> u64 { 15 }
 
 fn default_sender_shutdown_timeout() -> u64 { 5 }
 
+fn default_federation_txn_cache_lifetime() -> u64 { 1800 }
+
 // blurhashing defaults recommended by https://blurha.sh/
 // 2^25
 fn default_blurhash_max_raw_size() -> u64 { 33_554_432 }
@@ -2907,3 +3214,11 @@ fn default_deprioritize_joins_through_servers() -> RegexSet {
 }
 
 fn default_one_time_key_limit() -> usize { 256 }
+
+fn default_backfill_scan_depth() -> usize { 200 }
+
+fn default_backfill_max_requests() -> usize { 20 }
+
+fn default_acme_challenge() -> String { "tls-alpn-01".to_owned() }
+
+fn default_acme_renew_days() -> u64 { 30 }
@@ -70,9 +70,9 @@ pub struct Pdu {
 	#[serde(default, skip_serializing_if = "Option::is_none")]
 	pub signatures: Option<Box<RawJsonValue>>,
 
-	//TODO: https://spec.matrix.org/v1.14/rooms/v11/#rejected-events
-	#[cfg(test)]
-	#[serde(default, skip_serializing)]
+	/// Set when the event failed authorization and was rejected; never part of
+	/// the event itself. See: https://spec.matrix.org/v1.14/rooms/v11/#rejected-events
+	#[serde(skip)]
 	pub rejected: bool,
 }
 
@@ -159,14 +159,9 @@ fn prev_events(&self) -> impl DoubleEndedIterator<Item = &EventId> + Clone + Sen
 	#[inline]
 	fn redacts(&self) -> Option<&EventId> { self.redacts.as_deref() }
 
-	#[cfg(test)]
 	#[inline]
 	fn rejected(&self) -> bool { self.rejected }
 
-	#[cfg(not(test))]
-	#[inline]
-	fn rejected(&self) -> bool { false }
-
 	#[inline]
 	fn room_id(&self) -> &RoomId { &self.room_id }
 
@@ -230,14 +225,9 @@ fn prev_events(&self) -> impl DoubleEndedIterator<Item = &EventId> + Clone + Sen
 	#[inline]
 	fn redacts(&self) -> Option<&EventId> { self.redacts.as_deref() }
 
-	#[cfg(test)]
 	#[inline]
 	fn rejected(&self) -> bool { self.rejected }
 
-	#[cfg(not(test))]
-	#[inline]
-	fn rejected(&self) -> bool { false }
-
 	#[inline]
 	fn room_id(&self) -> &RoomId { &self.room_id }
 
@@ -73,3 +73,29 @@ pub fn add_relation(&mut self, name: &str, pdu: Option<&Pdu>) -> Result {
 
 	Ok(())
 }
+
+/// Sets the given bundled aggregations in `unsigned.m.relations`, replacing
+/// any of the same relation type.
+#[implement(Pdu)]
+pub fn set_relations(&mut self, relations: serde_json::Map<String, JsonValue>) -> Result {
+	use serde_json::Map;
+
+	let mut unsigned: Map<String, JsonValue> = self
+		.unsigned
+		.as_deref()
+		.map(RawJsonValue::get)
+		.map_or_else(|| Ok(Map::new()), serde_json::from_str)
+		.map_err(|e| err!(Database("Invalid unsigned in pdu event: {e}")))?;
+
+	if let Some(object) = unsigned
+		.entry("m.relations")
+		.or_insert(JsonValue::Object(Map::new()))
+		.as_object_mut()
+	{
+		object.extend(relations);
+	}
+
+	self.unsigned = Some(to_raw_value(&unsigned)?);
+
+	Ok(())
+}
@@ -60,12 +60,28 @@ pub async fn auth_check<FetchEvent, EventFut, FetchState, StateFut, Pdu>(
 	let independent = check_state_independent_auth_rules(rules, incoming_event, fetch_event);
 
 	match try_join(independent, dependent).await {
+		| Err(e) if is_lookup_error(&e) => Err(e),
 		| Err(e) if matches!(e, Error::Request(InvalidParam, ..)) => Err(e),
 		| Err(e) => Err!(Request(Forbidden("Auth check failed: {e}"))),
 		| Ok(_) => Ok(()),
 	}
 }
 
+/// Failing to find the events the rules are checked against is not a
+/// violation of the rules; the check may pass once they are found.
+fn is_lookup_error(e: &Error) -> bool {
+	e.is_not_found()
+		|| matches!(
+			e,
+			Error::Database(_)
+				| Error::Io(_)
+				| Error::JoinError(_)
+				| Error::Federation(..)
+				| Error::Reqwest(_)
+				| Error::BadServerResponse(_)
+		)
+}
+
 /// Check whether the incoming event passes the state-independent [authorization
 /// rules] for the given room version rules.
 ///
@@ -1,4 +1,5 @@
 use ruma::{
+	api::client::error::ErrorKind,
 	events::{
 		TimelineEventType,
 		room::{
@@ -16,7 +17,7 @@
 
 use self::room_power_levels::default_room_power_levels;
 use super::{
-	check_room_create, check_room_redaction, check_state_dependent_auth_rules,
+	auth_check, check_room_create, check_room_redaction, check_state_dependent_auth_rules,
 	check_state_independent_auth_rules,
 	events::{RoomCreateEvent, RoomPowerLevelsEvent},
 	test_utils::{
@@ -26,7 +27,10 @@
 		to_pdu_event,
 	},
 };
-use crate::matrix::{EventHash, PduEvent, StateKey};
+use crate::{
+	Error, Result, err,
+	matrix::{EventHash, PduEvent, StateKey},
+};
 
 #[test]
 fn valid_room_create() {
@@ -1053,3 +1057,70 @@ async fn rejected_room_create_in_fetch_event() {
 	.await
 	.unwrap_err();
 }
+
+#[tokio::test]
+async fn auth_check_forbids_rule_violation() {
+	let _guard = init_subscriber();
+
+	let incoming_event = to_pdu_event(
+		"HELLO",
+		ella(),
+		TimelineEventType::RoomMessage,
+		None,
+		to_raw_json_value(&RoomMessageEventContent::text_plain("Hi!")).unwrap(),
+		&["CREATE", "IPOWER"],
+		&["IPOWER"],
+	);
+
+	let init_events = INITIAL_EVENTS();
+	let auth_events = TestStateMap::new(&init_events);
+	let fetch_state = auth_events.fetch_state_fn();
+
+	// Sender not in room is a violation of the rules.
+	let error = auth_check(
+		&RoomVersionRules::V6,
+		&incoming_event,
+		&async |event_id| {
+			init_events
+				.get(&event_id)
+				.cloned()
+				.ok_or_else(not_found)
+		},
+		&fetch_state,
+	)
+	.await
+	.unwrap_err();
+
+	assert!(matches!(error, Error::Request(ErrorKind::Forbidden { .. }, ..)));
+}
+
+#[tokio::test]
+async fn auth_check_propagates_lookup_error() {
+	let _guard = init_subscriber();
+
+	let incoming_event = to_pdu_event(
+		"HELLO",
+		alice(),
+		TimelineEventType::RoomMessage,
+		None,
+		to_raw_json_value(&RoomMessageEventContent::text_plain("Hi!")).unwrap(),
+		&["CREATE", "IMA", "IPOWER"],
+		&["IPOWER"],
+	);
+
+	let init_events = INITIAL_EVENTS();
+	let auth_events = TestStateMap::new(&init_events);
+	let fetch_state = auth_events.fetch_state_fn();
+
+	// Failing to read the auth events is not a violation of the rules.
+	let error = auth_check(
+		&RoomVersionRules::V6,
+		&incoming_event,
+		&async |_event_id| -> Result<PduEvent> { Err(err!(Database("Test database error"))) },
+		&fetch_state,
+	)
+	.await
+	.unwrap_err();
+
+	assert!(matches!(error, Error::Database(_)));
+}
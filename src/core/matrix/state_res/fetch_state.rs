@@ -37,7 +37,10 @@ async fn room_create_event(&self) -> Result<RoomCreateEvent<Pdu>> {
 		self(StateEventType::RoomCreate, "".into())
 			.await
 			.map(RoomCreateEvent::new)
-			.map_err(|e| err!("no `m.room.create` event in current state: {e}"))
+			.map_err(|e| match e {
+				| e if e.is_not_found() => err!("no `m.room.create` event in current state: {e}"),
+				| e => e,
+			})
 	}
 
 	async fn user_membership(&self, user_id: &UserId) -> Result<MembershipState> {
@@ -58,7 +61,11 @@ async fn join_rule(&self) -> Result<JoinRule> {
 		self(StateEventType::RoomJoinRules, "".into())
 			.await
 			.map(RoomJoinRulesEvent::new)
-			.map_err(|e| err!("no `m.room.join_rules` event in current state: {e}"))?
+			.map_err(|e| match e {
+				| e if e.is_not_found() =>
+					err!("no `m.room.join_rules` event in current state: {e}"),
+				| e => e,
+			})?
 			.join_rule()
 	}
 
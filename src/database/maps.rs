@@ -49,10 +49,30 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "bannedroomids",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "crosssigningresettoken_userid",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "disabledroomids",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "emailexpiresatsid",
+		..descriptor::SEQUENTIAL_SMALL
+	},
+	Descriptor {
+		name: "emailsecretaddress_sid",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "emailsid_validation",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "emailtoken_senderkey",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "eventid_outlierpdu",
 		cache_disp: CacheDisp::SharedWith("pduid_pdu"),
@@ -71,6 +91,11 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		index_size: 512,
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "eventid_rejection",
+		key_size_hint: Some(48),
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "eventid_shorteventid",
 		cache_disp: CacheDisp::Unique,
@@ -133,6 +158,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "publicroomids",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "purgedroomids",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "pushkey_deviceid",
 		..descriptor::RANDOM_SMALL
@@ -165,10 +194,19 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "roomid_joinedcount",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "roomid_partialstate",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomid_pduleaves",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "roomid_resynccount",
+		val_size_hint: Some(8),
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomid_shortroomid",
 		val_size_hint: Some(8),
@@ -179,6 +217,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		val_size_hint: Some(8),
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "roomideventid_deferred",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomserverids",
 		..descriptor::RANDOM_SMALL
@@ -226,10 +268,22 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "roomusertype_roomuserdataid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "senderkey_emailstate",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "senderkey_pusher",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "senderkey_pushhealth",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "server_signedkeys",
+		..descriptor::RANDOM
+	},
 	Descriptor {
 		name: "server_signingkeys",
 		..descriptor::RANDOM
@@ -246,10 +300,18 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "servername_educount",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "servername_health",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "servername_override",
 		..descriptor::RANDOM_SMALL_CACHE
 	},
+	Descriptor {
+		name: "servername_policy",
+		..descriptor::RANDOM_SMALL_CACHE
+	},
 	Descriptor {
 		name: "servernameevent_data",
 		cache_disp: CacheDisp::Unique,
@@ -310,6 +372,14 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		val_size_hint: Some(8),
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "targetkey_annotationcount",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "targetkeysender_annotation",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "threadid_userids",
 		..descriptor::SEQUENTIAL_SMALL
@@ -337,6 +407,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "url_previews",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "userdevicealgorithm_fallbackkey",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userdeviceid_metadata",
 		..descriptor::RANDOM_SMALL
@@ -369,6 +443,14 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "userid_blurhash",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_crosssigningreset",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userid_dehydrateddevice",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_devicelistversion",
 		..descriptor::RANDOM_SMALL
@@ -397,6 +479,10 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "userid_presenceid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_remoteprofilefetched",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_selfsigningkeyid",
 		..descriptor::RANDOM_SMALL
@@ -405,6 +491,14 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "userid_usersigningkeyid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "useridaddress_threepid",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "useridcount_notification",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "useridprofilekey_value",
 		..descriptor::RANDOM_SMALL
@@ -433,6 +527,14 @@ pub(super) fn open_list(engine: &Arc<Engine>, maps: &[Descriptor]) -> Result<Map
 		name: "userroomid_notificationcount",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "userroomthreadid_highlightcount",
+		..descriptor::RANDOM_SMALL
+	},
+	Descriptor {
+		name: "userroomthreadid_notificationcount",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "roomid_maxremotepowerlevel",
 		..descriptor::RANDOM_SMALL
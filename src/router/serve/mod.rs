@@ -33,7 +33,7 @@ pub(super) async fn serve(
 		unix::serve(server, app, shutdown).await
 	} else if config.tls.certs.is_some() {
 		#[cfg(feature = "direct_tls")]
-		return tls::serve(server, app, handle, addrs).await;
+		return tls::serve(&services, app, handle, addrs).await;
 
 		#[cfg(not(feature = "direct_tls"))]
 		return tuwunel_core::Err!(Config(
@@ -1,6 +1,7 @@
 use std::{
 	net::SocketAddr,
 	sync::{Arc, atomic::Ordering},
+	time::{Duration, SystemTime},
 };
 
 use axum::Router;
@@ -9,15 +10,32 @@
 	ServerExt,
 	axum_server::{bind_rustls, tls_rustls::RustlsConfig},
 };
-use tokio::task::JoinSet;
-use tuwunel_core::{Result, Server, debug, debug_info, err, info, warn};
+use rustls::{
+	crypto::CryptoProvider,
+	pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+	sign::CertifiedKey,
+};
+use tokio::{
+	fs,
+	sync::broadcast::error::RecvError,
+	task::JoinSet,
+	time::{MissedTickBehavior, interval},
+};
+use tuwunel_core::{
+	Err, Result, Server, config::TlsConfig, debug, debug_info, err, error, info, warn,
+};
+use tuwunel_service::{Services, config::RELOADED};
+
+/// Interval at which the certificate and key files are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_secs(60);
 
 pub(super) async fn serve(
-	server: &Arc<Server>,
+	services: &Arc<Services>,
 	app: Router,
 	handle: ServerHandle,
 	addrs: Vec<SocketAddr>,
 ) -> Result {
+	let server = &services.server;
 	let tls = &server.config.tls;
 	let certs = tls.certs.as_ref().ok_or_else(|| {
 		err!(Config("tls.certs", "Missing required value in tls config section"))
@@ -38,9 +56,21 @@ pub(super) async fn serve(
 		 tuwunel directly with TLS."
 	);
 	debug!("Using direct TLS. Certificate path {certs} and certificate private key path {key}",);
-	let conf = RustlsConfig::from_pem_file(certs, key)
-		.await
-		.map_err(|e| err!(Config("tls", "Failed to load certificates or key: {e}")))?;
+	let conf = if services.acme.enabled() {
+		debug!("Certificate is provisioned by ACME");
+		RustlsConfig::from_config(services.acme.resolver.server_config())
+	} else {
+		RustlsConfig::from_pem_file(certs, key)
+			.await
+			.map_err(|e| err!(Config("tls", "Failed to load certificates or key: {e}")))?
+	};
+
+	// The ACME resolver serves renewed certificates itself.
+	let reload_task = (!services.acme.enabled()).then(|| {
+		server
+			.runtime()
+			.spawn(reloader(server.clone(), conf.clone()))
+	});
 
 	let mut join_set = JoinSet::new();
 	let app = app.into_make_service_with_connect_info::<SocketAddr>();
@@ -75,6 +105,9 @@ pub(super) async fn serve(
 	}
 
 	while join_set.join_next().await.is_some() {}
+	if let Some(reload_task) = reload_task {
+		reload_task.abort();
+	}
 
 	let handle_active = server
 		.metrics
@@ -98,3 +131,97 @@ pub(super) async fn serve(
 
 	Ok(())
 }
+
+/// Reloads the certificate and key into the running listeners after the
+/// configuration is reloaded or when either file is modified. The new pair is
+/// validated first; on failure the listeners keep serving the prior one.
+async fn reloader(server: Arc<Server>, conf: RustlsConfig) {
+	let mut signals = server.signal.subscribe();
+	let mut watch = interval(WATCH_INTERVAL);
+	watch.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+	let mut loaded = TlsFiles::stat(&server.config.tls).await;
+	while server.running() {
+		let forced = tokio::select! {
+			() = server.until_shutdown() => break,
+			_ = watch.tick() => false,
+			sig = signals.recv() => match sig {
+				| Ok(sig) if sig == RELOADED => true,
+				| Ok(_) | Err(RecvError::Lagged(_)) => continue,
+				| Err(RecvError::Closed) => break,
+			},
+		};
+
+		let current = TlsFiles::stat(&server.config.tls).await;
+		if !forced && current == loaded {
+			continue;
+		}
+
+		match current.reload(&conf).await {
+			| Ok(()) => info!("Reloaded TLS certificate {}", current.certs),
+			| Err(e) => error!("Failed to reload TLS certificate: {e}"),
+		}
+
+		loaded = current;
+	}
+}
+
+#[derive(Debug, Default, Eq, PartialEq)]
+struct TlsFiles {
+	certs: String,
+	key: String,
+	certs_modified: Option<SystemTime>,
+	key_modified: Option<SystemTime>,
+}
+
+impl TlsFiles {
+	async fn stat(tls: &TlsConfig) -> Self {
+		let (Some(certs), Some(key)) = (tls.certs.clone(), tls.key.clone()) else {
+			return Self::default();
+		};
+
+		Self {
+			certs_modified: modified(&certs).await,
+			key_modified: modified(&key).await,
+			certs,
+			key,
+		}
+	}
+
+	async fn reload(&self, conf: &RustlsConfig) -> Result {
+		if self.certs.is_empty() || self.key.is_empty() {
+			return Err!(Config("tls", "Missing certificate or key path in tls config section"));
+		}
+
+		self.validate()?;
+		conf.reload_from_pem_file(&self.certs, &self.key)
+			.await
+			.map_err(|e| err!(Config("tls", "Failed to load certificates or key: {e}")))
+	}
+
+	/// Checks the certificate chain and private key can be parsed and that the
+	/// key belongs to the leaf certificate.
+	fn validate(&self) -> Result {
+		let chain = CertificateDer::pem_file_iter(&self.certs)
+			.and_then(Iterator::collect::<Result<Vec<_>, _>>)
+			.map_err(|e| err!(Config("tls.certs", "Failed to parse certificates: {e}")))?;
+
+		let key = PrivateKeyDer::from_pem_file(&self.key)
+			.map_err(|e| err!(Config("tls.key", "Failed to parse private key: {e}")))?;
+
+		let provider = CryptoProvider::get_default()
+			.ok_or_else(|| err!("No default rustls crypto provider installed"))?;
+
+		CertifiedKey::from_der(chain, key, provider)
+			.map_err(|e| err!(Config("tls", "Certificate does not match private key: {e}")))?;
+
+		Ok(())
+	}
+}
+
+async fn modified(path: &str) -> Option<SystemTime> {
+	fs::metadata(path)
+		.await
+		.and_then(|metadata| metadata.modified())
+		.ok()
+}
@@ -0,0 +1,315 @@
+#![cfg(feature = "direct_tls")]
+
+use std::{
+	collections::HashMap,
+	sync::RwLock,
+	time::{Duration, SystemTime},
+};
+
+use instant_acme::{
+	Account, AccountCredentials, AuthorizationStatus, ChallengeType, Identifier, NewAccount,
+	NewOrder, Order, OrderStatus,
+};
+use rcgen::{CertificateParams, DistinguishedName, KeyPair};
+use tokio::{fs, io::AsyncWriteExt, time::sleep};
+use tuwunel_core::{Err, Error, Result, debug, debug_info, err, error, implement, info};
+
+use super::Resolver;
+
+/// Delay before retrying a failed issuance.
+const RETRY_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Number of times the ACME server is polled for the progress of an order.
+const POLL_ATTEMPTS: usize = 10;
+
+/// Loads the current certificate, then issues a new one whenever it is missing
+/// or about to expire.
+#[implement(super::Service)]
+pub(super) async fn renewal_worker(&self) -> Result {
+	let server = &self.services.server;
+	let tls = &server.config.tls;
+	let (certs, key) = (tls.certs.clone(), tls.key.clone());
+	if let (Some(certs), Some(key)) = (certs, key) {
+		self.resolver
+			.load(&certs, &key)
+			.map_err(|e| debug_info!("No usable certificate yet: {e}"))
+			.ok();
+	}
+
+	let mut retry = false;
+	while server.running() {
+		let delay = if retry { RETRY_INTERVAL } else { self.renewal_delay() };
+		if !delay.is_zero() {
+			debug!(?delay, "Next certificate renewal");
+			tokio::select! {
+				() = server.until_shutdown() => break,
+				() = sleep(delay) => {},
+			}
+		}
+
+		retry = self
+			.issue()
+			.await
+			.inspect_err(|e| error!("Failed to obtain certificate via ACME: {e}"))
+			.is_err();
+	}
+
+	Ok(())
+}
+
+/// Time remaining until the current certificate is due for renewal.
+#[implement(super::Service)]
+fn renewal_delay(&self) -> Duration {
+	let renew_days = self.services.server.config.tls.acme_renew_days;
+	let renew_before = Duration::from_secs(renew_days.saturating_mul(86_400));
+
+	self.resolver
+		.not_after()
+		.and_then(|not_after| not_after.checked_sub(renew_before))
+		.and_then(|renew_at| renew_at.duration_since(SystemTime::now()).ok())
+		.unwrap_or_default()
+}
+
+/// Orders a certificate for the configured domains, answers the challenges,
+/// then writes the certificate chain and key to the configured paths and
+/// begins serving them.
+#[implement(super::Service)]
+#[tracing::instrument(skip(self), level = "info")]
+pub async fn issue(&self) -> Result {
+	let config = &self.services.server.config;
+	let tls = &config.tls;
+	let (Some(directory), Some(certs), Some(key)) = (&tls.acme_directory, &tls.certs, &tls.key)
+	else {
+		return Err!(Config("tls.acme_directory", "ACME is not configured."));
+	};
+
+	let domains = if tls.acme_domains.is_empty() {
+		vec![config.server_name.to_string()]
+	} else {
+		tls.acme_domains.clone()
+	};
+
+	let challenge_type = match tls.acme_challenge.as_str() {
+		| "http-01" => ChallengeType::Http01,
+		| _ => ChallengeType::TlsAlpn01,
+	};
+
+	let account = self
+		.account(directory.as_str(), &tls.acme_contact)
+		.await?;
+
+	let identifiers: Vec<_> = domains
+		.iter()
+		.cloned()
+		.map(Identifier::Dns)
+		.collect();
+
+	let mut order = account
+		.new_order(&NewOrder { identifiers: &identifiers })
+		.await
+		.map_err(acme_error)?;
+
+	info!(?domains, ?challenge_type, "Ordering certificate");
+	let authorized = authorize(&mut order, &challenge_type, &self.http01, &self.resolver).await;
+
+	self.http01
+		.write()
+		.expect("locked for writing")
+		.clear();
+
+	self.resolver.clear_challenges();
+	authorized?;
+
+	let (chain, key_pem) = finalize(&mut order, domains).await?;
+	write_certificate(certs, key, &chain, &key_pem).await?;
+	self.resolver.load(certs, key)?;
+
+	info!("Obtained certificate; written to {certs}");
+
+	Ok(())
+}
+
+/// Submits a certificate request for a new key once the order is ready and
+/// returns the issued certificate chain along with that key, both in PEM.
+pub(super) async fn finalize(
+	order: &mut Order,
+	domains: Vec<String>,
+) -> Result<(String, String)> {
+	let key_pair = KeyPair::generate().map_err(|e| err!("Failed to generate key: {e}"))?;
+	let mut params = CertificateParams::new(domains)
+		.map_err(|e| err!("Failed to create certificate request: {e}"))?;
+
+	params.distinguished_name = DistinguishedName::new();
+	let csr = params
+		.serialize_request(&key_pair)
+		.map_err(|e| err!("Failed to create certificate request: {e}"))?;
+
+	order
+		.finalize(csr.der())
+		.await
+		.map_err(acme_error)?;
+
+	let chain = poll_certificate(order).await?;
+
+	Ok((chain, key_pair.serialize_pem()))
+}
+
+/// Replaces the certificate chain and key. Both are written to temporary files
+/// beside their destination and renamed into place, the certificate last, so
+/// neither is ever read partially written.
+pub(super) async fn write_certificate(
+	certs: &str,
+	key: &str,
+	chain: &str,
+	key_pem: &str,
+) -> Result {
+	let certs_tmp = format!("{certs}.tmp");
+	let key_tmp = format!("{key}.tmp");
+
+	write_key(&key_tmp, key_pem).await?;
+	write_chain(&certs_tmp, chain).await?;
+
+	fs::rename(&key_tmp, key).await?;
+	fs::rename(&certs_tmp, certs).await?;
+
+	Ok(())
+}
+
+async fn write_chain(path: &str, chain: &str) -> Result {
+	let mut file = fs::File::create(path).await?;
+
+	file.write_all(chain.as_bytes()).await?;
+	file.sync_all().await?;
+
+	Ok(())
+}
+
+/// Writes the private key readable by its owner only.
+async fn write_key(path: &str, pem: &str) -> Result {
+	let mut options = fs::OpenOptions::new();
+	options.write(true).create(true).truncate(true);
+
+	#[cfg(unix)]
+	options.mode(0o600);
+
+	let mut file = options.open(path).await?;
+
+	// The mode only applies when the file is created.
+	#[cfg(unix)]
+	{
+		use std::{fs::Permissions, os::unix::fs::PermissionsExt};
+
+		file.set_permissions(Permissions::from_mode(0o600))
+			.await?;
+	}
+
+	file.write_all(pem.as_bytes()).await?;
+	file.sync_all().await?;
+
+	Ok(())
+}
+
+/// Answers the pending authorizations of the order and waits for the ACME
+/// server to validate them.
+pub(super) async fn authorize(
+	order: &mut Order,
+	challenge_type: &ChallengeType,
+	http01: &RwLock<HashMap<String, String>>,
+	resolver: &Resolver,
+) -> Result {
+	let authorizations = order.authorizations().await.map_err(acme_error)?;
+
+	for authorization in &authorizations {
+		let Identifier::Dns(domain) = &authorization.identifier;
+		match authorization.status {
+			| AuthorizationStatus::Pending => {},
+			| AuthorizationStatus::Valid => continue,
+			| status => return Err!("Authorization for {domain} is {status:?}"),
+		}
+
+		let challenge = authorization
+			.challenges
+			.iter()
+			.find(|challenge| challenge.r#type == *challenge_type)
+			.ok_or_else(|| err!("No {challenge_type:?} challenge offered for {domain}"))?;
+
+		let key_authorization = order.key_authorization(challenge);
+		match challenge_type {
+			| ChallengeType::Http01 => {
+				http01
+					.write()
+					.expect("locked for writing")
+					.insert(challenge.token.clone(), key_authorization.as_str().to_owned());
+			},
+			| _ => resolver.add_challenge(domain, key_authorization.digest().as_ref())?,
+		}
+
+		debug!(?domain, ?challenge_type, "Challenge ready");
+		order
+			.set_challenge_ready(&challenge.url)
+			.await
+			.map_err(acme_error)?;
+	}
+
+	let mut delay = Duration::from_millis(250);
+	for _ in 0..POLL_ATTEMPTS {
+		sleep(delay).await;
+		delay = delay.saturating_mul(2);
+
+		let state = order.refresh().await.map_err(acme_error)?;
+		match state.status {
+			| OrderStatus::Ready | OrderStatus::Valid => return Ok(()),
+			| OrderStatus::Invalid => return Err!("Order was rejected by the ACME server."),
+			| _ => continue,
+		}
+	}
+
+	Err!("Timed out waiting for the ACME server to validate the challenges.")
+}
+
+async fn poll_certificate(order: &mut Order) -> Result<String> {
+	let mut delay = Duration::from_millis(250);
+	for _ in 0..POLL_ATTEMPTS {
+		if let Some(chain) = order.certificate().await.map_err(acme_error)? {
+			return Ok(chain);
+		}
+
+		sleep(delay).await;
+		delay = delay.saturating_mul(2);
+	}
+
+	Err!("Timed out waiting for the ACME server to issue the certificate.")
+}
+
+/// Restores the ACME account registered with `directory` from the database or
+/// registers a new one.
+#[implement(super::Service)]
+async fn account(&self, directory: &str, contact: &[String]) -> Result<Account> {
+	let key = format!("acme_account:{directory}");
+	if let Ok(credentials) = self.db.global.get(&key).await {
+		let credentials: AccountCredentials = serde_json::from_slice(&credentials)?;
+		return Account::from_credentials(credentials)
+			.await
+			.map_err(acme_error);
+	}
+
+	let contact: Vec<&str> = contact.iter().map(String::as_str).collect();
+	let new_account = NewAccount {
+		contact: &contact,
+		terms_of_service_agreed: true,
+		only_return_existing: false,
+	};
+
+	let (account, credentials) = Account::create(&new_account, directory, None)
+		.await
+		.map_err(acme_error)?;
+
+	self.db
+		.global
+		.insert(&key, serde_json::to_vec(&credentials)?);
+
+	info!(?directory, "Registered ACME account");
+	Ok(account)
+}
+
+fn acme_error(e: instant_acme::Error) -> Error { err!("ACME request failed: {e}") }
@@ -0,0 +1,94 @@
+mod issue;
+mod resolver;
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use tuwunel_core::{Err, Result, implement};
+#[cfg(feature = "direct_tls")]
+use tuwunel_database::Map;
+
+#[cfg(feature = "direct_tls")]
+pub use self::resolver::Resolver;
+
+/// Automatic certificate provisioning for direct TLS using the ACME protocol.
+pub struct Service {
+	services: Arc<crate::services::OnceServices>,
+
+	/// Key authorizations of pending http-01 challenges by token.
+	http01: RwLock<HashMap<String, String>>,
+
+	/// Certificate resolver installed into the TLS listeners when ACME is
+	/// enabled; serves the issued certificate and tls-alpn-01 challenges.
+	#[cfg(feature = "direct_tls")]
+	pub resolver: Arc<Resolver>,
+
+	#[cfg(feature = "direct_tls")]
+	db: Data,
+}
+
+#[cfg(feature = "direct_tls")]
+struct Data {
+	global: Arc<Map>,
+}
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: args.services.clone(),
+			http01: RwLock::default(),
+			#[cfg(feature = "direct_tls")]
+			resolver: Arc::default(),
+			#[cfg(feature = "direct_tls")]
+			db: Data { global: args.db["global"].clone() },
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		if !self.enabled() {
+			return Ok(());
+		}
+
+		if cfg!(not(feature = "direct_tls")) {
+			return Err!(FeatureDisabled("direct_tls"));
+		}
+
+		#[cfg(feature = "direct_tls")]
+		self.renewal_worker().await?;
+
+		Ok(())
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Whether the certificate is provisioned by ACME.
+#[implement(Service)]
+#[inline]
+#[must_use]
+pub fn enabled(&self) -> bool {
+	self.services
+		.server
+		.config
+		.tls
+		.acme_directory
+		.is_some()
+}
+
+/// Returns the key authorization answering the http-01 challenge `token`, if
+/// that challenge is pending.
+#[implement(Service)]
+#[must_use]
+pub fn http01_key_authorization(&self, token: &str) -> Option<String> {
+	self.http01
+		.read()
+		.expect("locked for reading")
+		.get(token)
+		.cloned()
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "direct_tls")]
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, RwLock},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rcgen::{CertificateParams, CustomExtension, KeyPair};
+use rustls::{
+	ServerConfig,
+	crypto::aws_lc_rs::default_provider,
+	pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, pem::PemObject},
+	server::{ClientHello, ResolvesServerCert},
+	sign::CertifiedKey,
+};
+use tuwunel_core::{Result, err};
+
+/// ALPN protocol negotiated by ACME servers validating a tls-alpn-01
+/// challenge (RFC 8737).
+const ACME_TLS_ALPN_NAME: &[u8] = b"acme-tls/1";
+
+/// Selects the certificate presented by the TLS listeners: the challenge
+/// certificate for the requested name during tls-alpn-01 validation, otherwise
+/// the certificate issued by ACME.
+#[derive(Debug, Default)]
+pub struct Resolver {
+	certificate: RwLock<Option<Certificate>>,
+	challenges: RwLock<HashMap<String, Arc<CertifiedKey>>>,
+}
+
+#[derive(Debug)]
+struct Certificate {
+	key: Arc<CertifiedKey>,
+	not_after: SystemTime,
+}
+
+impl ResolvesServerCert for Resolver {
+	fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+		let challenge = client_hello
+			.alpn()
+			.into_iter()
+			.flatten()
+			.eq([ACME_TLS_ALPN_NAME]);
+
+		if challenge {
+			let name = client_hello.server_name()?;
+			return self
+				.challenges
+				.read()
+				.expect("locked for reading")
+				.get(name)
+				.cloned();
+		}
+
+		self.certificate
+			.read()
+			.expect("locked for reading")
+			.as_ref()
+			.map(|certificate| certificate.key.clone())
+	}
+}
+
+impl Resolver {
+	/// Builds the rustls configuration for the TLS listeners using this
+	/// resolver.
+	#[must_use]
+	pub fn server_config(self: &Arc<Self>) -> Arc<ServerConfig> {
+		let mut config = ServerConfig::builder()
+			.with_no_client_auth()
+			.with_cert_resolver(self.clone());
+
+		config.alpn_protocols =
+			vec![b"h2".to_vec(), b"http/1.1".to_vec(), ACME_TLS_ALPN_NAME.to_vec()];
+
+		Arc::new(config)
+	}
+
+	/// Time at which the current certificate expires, if one is loaded.
+	pub(super) fn not_after(&self) -> Option<SystemTime> {
+		self.certificate
+			.read()
+			.expect("locked for reading")
+			.as_ref()
+			.map(|certificate| certificate.not_after)
+	}
+
+	/// Loads the certificate chain and key from PEM files, replacing the
+	/// certificate being served.
+	pub(super) fn load(&self, certs: &str, key: &str) -> Result {
+		let chain = CertificateDer::pem_file_iter(certs)
+			.and_then(Iterator::collect::<Result<Vec<_>, _>>)
+			.map_err(|e| err!(Config("tls.certs", "Failed to parse certificates: {e}")))?;
+
+		let not_after = chain
+			.first()
+			.ok_or_else(|| err!(Config("tls.certs", "No certificate found in {certs:?}")))
+			.and_then(|leaf| {
+				x509_parser::parse_x509_certificate(leaf)
+					.map_err(|e| err!(Config("tls.certs", "Failed to parse certificate: {e}")))
+			})
+			.map(|(_, leaf)| leaf.validity().not_after.timestamp())?;
+
+		let key = PrivateKeyDer::from_pem_file(key)
+			.map_err(|e| err!(Config("tls.key", "Failed to parse private key: {e}")))?;
+
+		let key = CertifiedKey::from_der(chain, key, &default_provider())
+			.map_err(|e| err!(Config("tls", "Certificate does not match private key: {e}")))?;
+
+		let not_after = UNIX_EPOCH + Duration::from_secs(not_after.try_into().unwrap_or(0));
+		self.certificate
+			.write()
+			.expect("locked for writing")
+			.replace(Certificate { key: Arc::new(key), not_after });
+
+		Ok(())
+	}
+
+	/// Generates and serves the tls-alpn-01 challenge certificate for `domain`
+	/// carrying the SHA-256 `digest` of the key authorization.
+	pub(super) fn add_challenge(&self, domain: &str, digest: &[u8]) -> Result {
+		let mut params = CertificateParams::new(vec![domain.to_owned()])
+			.map_err(|e| err!("Failed to create challenge certificate: {e}"))?;
+
+		params.custom_extensions = vec![CustomExtension::new_acme_identifier(digest)];
+		let key_pair =
+			KeyPair::generate().map_err(|e| err!("Failed to generate challenge key: {e}"))?;
+
+		let cert = params
+			.self_signed(&key_pair)
+			.map_err(|e| err!("Failed to sign challenge certificate: {e}"))?;
+
+		let key = PrivatePkcs8KeyDer::from(key_pair.serialize_der());
+		let key =
+			CertifiedKey::from_der(vec![cert.der().clone()], key.into(), &default_provider())
+				.map_err(|e| err!("Failed to load challenge certificate: {e}"))?;
+
+		self.challenges
+			.write()
+			.expect("locked for writing")
+			.insert(domain.to_owned(), Arc::new(key));
+
+		Ok(())
+	}
+
+	pub(super) fn clear_challenges(&self) {
+		self.challenges
+			.write()
+			.expect("locked for writing")
+			.clear();
+	}
+}
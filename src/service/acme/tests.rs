@@ -0,0 +1,162 @@
+#![cfg(feature = "direct_tls")]
+
+use std::{env, fs, path::PathBuf, sync::RwLock};
+
+use instant_acme::{Account, ChallengeType, Identifier, NewAccount, NewOrder};
+use rcgen::{CertificateParams, KeyPair};
+use tuwunel_core::utils;
+
+use super::{
+	Resolver,
+	issue::{authorize, finalize, write_certificate},
+};
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+	fn new() -> Self {
+		let path = env::temp_dir().join(format!("tuwunel-acme-{}", utils::random_string(8)));
+		fs::create_dir_all(&path).unwrap();
+		Self(path)
+	}
+
+	fn file(&self, name: &str) -> String { self.0.join(name).to_str().unwrap().to_owned() }
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) { fs::remove_dir_all(&self.0).ok(); }
+}
+
+fn self_signed(domain: &str) -> (String, String) {
+	let key_pair = KeyPair::generate().unwrap();
+	let cert = CertificateParams::new(vec![domain.to_owned()])
+		.unwrap()
+		.self_signed(&key_pair)
+		.unwrap();
+
+	(cert.pem(), key_pair.serialize_pem())
+}
+
+#[tokio::test]
+async fn write_certificate_replaces_files() {
+	let dir = TempDir::new();
+	let (certs, key) = (dir.file("cert.pem"), dir.file("key.pem"));
+	fs::write(&certs, "old chain").unwrap();
+	fs::write(&key, "old key").unwrap();
+
+	write_certificate(&certs, &key, "new chain", "new key")
+		.await
+		.unwrap();
+
+	assert_eq!(fs::read_to_string(&certs).unwrap(), "new chain");
+	assert_eq!(fs::read_to_string(&key).unwrap(), "new key");
+
+	let mut names: Vec<_> = fs::read_dir(&dir.0)
+		.unwrap()
+		.map(|entry| entry.unwrap().file_name())
+		.collect();
+
+	names.sort();
+	assert_eq!(names, ["cert.pem", "key.pem"]);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn write_certificate_key_is_private() {
+	use std::os::unix::fs::PermissionsExt;
+
+	let dir = TempDir::new();
+	let (certs, key) = (dir.file("cert.pem"), dir.file("key.pem"));
+	fs::write(&key, "old key").unwrap();
+	fs::set_permissions(&key, fs::Permissions::from_mode(0o644)).unwrap();
+
+	write_certificate(&certs, &key, "chain", "key")
+		.await
+		.unwrap();
+
+	let mode = fs::metadata(&key).unwrap().permissions().mode();
+	assert_eq!(mode & 0o777, 0o600);
+}
+
+#[tokio::test]
+async fn written_certificate_is_served() {
+	let dir = TempDir::new();
+	let (certs, key) = (dir.file("cert.pem"), dir.file("key.pem"));
+	let (chain, key_pem) = self_signed("tuwunel.test");
+
+	write_certificate(&certs, &key, &chain, &key_pem)
+		.await
+		.unwrap();
+
+	let resolver = Resolver::default();
+	resolver.load(&certs, &key).unwrap();
+	assert!(resolver.not_after().is_some());
+}
+
+#[tokio::test]
+async fn mismatched_key_is_not_served() {
+	let dir = TempDir::new();
+	let (certs, key) = (dir.file("cert.pem"), dir.file("key.pem"));
+	let (chain, _) = self_signed("tuwunel.test");
+	let (_, other_key) = self_signed("tuwunel.test");
+
+	write_certificate(&certs, &key, &chain, &other_key)
+		.await
+		.unwrap();
+
+	let resolver = Resolver::default();
+	assert!(resolver.load(&certs, &key).is_err());
+	assert!(resolver.not_after().is_none());
+}
+
+/// Issues a certificate from a local Pebble server. Start Pebble with
+/// `PEBBLE_VA_ALWAYS_VALID=1` so the challenges need not be reachable, and
+/// point `SSL_CERT_FILE` at its `pebble.minica.pem` so its directory is
+/// trusted. `TUWUNEL_TEST_ACME_DIRECTORY` overrides the directory URL.
+#[tokio::test]
+#[ignore = "requires a local Pebble ACME server"]
+async fn issue_with_pebble() {
+	let directory = env::var("TUWUNEL_TEST_ACME_DIRECTORY")
+		.unwrap_or_else(|_| "https://localhost:14000/dir".to_owned());
+
+	let new_account = NewAccount {
+		contact: &[],
+		terms_of_service_agreed: true,
+		only_return_existing: false,
+	};
+
+	let (account, _) = Account::create(&new_account, &directory, None)
+		.await
+		.unwrap();
+
+	let domains = vec!["tuwunel.test".to_owned()];
+	let identifiers: Vec<_> = domains
+		.iter()
+		.cloned()
+		.map(Identifier::Dns)
+		.collect();
+
+	let mut order = account
+		.new_order(&NewOrder { identifiers: &identifiers })
+		.await
+		.unwrap();
+
+	let http01 = RwLock::default();
+	let resolver = Resolver::default();
+	authorize(&mut order, &ChallengeType::Http01, &http01, &resolver)
+		.await
+		.unwrap();
+
+	assert_eq!(http01.read().unwrap().len(), 1);
+
+	let (chain, key_pem) = finalize(&mut order, domains).await.unwrap();
+
+	let dir = TempDir::new();
+	let (certs, key) = (dir.file("cert.pem"), dir.file("key.pem"));
+	write_certificate(&certs, &key, &chain, &key_pem)
+		.await
+		.unwrap();
+
+	resolver.load(&certs, &key).unwrap();
+	assert!(resolver.not_after().is_some());
+}
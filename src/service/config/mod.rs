@@ -13,6 +13,10 @@ pub struct Service {
 
 const SIGNAL: &str = "SIGUSR1";
 
+/// Broadcast on the server's signal channel after the configuration has been
+/// reloaded, so components holding state derived from it can refresh.
+pub const RELOADED: &str = "RELOADED";
+
 #[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
@@ -71,5 +75,8 @@ pub fn reload<'a, I>(&self, paths: I) -> Result<Arc<Config>>
 	let new = Config::load(paths).and_then(|raw| Config::new(&raw))?;
 
 	check::reload(&old, &new)?;
-	self.server.config.update(new)
+	let old = self.server.config.update(new)?;
+	self.server.signal(RELOADED)?;
+
+	Ok(old)
 }
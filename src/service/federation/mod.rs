@@ -1,20 +1,56 @@
 mod execute;
 mod format;
+mod policy;
+mod txn_log;
 
-use std::sync::Arc;
+use std::sync::{Arc, atomic::AtomicBool};
 
+use async_trait::async_trait;
 use tuwunel_core::Result;
+use tuwunel_database::{Deserialized, Map};
 
+use self::txn_log::TxnLog;
+pub use self::{
+	policy::ServerPolicy,
+	txn_log::{Direction, TxnLogEntry},
+};
 use crate::services::OnceServices;
 
 pub struct Service {
+	allowlist: AtomicBool,
+	txn_log: TxnLog,
 	services: Arc<OnceServices>,
+	db: Data,
 }
 
+struct Data {
+	global: Arc<Map>,
+	servername_policy: Arc<Map>,
+}
+
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
-		Ok(Arc::new(Self { services: args.services.clone() }))
+		let global = args.db["global"].clone();
+		let allowlist = global
+			.get_blocking(policy::ALLOWLIST_KEY)
+			.deserialized::<u64>()
+			.map_or(args.server.config.federation_allowlist, |enabled| enabled != 0);
+
+		Ok(Arc::new(Self {
+			allowlist: allowlist.into(),
+			txn_log: TxnLog::new(args.server)?,
+			services: args.services.clone(),
+			db: Data {
+				global,
+				servername_policy: args.db["servername_policy"].clone(),
+			},
+		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result { self.txn_log_writer().await }
+
+	async fn interrupt(&self) { self.txn_log_close(); }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
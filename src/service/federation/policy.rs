@@ -0,0 +1,104 @@
+use std::sync::atomic::Ordering;
+
+use futures::Stream;
+use ruma::ServerName;
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{implement, utils::stream::TryIgnore};
+use tuwunel_database::{Deserialized, Json};
+
+pub(super) const ALLOWLIST_KEY: &[u8] = b"federation_allowlist";
+
+/// Federation policy for a remote server, set at runtime by an admin.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ServerPolicy {
+	/// Listed on the allow-list.
+	pub allowed: bool,
+
+	/// Never fetch media from the server.
+	pub no_media: bool,
+
+	/// Ignore presence and typing EDUs from the server and send it none.
+	pub no_edus: bool,
+
+	/// Reject invites sent by the server.
+	pub no_invites: bool,
+
+	/// Accept no events, joins, knocks or invites from the server; it can
+	/// still be queried.
+	pub read_only: bool,
+}
+
+/// Whether federation with a server is allowed at all: federation is enabled,
+/// the server is not forbidden, and it is on the allow-list when the
+/// allow-list is enabled.
+#[implement(super::Service)]
+pub async fn is_allowed(&self, server: &ServerName) -> bool {
+	let config = &self.services.server.config;
+	if !config.allow_federation {
+		return false;
+	}
+
+	if config
+		.forbidden_remote_server_names
+		.is_match(server.host())
+	{
+		return false;
+	}
+
+	if !self.allowlist_enabled()
+		|| config
+			.allowed_remote_server_names
+			.is_match(server.host())
+	{
+		return true;
+	}
+
+	self.policy(server).await.allowed
+}
+
+#[implement(super::Service)]
+#[inline]
+#[must_use]
+pub fn allowlist_enabled(&self) -> bool { self.allowlist.load(Ordering::Acquire) }
+
+/// Enables or disables the allow-list; the setting is persisted and
+/// overrides `federation_allowlist` in the config.
+#[implement(super::Service)]
+pub fn set_allowlist_enabled(&self, enabled: bool) {
+	self.db
+		.global
+		.raw_put(ALLOWLIST_KEY, u64::from(enabled));
+
+	self.allowlist.store(enabled, Ordering::Release);
+}
+
+/// Returns the policy for a server; servers without one have the default,
+/// unrestricted policy.
+#[implement(super::Service)]
+pub async fn policy(&self, server: &ServerName) -> ServerPolicy {
+	self.db
+		.servername_policy
+		.get(server)
+		.await
+		.deserialized()
+		.unwrap_or_default()
+}
+
+/// Replaces the policy for a server. Setting the default policy removes it.
+#[implement(super::Service)]
+pub fn set_policy(&self, server: &ServerName, policy: &ServerPolicy) {
+	if *policy == ServerPolicy::default() {
+		self.db.servername_policy.remove(server);
+	} else {
+		self.db
+			.servername_policy
+			.raw_put(server, Json(policy));
+	}
+}
+
+/// Iterates all servers with a policy.
+#[implement(super::Service)]
+pub fn policies(&self) -> impl Stream<Item = (&ServerName, ServerPolicy)> + Send + '_ {
+	self.db.servername_policy.stream().ignore_err()
+}
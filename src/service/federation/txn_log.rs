@@ -0,0 +1,218 @@
+use std::{
+	collections::{BTreeMap, VecDeque},
+	fs::{File, OpenOptions},
+	io::{BufWriter, Write},
+	sync::{
+		Mutex,
+		atomic::{AtomicUsize, Ordering},
+	},
+	time::Duration,
+};
+
+use loole::{Receiver, Sender, TrySendError};
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedServerName, OwnedTransactionId, ServerName,
+};
+use serde::Serialize;
+use tuwunel_core::{Error, Result, Server, err, implement, result::LogErr};
+
+/// Bounded log of recent federation transactions.
+pub(super) struct TxnLog {
+	entries: Mutex<VecDeque<TxnLogEntry>>,
+	file: Mutex<Option<File>>,
+	channel: Option<(Sender<TxnLogEntry>, Receiver<TxnLogEntry>)>,
+	capacity: usize,
+
+	/// Number of entries not written to the file because the writer fell
+	/// behind.
+	dropped: AtomicUsize,
+}
+
+/// A federation transaction sent or received by this server.
+#[derive(Clone, Debug, Serialize)]
+pub struct TxnLogEntry {
+	pub direction: Direction,
+
+	/// Origin of an inbound or destination of an outbound transaction.
+	pub server: OwnedServerName,
+
+	pub txn_id: OwnedTransactionId,
+
+	pub ts: MilliSecondsSinceUnixEpoch,
+
+	pub pdus: usize,
+
+	pub edus: usize,
+
+	/// Outcome of each PDU, as returned to or by the remote.
+	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
+	pub pdu_results: BTreeMap<OwnedEventId, Result<(), String>>,
+
+	pub latency_ms: u64,
+
+	/// Error failing the transaction as a whole.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+	Inbound,
+	Outbound,
+}
+
+impl TxnLog {
+	pub(super) fn new(server: &Server) -> Result<Self> {
+		let config = &server.config;
+		let file = config
+			.federation_txn_log_path
+			.as_ref()
+			.filter(|_| config.federation_txn_log_capacity > 0)
+			.map(|path| {
+				OpenOptions::new()
+					.create(true)
+					.append(true)
+					.open(path)
+					.map_err(|e| {
+						err!(Config(
+							"federation_txn_log_path",
+							"Failed to open {path:?} for appending: {e}"
+						))
+					})
+			})
+			.transpose()?;
+
+		Ok(Self {
+			entries: Mutex::new(VecDeque::new()),
+			channel: file
+				.as_ref()
+				.map(|_| loole::bounded(config.federation_txn_log_capacity)),
+			file: Mutex::new(file),
+			capacity: config.federation_txn_log_capacity,
+			dropped: AtomicUsize::new(0),
+		})
+	}
+}
+
+impl TxnLogEntry {
+	#[must_use]
+	pub fn new(direction: Direction, server: &ServerName, txn_id: OwnedTransactionId) -> Self {
+		Self {
+			direction,
+			server: server.to_owned(),
+			txn_id,
+			ts: MilliSecondsSinceUnixEpoch::now(),
+			pdus: 0,
+			edus: 0,
+			pdu_results: BTreeMap::new(),
+			latency_ms: 0,
+			error: None,
+		}
+	}
+
+	#[must_use]
+	pub fn latency(mut self, latency: Duration) -> Self {
+		self.latency_ms = latency.as_millis().try_into().unwrap_or(u64::MAX);
+		self
+	}
+}
+
+/// Whether federation transactions are being logged.
+#[implement(super::Service)]
+#[inline]
+#[must_use]
+pub fn txn_log_enabled(&self) -> bool { self.txn_log.capacity > 0 }
+
+/// Records a federation transaction, evicting the oldest entry when the log
+/// is full, and queues it for the log file if one is configured. The entry is
+/// not written to the file when the queue is full.
+#[implement(super::Service)]
+pub fn log_txn(&self, entry: TxnLogEntry) {
+	if !self.txn_log_enabled() {
+		return;
+	}
+
+	if let Some((sender, _)) = &self.txn_log.channel {
+		// Closed during shutdown; the entry is still kept in memory below.
+		if let Err(TrySendError::Full(_)) = sender.try_send(entry.clone()) {
+			self.txn_log
+				.dropped
+				.fetch_add(1, Ordering::Relaxed);
+		}
+	}
+
+	let mut entries = self.txn_log.entries.lock().expect("locked");
+	while entries.len() >= self.txn_log.capacity {
+		entries.pop_front();
+	}
+
+	entries.push_back(entry);
+}
+
+/// Returns the logged transactions, oldest first, optionally only those with
+/// a server.
+#[implement(super::Service)]
+#[must_use]
+pub fn txn_log(&self, server: Option<&ServerName>) -> Vec<TxnLogEntry> {
+	self.txn_log
+		.entries
+		.lock()
+		.expect("locked")
+		.iter()
+		.filter(|entry| server.is_none_or(|server| entry.server == server))
+		.cloned()
+		.collect()
+}
+
+/// Number of transactions which were not written to the log file because the
+/// writer fell behind.
+#[implement(super::Service)]
+#[must_use]
+pub fn txn_log_dropped(&self) -> usize { self.txn_log.dropped.load(Ordering::Relaxed) }
+
+/// Appends queued transactions to the log file until the channel is closed.
+/// Runs on a blocking thread so request handlers never wait on file I/O.
+#[implement(super::Service)]
+pub(super) async fn txn_log_writer(&self) -> Result {
+	let Some((_, receiver)) = self.txn_log.channel.clone() else {
+		return Ok(());
+	};
+
+	let Some(file) = self.txn_log.file.lock().expect("locked").take() else {
+		return Ok(());
+	};
+
+	self.services
+		.server
+		.runtime()
+		.spawn_blocking(move || write_entries(file, &receiver))
+		.await?;
+
+	Ok(())
+}
+
+/// Closes the channel to the log file writer.
+#[implement(super::Service)]
+pub(super) fn txn_log_close(&self) {
+	if let Some((sender, _)) = &self.txn_log.channel {
+		sender.close();
+	}
+}
+
+fn write_entries(file: File, receiver: &Receiver<TxnLogEntry>) {
+	let mut file = BufWriter::new(file);
+	while let Ok(entry) = receiver.recv() {
+		serde_json::to_writer(&mut file, &entry)
+			.map_err(Error::from)
+			.and_then(|()| writeln!(file).map_err(Error::from))
+			.log_err()
+			.ok();
+
+		if receiver.is_empty() {
+			file.flush().map_err(Error::from).log_err().ok();
+		}
+	}
+
+	file.flush().map_err(Error::from).log_err().ok();
+}
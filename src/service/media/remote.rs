@@ -29,7 +29,7 @@ pub async fn fetch_remote_thumbnail(
 	timeout_ms: Duration,
 	dim: &Dim,
 ) -> Result<FileMeta> {
-	self.check_fetch_authorized(mxc)?;
+	self.check_fetch_authorized(mxc).await?;
 
 	let result = self
 		.fetch_thumbnail_authenticated(mxc, user, server, timeout_ms, dim)
@@ -54,7 +54,7 @@ pub async fn fetch_remote_content(
 	server: Option<&ServerName>,
 	timeout_ms: Duration,
 ) -> Result<FileMeta> {
-	self.check_fetch_authorized(mxc)?;
+	self.check_fetch_authorized(mxc).await?;
 
 	let result = self
 		.fetch_content_authenticated(mxc, user, server, timeout_ms)
@@ -371,7 +371,7 @@ pub async fn fetch_remote_thumbnail_legacy(
 	};
 
 	self.check_legacy_freeze()?;
-	self.check_fetch_authorized(&mxc)?;
+	self.check_fetch_authorized(&mxc).await?;
 	let response = self
 		.services
 		.sending
@@ -411,7 +411,7 @@ pub async fn fetch_remote_content_legacy(
 	timeout_ms: Duration,
 ) -> Result<media::get_content::v3::Response, Error> {
 	self.check_legacy_freeze()?;
-	self.check_fetch_authorized(mxc)?;
+	self.check_fetch_authorized(mxc).await?;
 	let response = self
 		.services
 		.sending
@@ -443,7 +443,7 @@ pub async fn fetch_remote_content_legacy(
 }
 
 #[implement(super::Service)]
-fn check_fetch_authorized(&self, mxc: &Mxc<'_>) -> Result {
+async fn check_fetch_authorized(&self, mxc: &Mxc<'_>) -> Result {
 	if self
 		.services
 		.server
@@ -456,6 +456,12 @@ fn check_fetch_authorized(&self, mxc: &Mxc<'_>) -> Result {
 			.config
 			.forbidden_remote_server_names
 			.is_match(mxc.server_name.host())
+		|| self
+			.services
+			.federation
+			.policy(mxc.server_name)
+			.await
+			.no_media
 	{
 		// we'll lie to the client and say the blocked server's media was not found and
 		// log. the client has no way of telling anyways so this is a security bonus.
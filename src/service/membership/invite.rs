@@ -2,7 +2,7 @@
 use ruma::{
 	OwnedServerName, RoomId, UserId,
 	api::federation::membership::create_invite,
-	events::room::member::{MembershipState, RoomMemberEventContent},
+	events::room::member::{MembershipState, RoomMemberEventContent, ThirdPartyInvite},
 };
 use tuwunel_core::{
 	Err, Result, err, implement, matrix::event::gen_event_id_canonical_json, pdu::PduBuilder,
@@ -25,11 +25,11 @@ pub async fn invite(
 	is_direct: bool,
 ) -> Result {
 	if self.services.globals.user_is_local(user_id) {
-		self.local_invite(sender_user, user_id, room_id, reason, is_direct)
+		self.local_invite(sender_user, user_id, room_id, reason, is_direct, None)
 			.boxed()
 			.await?;
 	} else {
-		self.remote_invite(sender_user, user_id, room_id, reason, is_direct)
+		self.remote_invite(sender_user, user_id, room_id, reason, is_direct, None)
 			.boxed()
 			.await?;
 	}
@@ -39,13 +39,14 @@ pub async fn invite(
 
 #[implement(Service)]
 #[tracing::instrument(name = "remote", level = "debug", skip_all)]
-async fn remote_invite(
+pub(super) async fn remote_invite(
 	&self,
 	sender_user: &UserId,
 	user_id: &UserId,
 	room_id: &RoomId,
 	reason: Option<&String>,
 	is_direct: bool,
+	third_party_invite: Option<ThirdPartyInvite>,
 ) -> Result {
 	let (pdu, pdu_json, invite_room_state) = {
 		let state_lock = self.services.state.mutex.lock(room_id).await;
@@ -54,6 +55,7 @@ async fn remote_invite(
 			avatar_url: self.services.users.avatar_url(user_id).await.ok(),
 			is_direct: Some(is_direct),
 			reason: reason.cloned(),
+			third_party_invite,
 			..RoomMemberEventContent::new(MembershipState::Invite)
 		};
 
@@ -149,13 +151,14 @@ async fn remote_invite(
 
 #[implement(Service)]
 #[tracing::instrument(name = "local", level = "debug", skip_all)]
-async fn local_invite(
+pub(super) async fn local_invite(
 	&self,
 	sender_user: &UserId,
 	user_id: &UserId,
 	room_id: &RoomId,
 	reason: Option<&String>,
 	is_direct: bool,
+	third_party_invite: Option<ThirdPartyInvite>,
 ) -> Result {
 	if !self
 		.services
@@ -181,6 +184,7 @@ async fn local_invite(
 		blurhash: self.services.users.blurhash(user_id).await.ok(),
 		is_direct: Some(is_direct),
 		reason: reason.cloned(),
+		third_party_invite,
 		..RoomMemberEventContent::new(MembershipState::Invite)
 	};
 
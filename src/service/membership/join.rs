@@ -3,7 +3,7 @@
 use futures::{FutureExt, StreamExt, TryFutureExt, TryStreamExt, pin_mut};
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, OwnedServerName, OwnedUserId, RoomId, RoomVersionId,
-	UserId,
+	ServerName, UserId,
 	api::{client::error::ErrorKind, federation},
 	canonical_json::to_canonical_value,
 	events::{
@@ -28,6 +28,7 @@
 use crate::{
 	appservice::RegistrationInfo,
 	rooms::{
+		partial_state::PartialState,
 		state::RoomMutexGuard,
 		state_compressor::{CompressedState, HashSetCompressStateEvent},
 	},
@@ -232,7 +233,7 @@ pub async fn join_remote(
 	let send_join_request = federation::membership::create_join_event::v2::Request {
 		room_id: room_id.to_owned(),
 		event_id: event_id.clone(),
-		omit_members: false,
+		omit_members: self.services.config.partial_state_joins,
 		pdu: self
 			.services
 			.federation
@@ -443,6 +444,7 @@ pub async fn join_remote(
 		.await;
 
 	debug!("Saving compressed state");
+	let gc_guard = self.services.state_compressor.gc_guard().await;
 	let HashSetCompressStateEvent {
 		shortstatehash: statehash_before_join,
 		added,
@@ -492,6 +494,33 @@ pub async fn join_remote(
 		.state
 		.set_room_state(room_id, statehash_after_join, state_lock);
 
+	drop(gc_guard);
+
+	if send_join_response.room_state.members_omitted {
+		let servers = once(remote_server.clone())
+			.chain(
+				send_join_response
+					.room_state
+					.servers_in_room
+					.iter()
+					.flatten()
+					.filter_map(|server| ServerName::parse(server).ok()),
+			)
+			.filter(|server| !self.services.globals.server_is_ours(server))
+			.fold(Vec::new(), |mut servers, server| {
+				if !servers.contains(&server) {
+					servers.push(server);
+				}
+
+				servers
+			});
+
+		info!("Joined with partial state; fetching the full state in the background");
+		self.services
+			.partial_state
+			.mark_partial(room_id, &PartialState { event_id, servers });
+	}
+
 	Ok(())
 }
 
@@ -3,6 +3,7 @@
 mod join;
 mod kick;
 mod leave;
+mod third_party;
 mod unban;
 
 use std::sync::Arc;
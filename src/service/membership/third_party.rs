@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use futures::FutureExt;
+use ipaddress::IPAddress;
+use reqwest::{RequestBuilder, Url, header::CONTENT_TYPE};
+use ruma::{
+	OwnedUserId, RoomId, UserId,
+	api::federation::thirdparty::exchange_invite,
+	events::{
+		StateEventType,
+		room::{
+			member::{SignedContent, ThirdPartyInvite},
+			third_party_invite::{PublicKey, RoomThirdPartyInviteEventContent},
+		},
+	},
+	serde::Raw,
+	thirdparty::Medium,
+};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::json;
+use tuwunel_core::{
+	Err, Result, debug, err, implement, matrix::Event, pdu::PduBuilder, utils::hash::sha256,
+};
+
+use super::Service;
+
+/// Response of an identity server to `POST /_matrix/identity/v2/store-invite`.
+#[derive(Deserialize)]
+struct StoredInvite {
+	token: String,
+	display_name: String,
+	public_keys: Vec<PublicKey>,
+}
+
+#[derive(Deserialize)]
+struct HashDetails {
+	algorithms: Vec<String>,
+	lookup_pepper: String,
+}
+
+#[derive(Deserialize)]
+struct LookupResponse {
+	mappings: BTreeMap<String, OwnedUserId>,
+}
+
+/// Invites a third-party identifier to a room. An address already bound to a
+/// Matrix ID on the identity server is invited directly; otherwise the
+/// invite is stored with the identity server and announced in the room with
+/// an `m.room.third_party_invite` event, to be exchanged for a membership
+/// once the address is bound.
+#[implement(Service)]
+#[tracing::instrument(level = "debug", skip(self, id_access_token))]
+pub async fn invite_third_party(
+	&self,
+	sender_user: &UserId,
+	room_id: &RoomId,
+	id_server: &str,
+	id_access_token: &str,
+	medium: &Medium,
+	address: &str,
+) -> Result {
+	if !self
+		.services
+		.state_cache
+		.is_joined(sender_user, room_id)
+		.await
+	{
+		return Err!(Request(Forbidden(
+			"You must be joined in the room you are trying to invite from."
+		)));
+	}
+
+	match self
+		.lookup_third_party(id_server, id_access_token, medium, address)
+		.await
+	{
+		| Ok(Some(user_id)) => {
+			debug!(%user_id, "Third-party identifier is bound, inviting directly");
+			return self
+				.invite(sender_user, &user_id, room_id, None, false)
+				.boxed()
+				.await;
+		},
+		| Ok(None) => {},
+		| Err(e) => debug!("Failed to look up third-party identifier: {e}"),
+	}
+
+	let room_name = self
+		.services
+		.state_accessor
+		.get_name(room_id)
+		.await
+		.ok();
+
+	let room_alias = self
+		.services
+		.state_accessor
+		.get_canonical_alias(room_id)
+		.await
+		.ok();
+
+	let body = json!({
+		"medium": medium.as_str(),
+		"address": address,
+		"room_id": room_id,
+		"sender": sender_user,
+		"sender_display_name": self.services.users.displayname(sender_user).await.ok(),
+		"sender_avatar_url": self.services.users.avatar_url(sender_user).await.ok(),
+		"room_name": room_name,
+		"room_alias": room_alias,
+	});
+
+	let url = self.identity_server_url(id_server, "/_matrix/identity/v2/store-invite")?;
+	let request = self
+		.services
+		.client
+		.default
+		.post(url)
+		.bearer_auth(id_access_token)
+		.header(CONTENT_TYPE, "application/json")
+		.body(body.to_string());
+
+	let stored: StoredInvite = identity_request(request).await?;
+	let Some(first) = stored.public_keys.first() else {
+		return Err!(BadServerResponse("Identity server returned no public keys."));
+	};
+
+	let key_validity_url = first.key_validity_url.clone().ok_or_else(|| {
+		err!(BadServerResponse("Identity server returned a key without validity URL."))
+	})?;
+
+	let content = RoomThirdPartyInviteEventContent {
+		public_keys: Some(stored.public_keys.clone()),
+		..RoomThirdPartyInviteEventContent::new(
+			stored.display_name.clone(),
+			key_validity_url,
+			first.public_key.clone(),
+		)
+	};
+
+	let state_lock = self.services.state.mutex.lock(room_id).await;
+
+	self.services
+		.timeline
+		.build_and_append_pdu(
+			PduBuilder::state(stored.token, &content),
+			sender_user,
+			room_id,
+			&state_lock,
+		)
+		.await?;
+
+	drop(state_lock);
+
+	Ok(())
+}
+
+/// Handles an identity server reporting that an address with pending
+/// third-party invites was bound to a local user. Each invite is exchanged
+/// for a membership by the server of the user who sent it.
+#[implement(Service)]
+#[tracing::instrument(level = "debug", skip(self, signed))]
+pub async fn third_party_invite_bound(
+	&self,
+	sender: &UserId,
+	user_id: &UserId,
+	room_id: &RoomId,
+	address: &str,
+	signed: &SignedContent,
+) -> Result {
+	let third_party_invite = ThirdPartyInvite::new(address.to_owned(), Raw::new(signed)?);
+
+	if self.services.globals.user_is_local(sender) {
+		return self
+			.exchange_third_party_invite(sender, user_id, room_id, third_party_invite)
+			.await;
+	}
+
+	self.services
+		.sending
+		.send_federation_request(sender.server_name(), exchange_invite::v1::Request {
+			room_id: room_id.to_owned(),
+			kind: StateEventType::RoomMember,
+			sender: sender.to_owned(),
+			state_key: user_id.to_owned(),
+			content: third_party_invite,
+		})
+		.await?;
+
+	Ok(())
+}
+
+/// Turns a third-party invite sent by a local user into an invite of the
+/// user the address was bound to. The signed token must match an
+/// `m.room.third_party_invite` event of the sender still in the room.
+#[implement(Service)]
+#[tracing::instrument(level = "debug", skip(self, third_party_invite))]
+pub async fn exchange_third_party_invite(
+	&self,
+	sender: &UserId,
+	user_id: &UserId,
+	room_id: &RoomId,
+	mut third_party_invite: ThirdPartyInvite,
+) -> Result {
+	if !self.services.globals.user_is_local(sender) {
+		return Err!(Request(Forbidden("Sender of the invite is not a user of this server.")));
+	}
+
+	let signed = third_party_invite
+		.signed
+		.deserialize()
+		.map_err(|e| err!(Request(BadJson("Invalid signed third-party invite: {e}"))))?;
+
+	if signed.mxid != user_id {
+		return Err!(Request(InvalidParam("Signed third-party invite is for another user.")));
+	}
+
+	let invite_event = self
+		.services
+		.state_accessor
+		.room_state_get(room_id, &StateEventType::RoomThirdPartyInvite, &signed.token)
+		.await
+		.map_err(|_| err!(Request(NotFound("No third-party invite found for this token."))))?;
+
+	if invite_event.sender() != sender {
+		return Err!(Request(Forbidden("Third-party invite was sent by another user.")));
+	}
+
+	let content: RoomThirdPartyInviteEventContent = invite_event.get_content()?;
+	third_party_invite.display_name = content.display_name;
+
+	if self.services.globals.user_is_local(user_id) {
+		self.local_invite(sender, user_id, room_id, None, false, Some(third_party_invite))
+			.boxed()
+			.await
+	} else {
+		self.remote_invite(sender, user_id, room_id, None, false, Some(third_party_invite))
+			.boxed()
+			.await
+	}
+}
+
+/// Looks up the Matrix ID an address is bound to on an identity server.
+#[implement(Service)]
+async fn lookup_third_party(
+	&self,
+	id_server: &str,
+	id_access_token: &str,
+	medium: &Medium,
+	address: &str,
+) -> Result<Option<OwnedUserId>> {
+	let url = self.identity_server_url(id_server, "/_matrix/identity/v2/hash_details")?;
+	let request = self
+		.services
+		.client
+		.default
+		.get(url)
+		.bearer_auth(id_access_token);
+
+	let details: HashDetails = identity_request(request).await?;
+	let medium = medium.as_str();
+	let pepper = details.lookup_pepper;
+	let (algorithm, lookup) = if details.algorithms.iter().any(|a| a == "sha256") {
+		let digest = sha256::hash(format!("{address} {medium} {pepper}"));
+		("sha256", URL_SAFE_NO_PAD.encode(digest))
+	} else if details.algorithms.iter().any(|a| a == "none") {
+		("none", format!("{address} {medium}"))
+	} else {
+		return Ok(None);
+	};
+
+	let url = self.identity_server_url(id_server, "/_matrix/identity/v2/lookup")?;
+	let request = self
+		.services
+		.client
+		.default
+		.post(url)
+		.bearer_auth(id_access_token)
+		.header(CONTENT_TYPE, "application/json")
+		.body(
+			json!({
+				"addresses": [&lookup],
+				"algorithm": algorithm,
+				"pepper": pepper,
+			})
+			.to_string(),
+		);
+
+	let mut response: LookupResponse = identity_request(request).await?;
+
+	Ok(response.mappings.remove(&lookup))
+}
+
+#[implement(Service)]
+fn identity_server_url(&self, id_server: &str, path: &str) -> Result<Url> {
+	let scheme = if self.services.config.identity_server_allow_http {
+		"http"
+	} else {
+		"https"
+	};
+
+	let url = Url::parse(&format!("{scheme}://{id_server}{path}"))
+		.map_err(|e| err!(Request(InvalidParam("Invalid identity server {id_server:?}: {e}"))))?;
+
+	if let Some(host) = url.host_str() {
+		if let Ok(ip) = IPAddress::parse(host) {
+			if !self.services.client.valid_cidr_range(&ip) {
+				return Err!(Request(Forbidden("Requesting from this address is forbidden")));
+			}
+		}
+	}
+
+	Ok(url)
+}
+
+async fn identity_request<T>(request: RequestBuilder) -> Result<T>
+where
+	T: DeserializeOwned,
+{
+	let response = request.send().await?;
+	let status = response.status();
+	if !status.is_success() {
+		return Err!(BadServerResponse("Identity server responded with {status}"));
+	}
+
+	let body = response.bytes().await?;
+
+	serde_json::from_slice(&body)
+		.map_err(|e| err!(BadServerResponse("Invalid response from identity server: {e}")))
+}
@@ -5,13 +5,14 @@
 use ruma::{
 	OwnedUserId, RoomId, UserId,
 	events::{
-		GlobalAccountDataEventType, push_rules::PushRulesEvent, room::member::MembershipState,
+		GlobalAccountDataEventType, TimelineEventType, push_rules::PushRulesEvent,
+		reaction::ReactionEventContent, room::member::MembershipState,
 	},
 	push::Ruleset,
 };
 use tuwunel_core::{
 	Err, Result, debug, debug_info, debug_warn, error, info,
-	matrix::PduCount,
+	matrix::{Event, PduCount, PduEvent},
 	result::NotFound,
 	utils::{
 		IterStream, ReadyExt,
@@ -19,6 +20,7 @@
 	},
 	warn,
 };
+use tuwunel_database::Ignore;
 
 use crate::{Services, media};
 
@@ -66,6 +68,7 @@ async fn fresh(services: &Services) -> Result {
 	db["global"].insert(b"retroactively_fix_bad_data_from_roomuserid_joined", []);
 	db["global"].insert(b"fix_referencedevents_missing_sep", []);
 	db["global"].insert(b"fix_readreceiptid_readreceipt_duplicates", []);
+	db["global"].insert(b"populate_annotation_counts", []);
 
 	// Create the admin room and server user on first run
 	if services.config.create_admin_room {
@@ -145,6 +148,14 @@ async fn migrate(services: &Services) -> Result {
 		fix_readreceiptid_readreceipt_duplicates(services).await?;
 	}
 
+	if db["global"]
+		.get(b"populate_annotation_counts")
+		.await
+		.is_not_found()
+	{
+		populate_annotation_counts(services).await?;
+	}
+
 	if services.globals.db.database_version().await < 17 {
 		services.globals.db.bump_database_version(17);
 		info!("Migration: Bumped database version to 17");
@@ -578,3 +589,47 @@ async fn fix_readreceiptid_readreceipt_duplicates(services: &Services) -> Result
 	db["global"].insert(b"fix_readreceiptid_readreceipt_duplicates", []);
 	db.engine.sort()
 }
+
+async fn populate_annotation_counts(services: &Services) -> Result {
+	warn!("Counting reactions by key in targetkey_annotationcount...");
+
+	let db = &services.db;
+	let cork = db.cork_and_sync();
+
+	let totals: (usize, usize) = (0, 0);
+	let (total, counted) = db["pduid_pdu"]
+		.stream()
+		.expect_ok()
+		.then(async |(_, pdu): (Ignore, PduEvent)| {
+			if *pdu.kind() != TimelineEventType::Reaction {
+				return false;
+			}
+
+			// redacted reactions have no relation left to count
+			let Ok(ReactionEventContent { relates_to, .. }) = pdu.get_content() else {
+				return false;
+			};
+
+			services
+				.pdu_metadata
+				.add_annotation(
+					&relates_to.event_id,
+					&relates_to.key,
+					pdu.sender(),
+					pdu.event_id(),
+				)
+				.await;
+
+			true
+		})
+		.ready_fold(totals, |(total, counted), is_reaction| {
+			(total.saturating_add(1), counted.saturating_add(is_reaction.into()))
+		})
+		.await;
+
+	drop(cork);
+	info!(?total, ?counted, "Counted reactions in targetkey_annotationcount.");
+
+	db["global"].insert(b"populate_annotation_counts", []);
+	db.engine.sort()
+}
@@ -8,6 +8,7 @@
 pub mod services;
 
 pub mod account_data;
+pub mod acme;
 pub mod admin;
 pub mod appservice;
 pub mod client;
@@ -21,6 +22,7 @@
 pub mod membership;
 pub mod presence;
 pub mod pusher;
+pub mod remote_profile;
 pub mod resolver;
 pub mod rooms;
 pub mod sending;
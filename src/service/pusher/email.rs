@@ -0,0 +1,316 @@
+use std::fmt::Write;
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use futures::StreamExt;
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedUserId, UInt, UserId,
+	api::client::push::{Pusher, PusherKind, get_notifications::v3::Notification},
+};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	Result, debug, err, implement,
+	utils::{self, ReadyExt, stream::TryIgnore, time::now_millis},
+	warn,
+};
+use tuwunel_database::{Deserialized, Ignore, Json};
+use url::Url;
+
+use super::smtp;
+
+const DIGEST_TEXT: &str = include_str!("templates/digest.txt");
+const DIGEST_HTML: &str = include_str!("templates/digest.html");
+
+/// Most messages listed in one email.
+const DIGEST_MESSAGES_MAX: usize = 20;
+
+/// Unsubscribe token length
+const TOKEN_LENGTH: usize = 32;
+
+/// Progress of an email pusher.
+#[derive(Deserialize, Serialize)]
+struct EmailState {
+	/// Count of the last notification emailed or passed over.
+	last_count: u64,
+
+	/// Token of the unsubscribe link in the emails.
+	token: String,
+}
+
+/// The parts of an event shown in an email.
+#[derive(Deserialize)]
+struct Preview {
+	sender: OwnedUserId,
+
+	#[serde(default)]
+	content: PreviewContent,
+}
+
+#[derive(Default, Deserialize)]
+struct PreviewContent {
+	body: Option<String>,
+}
+
+/// Emails users with an email pusher who have been inactive for
+/// `email_notification_delay` about the highlights they haven't read since
+/// the last email.
+#[implement(super::Service)]
+pub(super) async fn send_email_digests(&self) {
+	type KeyVal<'a> = ((&'a UserId, Ignore), Pusher);
+
+	let pushers: Vec<(OwnedUserId, Pusher)> = self
+		.db
+		.senderkey_pusher
+		.stream()
+		.ignore_err()
+		.ready_filter_map(|((user_id, _), pusher): KeyVal<'_>| {
+			matches!(pusher.kind, PusherKind::Email(_)).then(|| (user_id.to_owned(), pusher))
+		})
+		.collect()
+		.await;
+
+	for (user_id, pusher) in pushers {
+		if let Err(e) = self.send_email_digest(&user_id, &pusher).await {
+			let pushkey = &pusher.ids.pushkey;
+			warn!(%user_id, ?pushkey, "Failed to send email notification: {e}");
+		}
+	}
+}
+
+#[implement(super::Service)]
+async fn send_email_digest(&self, user_id: &UserId, pusher: &Pusher) -> Result {
+	let config = &self.services.config;
+	let Some(mailer) = self.mailer() else {
+		return Ok(());
+	};
+
+	let delay = config
+		.email_notification_delay
+		.saturating_mul(1000);
+
+	if self
+		.services
+		.presence
+		.last_sync_gap_ms(user_id)
+		.await
+		.is_some_and(|gap| gap < delay)
+	{
+		return Ok(());
+	}
+
+	let pushkey = &pusher.ids.pushkey;
+	let mut state = self.email_state(user_id, pushkey).await;
+	let before = now_millis().saturating_sub(delay);
+	let before = MilliSecondsSinceUnixEpoch(UInt::try_from(before).unwrap_or(UInt::MIN));
+
+	let (notifications, last) = self
+		.unread_highlights(user_id, state.last_count, before)
+		.await;
+
+	if !notifications.is_empty() {
+		let server_name = self.services.globals.server_name();
+		let from = config
+			.email_from
+			.clone()
+			.unwrap_or_else(|| format!("noreply@{server_name}"));
+
+		let message = self
+			.digest_message(user_id, &from, pushkey, &state.token, &notifications)
+			.await?;
+
+		smtp::send_mail(mailer, &from, pushkey, &message).await?;
+		debug!(%user_id, count = notifications.len(), "Sent email notification");
+	}
+
+	if last != state.last_count {
+		state.last_count = last;
+		self.db
+			.senderkey_emailstate
+			.put((user_id, pushkey.as_str()), Json(&state));
+	}
+
+	Ok(())
+}
+
+/// Removes the email pusher an unsubscribe link was sent for.
+#[implement(super::Service)]
+pub async fn unsubscribe_email(&self, token: &str) -> Result {
+	let (user_id, pushkey): (OwnedUserId, String) = self
+		.db
+		.emailtoken_senderkey
+		.get(token)
+		.await
+		.deserialized()
+		.map_err(|_| err!(Request(NotFound("Unknown unsubscribe link."))))?;
+
+	debug!(%user_id, ?pushkey, "Unsubscribing email pusher");
+	self.delete_pusher(&user_id, &pushkey).await;
+
+	Ok(())
+}
+
+/// Returns the progress of an email pusher, starting it at the current count
+/// with a new unsubscribe token if it has none.
+#[implement(super::Service)]
+async fn email_state(&self, user_id: &UserId, pushkey: &str) -> EmailState {
+	let key = (user_id, pushkey);
+	if let Ok(state) = self
+		.db
+		.senderkey_emailstate
+		.qry(&key)
+		.await
+		.deserialized()
+	{
+		return state;
+	}
+
+	let state = EmailState {
+		last_count: self.services.globals.current_count(),
+		token: utils::random_string(TOKEN_LENGTH),
+	};
+
+	self.db
+		.emailtoken_senderkey
+		.raw_put(&state.token, Json(&key));
+	self.db
+		.senderkey_emailstate
+		.put(key, Json(&state));
+
+	state
+}
+
+#[implement(super::Service)]
+pub(super) async fn remove_email_state(&self, user_id: &UserId, pushkey: &str) {
+	let key = (user_id, pushkey);
+	if let Ok(state) = self
+		.db
+		.senderkey_emailstate
+		.qry(&key)
+		.await
+		.deserialized::<EmailState>()
+	{
+		self.db.emailtoken_senderkey.remove(&state.token);
+	}
+
+	self.db.senderkey_emailstate.del(key);
+}
+
+#[implement(super::Service)]
+async fn digest_message(
+	&self,
+	user_id: &UserId,
+	from: &str,
+	to: &str,
+	token: &str,
+	notifications: &[Notification],
+) -> Result<String> {
+	let server_name = self.services.globals.server_name().as_str();
+	let headers = smtp::origin_headers(self.services.globals.server_name())?;
+	let unsubscribe = self.unsubscribe_url(token)?;
+	let user = self
+		.services
+		.users
+		.displayname(user_id)
+		.await
+		.unwrap_or_else(|_| user_id.localpart().to_owned());
+
+	let (mut text, mut html) = (String::new(), String::new());
+	for notification in notifications.iter().take(DIGEST_MESSAGES_MAX) {
+		let Ok(preview) = serde_json::from_str::<Preview>(notification.event.json().get()) else {
+			continue;
+		};
+
+		let room = self
+			.services
+			.state_accessor
+			.get_name(&notification.room_id)
+			.await
+			.unwrap_or_else(|_| notification.room_id.to_string());
+
+		let sender = self
+			.services
+			.users
+			.displayname(&preview.sender)
+			.await
+			.unwrap_or_else(|_| preview.sender.to_string());
+
+		let body = preview.content.body.unwrap_or_default();
+		writeln!(text, "[{room}] {sender}: {body}")?;
+		writeln!(
+			html,
+			"<li><b>{}</b> in {}: {}</li>",
+			escape_html(&sender),
+			escape_html(&room),
+			escape_html(&body)
+		)?;
+	}
+
+	if let Some(more) = notifications
+		.len()
+		.checked_sub(DIGEST_MESSAGES_MAX)
+		.filter(|&more| more > 0)
+	{
+		writeln!(text, "... and {more} more")?;
+		writeln!(html, "<li>... and {more} more</li>")?;
+	}
+
+	// The messages are filled in last so nothing in them is taken as a
+	// placeholder.
+	let count = notifications.len().to_string();
+	let fill = |template: &str, user: &str, messages: &str| {
+		template
+			.replace("{{user}}", user)
+			.replace("{{server_name}}", server_name)
+			.replace("{{count}}", &count)
+			.replace("{{unsubscribe}}", unsubscribe.as_str())
+			.replace("{{messages}}", messages)
+	};
+
+	let text = fill(DIGEST_TEXT, &user, text.trim_end());
+	let html = fill(DIGEST_HTML, &escape_html(&user), &html);
+	let subject = format!("{count} unread mentions on {server_name}");
+	let boundary = utils::random_string(TOKEN_LENGTH);
+
+	Ok(format!(
+		"{headers}From: {from}\r\nTo: {to}\r\nSubject: =?UTF-8?B?{}?=\r\nMIME-Version: \
+		 1.0\r\nList-Unsubscribe: <{unsubscribe}>\r\nList-Unsubscribe-Post: \
+		 List-Unsubscribe=One-Click\r\nContent-Type: multipart/alternative; \
+		 boundary=\"{boundary}\"\r\n\r\n--{boundary}\r\n{}--{boundary}\r\n{}--{boundary}--\r\n",
+		STANDARD.encode(subject),
+		mime_part("text/plain", &text),
+		mime_part("text/html", &html),
+	))
+}
+
+#[implement(super::Service)]
+fn unsubscribe_url(&self, token: &str) -> Result<Url> {
+	let base = match &self.services.config.well_known.client {
+		| Some(client) => client.to_string(),
+		| None => format!("https://{}", self.services.globals.server_name()),
+	};
+
+	Url::parse(&base)
+		.and_then(|base| base.join(&format!("/_tuwunel/email/unsubscribe/{token}")))
+		.map_err(|e| err!(Config("well_known.client", "Invalid client URL: {e}")))
+}
+
+fn mime_part(content_type: &str, body: &str) -> String {
+	let encoded = STANDARD.encode(body);
+	let lines: Vec<_> = encoded
+		.as_bytes()
+		.chunks(76)
+		.map(|line| String::from_utf8_lossy(line))
+		.collect();
+
+	format!(
+		"Content-Type: {content_type}; charset=utf-8\r\nContent-Transfer-Encoding: \
+		 base64\r\n\r\n{}\r\n",
+		lines.join("\r\n")
+	)
+}
+
+fn escape_html(s: &str) -> String {
+	s.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
@@ -1,10 +1,23 @@
-use std::{fmt::Debug, mem, sync::Arc};
+mod email;
+mod notification;
+pub(crate) mod smtp;
+#[cfg(test)]
+mod tests;
+
+use std::{
+	collections::HashMap,
+	fmt::Debug,
+	mem,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
 
+use async_trait::async_trait;
 use bytes::BytesMut;
 use futures::{Stream, StreamExt};
 use ipaddress::IPAddress;
 use ruma::{
-	DeviceId, OwnedDeviceId, RoomId, UInt, UserId,
+	DeviceId, OwnedDeviceId, OwnedUserId, RoomId, UInt, UserId,
 	api::{
 		IncomingResponse, MatrixVersion, OutgoingRequest, SendAccessToken, SupportedVersions,
 		client::push::{Pusher, PusherKind, set_pusher},
@@ -35,28 +48,63 @@
 pub struct Service {
 	db: Data,
 	services: Arc<crate::services::OnceServices>,
+	notification_inserts: Mutex<HashMap<OwnedUserId, usize>>,
+	mailer: Option<smtp::Mailer>,
 }
 
 struct Data {
+	emailtoken_senderkey: Arc<Map>,
+	senderkey_emailstate: Arc<Map>,
 	senderkey_pusher: Arc<Map>,
 	pushkey_deviceid: Arc<Map>,
+	useridcount_notification: Arc<Map>,
 }
 
+/// Interval between email notification runs.
+const EMAIL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
 		Ok(Arc::new(Self {
 			db: Data {
+				emailtoken_senderkey: args.db["emailtoken_senderkey"].clone(),
+				senderkey_emailstate: args.db["senderkey_emailstate"].clone(),
 				senderkey_pusher: args.db["senderkey_pusher"].clone(),
 				pushkey_deviceid: args.db["pushkey_deviceid"].clone(),
+				useridcount_notification: args.db["useridcount_notification"].clone(),
 			},
 			services: args.services.clone(),
+			notification_inserts: Mutex::new(HashMap::new()),
+			mailer: smtp::mailer(config, &config.server_name)?,
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.mailer.is_none() {
+			return Ok(());
+		}
+
+		while self.services.server.running() {
+			self.send_email_digests().await;
+			tokio::select! {
+				() = self.services.server.until_shutdown() => break,
+				() = tokio::time::sleep(EMAIL_INTERVAL) => {},
+			}
+		}
+
+		Ok(())
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 impl Service {
+	/// Transport to the SMTP relay emails are sent through, if configured.
+	#[inline]
+	pub(crate) fn mailer(&self) -> Option<&smtp::Mailer> { self.mailer.as_ref() }
+
 	pub async fn set_pusher(
 		&self,
 		sender: &UserId,
@@ -109,6 +157,33 @@ pub async fn set_pusher(
 					}
 				}
 
+				// email pushers are keyed by the address notifications are sent to
+				if let PusherKind::Email(_) = pusher_kind {
+					if self.mailer.is_none() {
+						return Err!(Request(InvalidParam(
+							"Email notifications are not enabled on this server."
+						)));
+					}
+
+					if !is_email_address(pushkey) {
+						return Err!(Request(InvalidParam(
+							"Email pusher pushkey is not a valid email address."
+						)));
+					}
+
+					if !self
+						.services
+						.users
+						.has_threepid(sender, pushkey)
+						.await
+					{
+						return Err!(Request(ThreepidNotFound(
+							"Email pusher pushkey is not a validated email address of this \
+							 account."
+						)));
+					}
+				}
+
 				let pushkey = data.pusher.ids.pushkey.as_str();
 				let key = (sender, pushkey);
 				self.db.senderkey_pusher.put(key, Json(pusher));
@@ -129,6 +204,7 @@ pub async fn delete_pusher(&self, sender: &UserId, pushkey: &str) {
 		let key = (sender, pushkey);
 		self.db.senderkey_pusher.del(key);
 		self.db.pushkey_deviceid.remove(pushkey);
+		self.remove_email_state(sender, pushkey).await;
 
 		self.services
 			.sending
@@ -290,15 +366,15 @@ pub async fn send_request<T>(&self, dest: &str, request: T) -> Result<T::Incomin
 		}
 	}
 
-	#[tracing::instrument(skip(self, user, unread, pusher, ruleset, event))]
-	pub async fn send_push_notice<E>(
+	/// Returns the tweaks of the push rule actions when the event notifies the
+	/// user, or None when it doesn't.
+	#[tracing::instrument(skip(self, user, ruleset, event), level = "debug")]
+	pub async fn notify_tweaks<E>(
 		&self,
 		user: &UserId,
-		unread: UInt,
-		pusher: &Pusher,
-		ruleset: Ruleset,
+		ruleset: &Ruleset,
 		event: &E,
-	) -> Result
+	) -> Result<Option<Vec<Tweak>>>
 	where
 		E: Event,
 	{
@@ -313,7 +389,7 @@ pub async fn send_push_notice<E>(
 
 		let serialized = event.to_format();
 		for action in self
-			.get_actions(user, &ruleset, &power_levels, &serialized, event.room_id())
+			.get_actions(user, ruleset, &power_levels, &serialized, event.room_id())
 			.await
 		{
 			let n = match action {
@@ -334,13 +410,37 @@ pub async fn send_push_notice<E>(
 			notify = Some(n);
 		}
 
-		if notify == Some(true) {
-			self.send_notice(unread, pusher, tweaks, event)
-				.await?;
+		Ok((notify == Some(true)).then_some(tweaks))
+	}
+
+	/// Sends a notification for the event to the pusher. A pushkey rejected by
+	/// the push gateway is removed along with its pusher, in which case false
+	/// is returned.
+	#[tracing::instrument(skip(self, user, unread, pusher, tweaks, event))]
+	pub async fn send_push_notice<E>(
+		&self,
+		user: &UserId,
+		unread: UInt,
+		pusher: &Pusher,
+		tweaks: Vec<Tweak>,
+		event: &E,
+	) -> Result<bool>
+	where
+		E: Event,
+	{
+		let rejected = self
+			.send_notice(unread, pusher, tweaks, event)
+			.await?;
+
+		let pushkey = &pusher.ids.pushkey;
+		if !rejected.contains(pushkey) {
+			return Ok(true);
 		}
-		// Else the event triggered no actions
 
-		Ok(())
+		warn!(%user, ?pushkey, "Push gateway rejected pushkey, removing pusher");
+		self.delete_pusher(user, pushkey).await;
+
+		Ok(false)
 	}
 
 	#[tracing::instrument(skip(self, user, ruleset, pdu), level = "debug")]
@@ -386,6 +486,7 @@ pub async fn get_actions<'a>(
 		ruleset.get_actions(pdu, &ctx).await
 	}
 
+	/// Returns the pushkeys rejected by the push gateway.
 	#[tracing::instrument(skip(self, unread, pusher, tweaks, event))]
 	async fn send_notice<Pdu: Event>(
 		&self,
@@ -393,8 +494,7 @@ async fn send_notice<Pdu: Event>(
 		pusher: &Pusher,
 		tweaks: Vec<Tweak>,
 		event: &Pdu,
-	) -> Result {
-		// TODO: email
+	) -> Result<Vec<String>> {
 		match &pusher.kind {
 			| PusherKind::Http(http) => {
 				let url = &http.url;
@@ -494,14 +594,23 @@ async fn send_notice<Pdu: Event>(
 						.ok();
 				}
 
-				self.send_request(&http.url, send_event_notification::v1::Request::new(notifi))
+				let response = self
+					.send_request(&http.url, send_event_notification::v1::Request::new(notifi))
 					.await?;
 
-				Ok(())
+				Ok(response.rejected)
 			},
-			// TODO: Handle email
-			//PusherKind::Email(_) => Ok(()),
-			| _ => Ok(()),
+			// Emails are sent as digests by the worker instead.
+			| _ => Ok(Vec::new()),
 		}
 	}
 }
+
+pub(crate) fn is_email_address(address: &str) -> bool {
+	address
+		.split_once('@')
+		.is_some_and(|(local, domain)| !local.is_empty() && !domain.is_empty())
+		&& !address
+			.chars()
+			.any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | ','))
+}
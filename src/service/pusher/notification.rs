@@ -0,0 +1,193 @@
+use futures::StreamExt;
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedEventId, OwnedRoomId, UserId,
+	api::client::push::get_notifications::v3::Notification, push::Action,
+};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	implement,
+	matrix::Event,
+	utils::stream::{ReadyExt, TryIgnore},
+};
+use tuwunel_database::Json;
+
+/// A notifying event recorded for the notifications endpoint.
+#[derive(Debug, Deserialize, Serialize)]
+struct Record {
+	room_id: OwnedRoomId,
+	event_id: OwnedEventId,
+	actions: Vec<Action>,
+	highlight: bool,
+	ts: MilliSecondsSinceUnixEpoch,
+}
+
+/// Number of notifications recorded for a user between trims of their
+/// history.
+const TRIM_INTERVAL: usize = 64;
+
+/// Records that an event notified a user, keeping the most recent
+/// `notification_history_max` per user. The history is trimmed once every
+/// `TRIM_INTERVAL` notifications rather than on each.
+#[implement(super::Service)]
+pub async fn record_notification<E>(
+	&self,
+	user_id: &UserId,
+	count: u64,
+	event: &E,
+	actions: &[Action],
+	highlight: bool,
+) where
+	E: Event,
+{
+	let max = self.services.config.notification_history_max;
+	if max == 0 {
+		return;
+	}
+
+	let record = Record {
+		room_id: event.room_id().to_owned(),
+		event_id: event.event_id().to_owned(),
+		actions: actions.to_vec(),
+		highlight,
+		ts: event.origin_server_ts(),
+	};
+
+	self.db
+		.useridcount_notification
+		.put((user_id, count), Json(&record));
+
+	if !self.trim_due(user_id) {
+		return;
+	}
+
+	self.db
+		.useridcount_notification
+		.rev_keys_from(&(user_id, u64::MAX))
+		.ignore_err()
+		.ready_take_while(|(user, _): &(&UserId, u64)| *user == user_id)
+		.skip(max)
+		.ready_for_each(|key| {
+			self.db.useridcount_notification.del(key);
+		})
+		.await;
+}
+
+/// Counts a notification recorded for the user; true when their history is
+/// due to be trimmed.
+#[implement(super::Service)]
+fn trim_due(&self, user_id: &UserId) -> bool {
+	let mut inserts = self.notification_inserts.lock().expect("locked");
+
+	let count = inserts.entry(user_id.to_owned()).or_default();
+	*count = count.saturating_add(1);
+	if *count < TRIM_INTERVAL {
+		return false;
+	}
+
+	inserts.remove(user_id);
+	true
+}
+
+/// Returns up to `limit` notifications of a user, newest first, starting at
+/// `from`, with the token to pass as `from` for the next page. An event is
+/// read when the user's read receipt in the room is at or after it.
+#[implement(super::Service)]
+pub async fn notifications(
+	&self,
+	user_id: &UserId,
+	from: Option<u64>,
+	limit: usize,
+	only_highlight: bool,
+) -> (Vec<Notification>, Option<u64>) {
+	type KeyVal<'a> = ((&'a UserId, u64), Record);
+
+	let from = from.unwrap_or(u64::MAX);
+	let mut notifications: Vec<(u64, Notification)> = self
+		.db
+		.useridcount_notification
+		.rev_stream_from(&(user_id, from))
+		.ignore_err()
+		.ready_take_while(|((user, _), _): &KeyVal<'_>| *user == user_id)
+		.ready_filter(|(_, record)| !only_highlight || record.highlight)
+		.filter_map(async |((_, count), record): KeyVal<'_>| {
+			self.notification(user_id, count, record)
+				.await
+				.map(|notification| (count, notification))
+		})
+		.take(limit.saturating_add(1))
+		.collect()
+		.await;
+
+	let next = (notifications.len() > limit)
+		.then(|| notifications.pop())
+		.flatten()
+		.map(|(count, _)| count);
+
+	let notifications = notifications
+		.into_iter()
+		.map(|(_, notification)| notification)
+		.collect();
+
+	(notifications, next)
+}
+
+/// Returns the unread highlights of a user recorded after `since` and before
+/// `before`, oldest first, along with the count of the last notification
+/// looked at, to be passed as `since` next time.
+#[implement(super::Service)]
+pub(super) async fn unread_highlights(
+	&self,
+	user_id: &UserId,
+	since: u64,
+	before: MilliSecondsSinceUnixEpoch,
+) -> (Vec<Notification>, u64) {
+	type KeyVal<'a> = ((&'a UserId, u64), Record);
+
+	let mut last = since;
+	let notifications = self
+		.db
+		.useridcount_notification
+		.stream_from(&(user_id, since.saturating_add(1)))
+		.ignore_err()
+		.ready_take_while(|((user, _), record): &KeyVal<'_>| {
+			*user == user_id && record.ts <= before
+		})
+		.inspect(|((_, count), _)| last = *count)
+		.ready_filter(|(_, record)| record.highlight)
+		.filter_map(async |((_, count), record)| self.notification(user_id, count, record).await)
+		.ready_filter(|notification| !notification.read)
+		.collect()
+		.await;
+
+	(notifications, last)
+}
+
+#[implement(super::Service)]
+async fn notification(
+	&self,
+	user_id: &UserId,
+	count: u64,
+	record: Record,
+) -> Option<Notification> {
+	let pdu = self
+		.services
+		.timeline
+		.get_pdu(&record.event_id)
+		.await
+		.ok()?;
+
+	let read = self
+		.services
+		.user
+		.last_notification_read(user_id, &record.room_id)
+		.await >= count;
+
+	Some(Notification {
+		actions: record.actions,
+		event: pdu.to_format(),
+		profile_tag: None,
+		read,
+		room_id: record.room_id,
+		ts: record.ts,
+	})
+}
@@ -0,0 +1,75 @@
+use std::{fs, time::Duration};
+
+use lettre::{
+	Address, AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+	address::Envelope,
+	transport::smtp::{authentication::Credentials, extension::ClientId},
+};
+use ruma::ServerName;
+use tuwunel_core::{
+	Config, Result, err,
+	utils::{
+		self,
+		time::{now_secs, rfc2822_from_seconds},
+	},
+};
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+const MESSAGE_ID_LENGTH: usize = 32;
+
+pub(crate) type Mailer = AsyncSmtpTransport<Tokio1Executor>;
+
+/// Builds the transport to the configured SMTP relay, if there is one.
+pub(super) fn mailer(config: &Config, server_name: &ServerName) -> Result<Option<Mailer>> {
+	let Some(relay) = config.smtp_relay.as_deref() else {
+		return Ok(None);
+	};
+
+	let mut builder = Mailer::from_url(relay)
+		.map_err(|e| err!(Config("smtp_relay", "Invalid SMTP relay URL: {e}")))?
+		.hello_name(ClientId::Domain(server_name.to_string()))
+		.timeout(Some(SMTP_TIMEOUT));
+
+	if let Some(username) = &config.smtp_username {
+		let password = match &config.smtp_password_file {
+			| Some(path) => fs::read_to_string(path)
+				.map_err(|e| err!(Config("smtp_password_file", "Failed to read: {e}")))?
+				.trim_end()
+				.to_owned(),
+			| None => config.smtp_password.clone().unwrap_or_default(),
+		};
+
+		builder = builder.credentials(Credentials::new(username.clone(), password));
+	}
+
+	Ok(Some(builder.build()))
+}
+
+/// The Date and Message-ID headers required of every message, which relays
+/// do not always add.
+pub(crate) fn origin_headers(server_name: &ServerName) -> Result<String> {
+	let date = rfc2822_from_seconds(now_secs().try_into()?);
+	let id = utils::random_string(MESSAGE_ID_LENGTH);
+
+	Ok(format!("Date: {date}\r\nMessage-ID: <{id}@{server_name}>\r\n"))
+}
+
+/// Delivers a message, headers included, through the SMTP relay.
+pub(crate) async fn send_mail(mailer: &Mailer, from: &str, to: &str, message: &str) -> Result {
+	let address = |address: &str| {
+		address
+			.parse::<Address>()
+			.map_err(|e| err!(Request(InvalidParam("Invalid email address {address:?}: {e}"))))
+	};
+
+	let envelope = Envelope::new(Some(address(from)?), vec![address(to)?])
+		.map_err(|e| err!("Invalid email envelope: {e}"))?;
+
+	mailer
+		.send_raw(&envelope, message.as_bytes())
+		.await
+		.map_err(|e| err!(BadServerResponse("SMTP relay failed to send: {e}")))?;
+
+	Ok(())
+}
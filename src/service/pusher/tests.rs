@@ -0,0 +1,23 @@
+use super::is_email_address;
+
+#[test]
+fn email_addresses() {
+	assert!(is_email_address("alice@example.com"));
+	assert!(is_email_address("alice+matrix@mail.example.com"));
+}
+
+#[test]
+fn email_addresses_need_local_part_and_domain() {
+	assert!(!is_email_address("alice"));
+	assert!(!is_email_address("@example.com"));
+	assert!(!is_email_address("alice@"));
+	assert!(!is_email_address(""));
+}
+
+#[test]
+fn email_addresses_reject_header_injection() {
+	assert!(!is_email_address("alice@example.com\r\nBcc: eve@example.com"));
+	assert!(!is_email_address("alice@example.com>, <eve@example.com"));
+	assert!(!is_email_address("alice smith@example.com"));
+	assert!(!is_email_address("alice@example.com\0"));
+}
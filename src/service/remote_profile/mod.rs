@@ -0,0 +1,169 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use futures::{StreamExt, future::join4};
+use ruma::{
+	OwnedMxcUri, UserId,
+	api::federation::query::get_profile_information,
+	events::room::member::{MembershipState, RoomMemberEventContent},
+};
+use tuwunel_core::{Err, Result, debug, debug_warn, implement, utils::time::now_millis};
+use tuwunel_database::{Deserialized, Map};
+
+/// Caches the profiles of remote users. The profiles are kept in the same
+/// tables as those of local users; this service only tracks when each was
+/// last fetched from the user's server.
+pub struct Service {
+	db: Data,
+	services: Arc<crate::services::OnceServices>,
+}
+
+struct Data {
+	userid_remoteprofilefetched: Arc<Map>,
+}
+
+/// Profile of a remote user as last returned by their server.
+#[derive(Clone, Debug, Default)]
+pub struct RemoteProfile {
+	pub displayname: Option<String>,
+	pub avatar_url: Option<OwnedMxcUri>,
+	pub blurhash: Option<String>,
+	pub tz: Option<String>,
+	pub custom_profile_fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				userid_remoteprofilefetched: args.db["userid_remoteprofilefetched"].clone(),
+			},
+			services: args.services.clone(),
+		}))
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Returns the profile of a remote user, querying their server when it was
+/// not fetched within `remote_profile_cache_ttl` or changed since. The
+/// profile we have is returned when their server can't be reached.
+#[implement(Service)]
+pub async fn profile(&self, user_id: &UserId) -> Result<RemoteProfile> {
+	if self.is_fresh(user_id).await {
+		return Ok(self.stored(user_id).await);
+	}
+
+	match self.fetch(user_id).await {
+		| Ok(profile) => Ok(profile),
+		| Err(e) => {
+			debug_warn!(%user_id, "Failed to fetch remote profile: {e}");
+			if !self.services.users.exists(user_id).await {
+				return Err(e);
+			}
+
+			Ok(self.stored(user_id).await)
+		},
+	}
+}
+
+/// Queries the profile of a remote user from their server and stores it.
+#[implement(Service)]
+#[tracing::instrument(level = "debug", skip(self))]
+pub async fn fetch(&self, user_id: &UserId) -> Result<RemoteProfile> {
+	if self.services.globals.user_is_local(user_id) {
+		return Err!(Request(InvalidParam("User is not a remote user.")));
+	}
+
+	let response = self
+		.services
+		.sending
+		.send_federation_request(user_id.server_name(), get_profile_information::v1::Request {
+			user_id: user_id.to_owned(),
+			field: None,
+		})
+		.await?;
+
+	if !self.services.users.exists(user_id).await {
+		self.services
+			.users
+			.create(user_id, None, None)
+			.await?;
+	}
+
+	let users = &self.services.users;
+	users.set_displayname(user_id, response.displayname.clone());
+	users.set_avatar_url(user_id, response.avatar_url.clone());
+	users.set_blurhash(user_id, response.blurhash.clone());
+	users.set_timezone(user_id, response.tz.clone());
+	for (profile_key, profile_key_value) in &response.custom_profile_fields {
+		users.set_profile_key(user_id, profile_key, Some(profile_key_value.clone()));
+	}
+
+	self.db
+		.userid_remoteprofilefetched
+		.raw_put(user_id, now_millis());
+
+	Ok(RemoteProfile {
+		displayname: response.displayname,
+		avatar_url: response.avatar_url,
+		blurhash: response.blurhash,
+		tz: response.tz,
+		custom_profile_fields: response.custom_profile_fields,
+	})
+}
+
+/// Marks the profile of a remote user stale when a member event carries a
+/// displayname or avatar other than the one we have, so it is fetched again
+/// on the next lookup.
+#[implement(Service)]
+pub async fn update_from_member(&self, user_id: &UserId, content: &RoomMemberEventContent) {
+	if content.membership != MembershipState::Join || self.services.globals.user_is_local(user_id)
+	{
+		return;
+	}
+
+	let users = &self.services.users;
+	let displayname = users.displayname(user_id).await.ok();
+	let avatar_url = users.avatar_url(user_id).await.ok();
+	if displayname == content.displayname && avatar_url == content.avatar_url {
+		return;
+	}
+
+	debug!(%user_id, "Remote profile changed in member event");
+	self.db
+		.userid_remoteprofilefetched
+		.remove(user_id);
+}
+
+#[implement(Service)]
+async fn stored(&self, user_id: &UserId) -> RemoteProfile {
+	let users = &self.services.users;
+	let (displayname, avatar_url, blurhash, tz) = join4(
+		users.displayname(user_id),
+		users.avatar_url(user_id),
+		users.blurhash(user_id),
+		users.timezone(user_id),
+	)
+	.await;
+
+	RemoteProfile {
+		displayname: displayname.ok(),
+		avatar_url: avatar_url.ok(),
+		blurhash: blurhash.ok(),
+		tz: tz.ok(),
+		custom_profile_fields: users.all_profile_keys(user_id).collect().await,
+	}
+}
+
+#[implement(Service)]
+async fn is_fresh(&self, user_id: &UserId) -> bool {
+	let ttl = Duration::from_secs(self.services.config.remote_profile_cache_ttl);
+	let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+
+	self.db
+		.userid_remoteprofilefetched
+		.get(user_id)
+		.await
+		.deserialized()
+		.is_ok_and(|fetched_at: u64| now_millis().saturating_sub(fetched_at) < ttl)
+}
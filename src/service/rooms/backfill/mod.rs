@@ -0,0 +1,302 @@
+use std::{collections::BTreeSet, iter::once, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::{StreamExt, pin_mut};
+use ruma::{
+	EventId, OwnedEventId, OwnedServerName, RoomId, api::federation::backfill::get_backfill,
+	events::TimelineEventType, uint,
+};
+use tokio::time::sleep;
+use tuwunel_core::{
+	Result, debug, debug_info, debug_warn, implement,
+	matrix::{Event, PduCount},
+	utils::{ReadyExt, stream::TryIgnore},
+};
+
+pub struct Service {
+	services: Arc<crate::services::OnceServices>,
+}
+
+/// Outcome of filling the history of a room.
+#[derive(Debug, Default)]
+pub struct BackfillStats {
+	/// Events found referencing previous events we don't have.
+	pub gaps: usize,
+
+	/// Federation requests made.
+	pub requests: usize,
+
+	/// Events received from other servers.
+	pub fetched: usize,
+}
+
+/// Most rounds of backfilling the start of a room's timeline in a full
+/// backfill.
+const FULL_BACKFILL_ROUNDS: usize = 1000;
+
+/// Servers in the room asked for missing events, besides the sender's.
+const GAP_SERVERS: usize = 3;
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self { services: args.services.clone() }))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		let interval = self.services.server.config.backfill_interval;
+		if interval == 0 {
+			return Ok(());
+		}
+
+		let interval = Duration::from_secs(interval);
+		while self.services.server.running() {
+			tokio::select! {
+				() = self.services.server.until_shutdown() => break,
+				() = sleep(interval) => {
+					self.fill_gaps().await;
+				},
+			}
+		}
+
+		Ok(())
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// One pass of the worker: fills gaps in the recent history of rooms with
+/// local users until the per-run request budget is spent.
+#[implement(Service)]
+#[tracing::instrument(name = "backfill", level = "debug", skip_all)]
+async fn fill_gaps(&self) {
+	let config = &self.services.server.config;
+	let mut budget = config.backfill_max_requests;
+	let mut stats = BackfillStats::default();
+
+	let rooms = self
+		.services
+		.state_cache
+		.server_rooms(self.services.globals.server_name());
+
+	pin_mut!(rooms);
+	while let Some(room_id) = rooms.next().await {
+		if budget == 0 || !self.services.server.running() {
+			break;
+		}
+
+		if self.services.metadata.is_disabled(room_id).await
+			|| self.services.metadata.is_purged(room_id).await
+		{
+			continue;
+		}
+
+		let room = self
+			.fill_room_gaps(room_id, Some(config.backfill_scan_depth), budget)
+			.await;
+
+		budget = budget.saturating_sub(room.requests);
+		stats.gaps = stats.gaps.saturating_add(room.gaps);
+		stats.requests = stats.requests.saturating_add(room.requests);
+		stats.fetched = stats.fetched.saturating_add(room.fetched);
+	}
+
+	if stats.gaps > 0 {
+		debug_info!(?stats, "Backfill pass complete");
+	}
+}
+
+/// Backfills the entire history of a room: events missing anywhere in its
+/// timeline are fetched and the start of the timeline is extended until the
+/// room's create event is reached or no server has anything further.
+#[implement(Service)]
+#[tracing::instrument(skip(self), level = "info")]
+pub async fn backfill_room(&self, room_id: &RoomId) -> Result<BackfillStats> {
+	// An explicit backfill re-admits a purged room into automatic backfill.
+	self.services.metadata.unmark_purged(room_id);
+
+	let mut stats = self
+		.fill_room_gaps(room_id, None, usize::MAX)
+		.await;
+
+	let mut first = None;
+	for _ in 0..FULL_BACKFILL_ROUNDS {
+		let (count, pdu) = self
+			.services
+			.timeline
+			.first_item_in_room(room_id)
+			.await?;
+
+		if first == Some(count) || *pdu.kind() == TimelineEventType::RoomCreate {
+			break;
+		}
+
+		first = Some(count);
+		stats.requests = stats.requests.saturating_add(1);
+		self.services
+			.timeline
+			.backfill_if_required(room_id, PduCount::min())
+			.await?;
+	}
+
+	Ok(stats)
+}
+
+/// Finds events in the room whose previous events we don't have and asks
+/// other servers for the missing events. With a depth only that many of the
+/// most recent events are inspected. The start of the timeline is left to
+/// backfill.
+#[implement(Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+async fn fill_room_gaps(
+	&self,
+	room_id: &RoomId,
+	depth: Option<usize>,
+	budget: usize,
+) -> BackfillStats {
+	let mut stats = BackfillStats::default();
+	let gaps = self.find_gaps(room_id, depth).await;
+	stats.gaps = gaps.len();
+
+	for (event_id, origin) in gaps {
+		if stats.requests >= budget {
+			break;
+		}
+
+		let servers: Vec<OwnedServerName> = self
+			.services
+			.state_cache
+			.room_servers(room_id)
+			.ready_filter(|server| !self.services.globals.server_is_ours(server))
+			.ready_filter(|server| server.as_str() != origin.as_str())
+			.take(GAP_SERVERS)
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		let origin = once(origin).filter(|origin| !self.services.globals.server_is_ours(origin));
+		for server in origin.chain(servers) {
+			if stats.requests >= budget {
+				break;
+			}
+
+			stats.requests = stats.requests.saturating_add(1);
+			match self
+				.fetch_missing(room_id, &server, &event_id)
+				.await
+			{
+				| Ok(fetched) => {
+					stats.fetched = stats.fetched.saturating_add(fetched);
+					break;
+				},
+				| Err(e) => {
+					debug_warn!(%server, %event_id, "Failed to fetch missing events: {e}");
+				},
+			}
+		}
+	}
+
+	stats
+}
+
+/// Returns the events in the room referencing previous events we don't have,
+/// with the server of their sender. The oldest edge of the timeline always
+/// references unknown events; extending it is backfill's job, so backfilled
+/// events and the first event in the room are not gaps.
+#[implement(Service)]
+async fn find_gaps(
+	&self,
+	room_id: &RoomId,
+	depth: Option<usize>,
+) -> BTreeSet<(OwnedEventId, OwnedServerName)> {
+	let first = self
+		.services
+		.timeline
+		.first_item_in_room(room_id)
+		.await
+		.map(|(count, _)| count)
+		.ok();
+
+	let pdus = self
+		.services
+		.timeline
+		.pdus_rev(None, room_id, None)
+		.ignore_err()
+		.take(depth.unwrap_or(usize::MAX))
+		.ready_take_while(|(count, _)| matches!(count, PduCount::Normal(_)))
+		.ready_filter(|(count, _)| Some(*count) != first);
+
+	let mut gaps = BTreeSet::new();
+	pin_mut!(pdus);
+	while let Some((_, pdu)) = pdus.next().await {
+		for prev_event in pdu.prev_events() {
+			if self.is_missing(prev_event).await {
+				gaps.insert((pdu.event_id().to_owned(), pdu.sender().server_name().to_owned()));
+				break;
+			}
+		}
+	}
+
+	gaps
+}
+
+/// Whether an event is missing from the timeline. Events only stored as
+/// outliers are missing, unless they were soft failed or rejected and so are
+/// never added to the timeline.
+#[implement(Service)]
+async fn is_missing(&self, event_id: &EventId) -> bool {
+	self.services
+		.timeline
+		.get_pdu_id(event_id)
+		.await
+		.is_err()
+		&& !self
+			.services
+			.pdu_metadata
+			.is_event_soft_failed(event_id)
+			.await
+		&& !self
+			.services
+			.pdu_metadata
+			.is_event_rejected(event_id)
+			.await
+}
+
+/// Asks a server for the events preceding `event_id` and adds them to the
+/// timeline as backfilled events, the same way paginating past the start of
+/// the timeline does, so clients see them in `/messages` and sync. Returns the
+/// number of events received.
+#[implement(Service)]
+async fn fetch_missing(
+	&self,
+	room_id: &RoomId,
+	server: &OwnedServerName,
+	event_id: &OwnedEventId,
+) -> Result<usize> {
+	let request = get_backfill::v1::Request {
+		room_id: room_id.to_owned(),
+		v: vec![event_id.clone()],
+		limit: uint!(100),
+	};
+
+	debug!(%server, %event_id, "Requesting backfill of missing events");
+	let response = self
+		.services
+		.sending
+		.send_federation_request(server, request)
+		.await?;
+
+	let fetched = response.pdus.len();
+	for pdu in response.pdus {
+		if let Err(e) = self
+			.services
+			.timeline
+			.backfill_pdu(room_id, server, pdu)
+			.await
+		{
+			debug_warn!("Failed to add missing event in room {room_id}: {e}");
+		}
+	}
+
+	Ok(fetched)
+}
@@ -4,6 +4,7 @@
 use ruma::RoomId;
 use tuwunel_core::{
 	Result, debug,
+	matrix::PduCount,
 	result::LogErr,
 	trace,
 	utils::{ReadyExt, future::BoolExt},
@@ -194,4 +195,35 @@ pub async fn delete_room(
 		debug!("Successfully deleted room {room_id} from our database");
 		Ok(())
 	}
+
+	/// Purges the history of a room preceding the exclusive `until` count.
+	/// Non-state events are removed from the timeline and its indexes while
+	/// state events and forward extremities are kept, leaving the room's
+	/// current state intact. Returns the number of events removed.
+	pub async fn purge_history(
+		&self,
+		room_id: &RoomId,
+		until: PduCount,
+		_state_lock: &RoomMutexGuard,
+	) -> Result<usize> {
+		debug!(?room_id, ?until, "Purging room history");
+		let purged = self
+			.services
+			.timeline
+			.purge_pdus_until(room_id, until)
+			.await?;
+
+		if let PduCount::Normal(until) = until {
+			debug!("Deleting the room's read receipts preceding the purge");
+			self.services
+				.read_receipt
+				.delete_read_receipts_until(room_id, until)
+				.await;
+		}
+
+		self.services.metadata.mark_purged(room_id);
+
+		debug!("Purged {purged} events from the history of {room_id}");
+		Ok(purged)
+	}
 }
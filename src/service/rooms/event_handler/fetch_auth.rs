@@ -106,7 +106,7 @@ async fn fetch_auth_chain(
 	// a. Look in the main timeline (pduid_pdu tree)
 	// b. Look at outlier pdu tree
 	// (get_pdu_json checks both)
-	if let Ok(local_pdu) = self.services.timeline.get_pdu(event_id).await {
+	if let Ok(local_pdu) = self.event_fetch(event_id).await {
 		trace!(?event_id, "Found in database");
 		return (event_id.to_owned(), Some(local_pdu), vec![]);
 	}
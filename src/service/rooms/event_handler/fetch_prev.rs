@@ -56,6 +56,13 @@ pub(super) async fn fetch_prev<'a, Events>(
 
 		check_room_id(room_id, &pdu)?;
 
+		// Rejected events are not walked past nor added to the timeline.
+		if pdu.rejected() {
+			debug_warn!(?prev_event_id, "Prev event was rejected");
+			graph.insert(prev_event_id.clone(), HashSet::new());
+			continue;
+		}
+
 		let limit = self.services.server.config.max_fetch_prev_events;
 		if amount > limit {
 			debug_warn!(?limit, "Max prev event limit reached!");
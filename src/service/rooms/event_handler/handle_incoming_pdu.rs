@@ -64,6 +64,16 @@ pub async fn handle_incoming_pdu<'a>(
 		return Ok(Some(pdu_id));
 	}
 
+	// Skip the PDU if we already rejected it
+	if self
+		.services
+		.pdu_metadata
+		.is_event_rejected(event_id)
+		.await
+	{
+		return Err!(Request(InvalidParam("Event has been rejected")));
+	}
+
 	// 1.1 Check the server is in the room
 	let meta_exists = self.services.metadata.exists(room_id).map(Ok);
 
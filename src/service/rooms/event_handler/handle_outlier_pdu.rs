@@ -11,7 +11,7 @@
 	warn,
 };
 
-use super::check_room_id;
+use super::{check_room_id, is_rejection};
 
 #[implement(super::Service)]
 pub(super) async fn handle_outlier_pdu(
@@ -117,6 +117,7 @@ pub(super) async fn handle_outlier_pdu(
 		.collect()
 		.await;
 
+	// Rejected events are still persisted, so they aren't fetched again.
 	state_res::auth_check(
 		&room_rules,
 		&event,
@@ -132,6 +133,11 @@ pub(super) async fn handle_outlier_pdu(
 		},
 	)
 	.inspect_ok(|()| trace!("Validation successful."))
+	.inspect_err(|e| {
+		if is_rejection(e) {
+			self.reject(event_id, &pdu_json, e);
+		}
+	})
 	.await?;
 
 	// 7. Persist the event as an outlier.
@@ -19,11 +19,15 @@
 };
 
 use async_trait::async_trait;
-use ruma::{EventId, OwnedEventId, OwnedRoomId, RoomId};
+use ruma::{
+	CanonicalJsonObject, EventId, OwnedEventId, OwnedRoomId, RoomId,
+	api::client::error::ErrorKind::{Forbidden, InvalidParam},
+};
 use tuwunel_core::{
-	Err, Result, implement,
+	Err, Error, Result, implement,
 	matrix::{Event, PduEvent},
 	utils::{MutexMap, bytes::pretty, continue_exponential_backoff},
+	warn,
 };
 
 type RoomMutexMap = MutexMap<OwnedRoomId, ()>;
@@ -109,14 +113,60 @@ fn is_backed_off(&self, event_id: &EventId, range: Range<Duration>) -> bool {
 	continue_exponential_backoff(range.start, range.end, time.elapsed(), tries)
 }
 
+/// Rejected events are kept as outliers but are treated as if they don't
+/// exist, keeping them out of state resolution.
 #[implement(Service)]
 async fn event_exists(&self, event_id: &EventId) -> bool {
 	self.services.timeline.pdu_exists(event_id).await
+		&& !self
+			.services
+			.pdu_metadata
+			.is_event_rejected(event_id)
+			.await
 }
 
+/// Fetches an event flagging it if it was rejected, so the auth rules don't
+/// accept events on its basis.
 #[implement(Service)]
 async fn event_fetch(&self, event_id: &EventId) -> Result<PduEvent> {
-	self.services.timeline.get_pdu(event_id).await
+	let rejected = self
+		.services
+		.pdu_metadata
+		.is_event_rejected(event_id)
+		.await;
+
+	// rejected events are only kept as outliers and not found by get_pdu()
+	let mut pdu = if rejected {
+		self.services
+			.timeline
+			.get_outlier_pdu(event_id)
+			.await?
+	} else {
+		self.services.timeline.get_pdu(event_id).await?
+	};
+
+	pdu.rejected = rejected;
+	Ok(pdu)
+}
+
+/// Persists an event which failed authorization as a rejected outlier, so it
+/// isn't fetched and checked again.
+#[implement(Service)]
+fn reject(&self, event_id: &EventId, pdu_json: &CanonicalJsonObject, e: &Error) {
+	warn!(%event_id, "Rejecting event: {e}");
+	self.services
+		.timeline
+		.add_pdu_outlier(event_id, pdu_json);
+
+	self.services
+		.pdu_metadata
+		.mark_event_rejected(event_id, &e.to_string());
+}
+
+/// Whether an error from `auth_check` means the event violates the auth rules,
+/// rather than that the events it is checked against could not be found.
+fn is_rejection(e: &Error) -> bool {
+	matches!(e, Error::Request(Forbidden { .. } | InvalidParam, ..))
 }
 
 fn check_room_id<Pdu: Event>(room_id: &RoomId, pdu: &Pdu) -> Result {
@@ -6,13 +6,14 @@
 	events::StateEventType,
 };
 use tuwunel_core::{
-	Err, Result, debug, debug_info, err, implement, is_equal_to,
+	Err, Error, Result, debug, debug_info, debug_warn, err, implement, is_equal_to,
 	matrix::{Event, EventTypeExt, PduEvent, StateKey, room_version, state_res},
 	trace,
 	utils::stream::{BroadbandExt, ReadyExt},
 	warn,
 };
 
+use super::is_rejection;
 use crate::rooms::{
 	state_compressor::{CompressedState, HashSetCompressStateEvent},
 	timeline::RawPduId,
@@ -48,6 +49,15 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 		return Err!(Request(InvalidParam("Event has been soft failed")));
 	}
 
+	if self
+		.services
+		.pdu_metadata
+		.is_event_rejected(incoming_pdu.event_id())
+		.await
+	{
+		return Err!(Request(InvalidParam("Event has been rejected")));
+	}
+
 	debug!("Upgrading to timeline pdu");
 	let timer = Instant::now();
 	let room_rules = room_version::rules(room_version)?;
@@ -93,11 +103,33 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 				)))
 			})?;
 
-		self.services.timeline.get_pdu(event_id).await
+		self.event_fetch(event_id).await
 	};
 
+	// Rooms joined with partial state lack most membership events until the full
+	// state is fetched, so failures to authorize against it are not conclusive;
+	// such events are held back and authorized again after the resync.
+	let partial_state = self
+		.services
+		.partial_state
+		.is_partial(room_id)
+		.await;
+
 	let event_fetch = async |event_id: OwnedEventId| self.event_fetch(&event_id).await;
-	state_res::auth_check(&room_rules, &incoming_pdu, &event_fetch, &state_fetch).await?;
+	if let Err(e) =
+		state_res::auth_check(&room_rules, &incoming_pdu, &event_fetch, &state_fetch).await
+	{
+		if !is_rejection(&e) {
+			return Err(e);
+		}
+
+		if partial_state {
+			return self.defer(origin, room_id, &incoming_pdu, &val, &e);
+		}
+
+		self.reject(incoming_pdu.event_id(), &val, &e);
+		return Err(e);
+	}
 
 	debug!("Gathering auth events");
 	let auth_events = self
@@ -121,7 +153,15 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 			.ok_or_else(|| err!(Request(NotFound("state event not found"))))
 	};
 
-	state_res::auth_check(&room_rules, &incoming_pdu, &event_fetch, &state_fetch).await?;
+	if let Err(e) =
+		state_res::auth_check(&room_rules, &incoming_pdu, &event_fetch, &state_fetch).await
+	{
+		if partial_state && is_rejection(&e) {
+			return self.defer(origin, room_id, &incoming_pdu, &val, &e);
+		}
+
+		return Err(e);
+	}
 
 	// Soft fail check before doing state res
 	debug!("Performing soft-fail check");
@@ -208,6 +248,7 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 
 		// Set the new room state to the resolved state
 		debug!("Forcing new room state");
+		let _gc = self.services.state_compressor.gc_guard().await;
 		let HashSetCompressStateEvent { shortstatehash, added, removed } = self
 			.services
 			.state_compressor
@@ -272,3 +313,24 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 
 	Ok(pdu_id)
 }
+
+#[implement(super::Service)]
+fn defer(
+	&self,
+	origin: &ServerName,
+	room_id: &RoomId,
+	incoming_pdu: &PduEvent,
+	pdu_json: &CanonicalJsonObject,
+	e: &Error,
+) -> Result<Option<RawPduId>> {
+	debug_warn!("Deferring event failing auth against partial state: {e}");
+	self.services
+		.timeline
+		.add_pdu_outlier(incoming_pdu.event_id(), pdu_json);
+
+	self.services
+		.partial_state
+		.defer_event(room_id, incoming_pdu.event_id(), origin);
+
+	Err!(Request(Unknown("Event deferred until the room's full state is known")))
+}
@@ -19,6 +19,7 @@ pub struct Service {
 struct Data {
 	disabledroomids: Arc<Map>,
 	bannedroomids: Arc<Map>,
+	purgedroomids: Arc<Map>,
 	roomid_shortroomid: Arc<Map>,
 	pduid_pdu: Arc<Map>,
 }
@@ -29,6 +30,7 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 			db: Data {
 				disabledroomids: args.db["disabledroomids"].clone(),
 				bannedroomids: args.db["bannedroomids"].clone(),
+				purgedroomids: args.db["purgedroomids"].clone(),
 				roomid_shortroomid: args.db["roomid_shortroomid"].clone(),
 				pduid_pdu: args.db["pduid_pdu"].clone(),
 			},
@@ -108,6 +110,16 @@ pub async fn is_public(&self, room_id: &RoomId) -> bool {
 #[inline]
 pub fn unban_room(&self, room_id: &RoomId) { self.db.bannedroomids.remove(room_id); }
 
+/// Records that history of the room was purged, opting it out of automatic
+/// backfill so the purged events are not fetched again.
+#[implement(Service)]
+#[inline]
+pub fn mark_purged(&self, room_id: &RoomId) { self.db.purgedroomids.insert(room_id, []); }
+
+#[implement(Service)]
+#[inline]
+pub fn unmark_purged(&self, room_id: &RoomId) { self.db.purgedroomids.remove(room_id); }
+
 #[implement(Service)]
 pub fn list_banned_rooms(&self) -> impl Stream<Item = &RoomId> + Send + '_ {
 	self.db.bannedroomids.keys().ignore_err()
@@ -124,3 +136,9 @@ pub async fn is_disabled(&self, room_id: &RoomId) -> bool {
 pub async fn is_banned(&self, room_id: &RoomId) -> bool {
 	self.db.bannedroomids.get(room_id).await.is_ok()
 }
+
+#[implement(Service)]
+#[inline]
+pub async fn is_purged(&self, room_id: &RoomId) -> bool {
+	self.db.purgedroomids.get(room_id).await.is_ok()
+}
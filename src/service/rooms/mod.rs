@@ -1,10 +1,12 @@
 pub mod alias;
 pub mod auth_chain;
+pub mod backfill;
 pub mod delete;
 pub mod directory;
 pub mod event_handler;
 pub mod lazy_loading;
 pub mod metadata;
+pub mod partial_state;
 pub mod pdu_metadata;
 pub mod read_receipt;
 pub mod search;
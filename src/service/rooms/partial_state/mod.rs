@@ -0,0 +1,331 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures::{FutureExt, Stream, StreamExt, pin_mut};
+use ruma::{
+	EventId, OwnedEventId, OwnedRoomId, OwnedServerName, RoomId, ServerName,
+	events::StateEventType,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+	sync::Notify,
+	time::{sleep, timeout},
+};
+use tuwunel_core::{Err, Result, debug, debug_warn, implement, info, utils::stream::TryIgnore};
+use tuwunel_database::{Deserialized, Ignore, Interfix, Json, Map};
+
+use crate::rooms::{short::ShortStateKey, state_compressor::HashSetCompressStateEvent};
+
+pub struct Service {
+	db: Data,
+	services: Arc<crate::services::OnceServices>,
+	pending: Notify,
+	resynced: Notify,
+}
+
+struct Data {
+	roomid_partialstate: Arc<Map>,
+	roomid_resynccount: Arc<Map>,
+	roomideventid_deferred: Arc<Map>,
+}
+
+/// A room joined with partial state (MSC3706) whose full state has not yet
+/// been fetched.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PartialState {
+	/// Our join event; the full state is fetched at this event.
+	pub event_id: OwnedEventId,
+
+	/// Servers in the room according to the resident server, asked for the
+	/// full state and sent our events until it is known.
+	pub servers: Vec<OwnedServerName>,
+}
+
+/// Time between attempts to resync rooms whose full state could not be
+/// fetched.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[async_trait]
+impl crate::Service for Service {
+	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			db: Data {
+				roomid_partialstate: args.db["roomid_partialstate"].clone(),
+				roomid_resynccount: args.db["roomid_resynccount"].clone(),
+				roomideventid_deferred: args.db["roomideventid_deferred"].clone(),
+			},
+			services: args.services.clone(),
+			pending: Notify::new(),
+			resynced: Notify::new(),
+		}))
+	}
+
+	async fn worker(self: Arc<Self>) -> Result {
+		while self.services.server.running() {
+			self.resync_rooms().await;
+			tokio::select! {
+				() = self.services.server.until_shutdown() => break,
+				() = self.pending.notified() => {},
+				() = sleep(RETRY_INTERVAL) => {},
+			}
+		}
+
+		Ok(())
+	}
+
+	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
+}
+
+/// Whether the room was joined with partial state which has not yet been
+/// resynced.
+#[implement(Service)]
+#[inline]
+pub async fn is_partial(&self, room_id: &RoomId) -> bool {
+	self.db
+		.roomid_partialstate
+		.get(room_id)
+		.await
+		.is_ok()
+}
+
+#[implement(Service)]
+pub async fn partial_state(&self, room_id: &RoomId) -> Result<PartialState> {
+	self.db
+		.roomid_partialstate
+		.get(room_id)
+		.await
+		.deserialized()
+}
+
+/// Servers known to be in a partial-state room besides those in its state.
+#[implement(Service)]
+pub async fn partial_servers(&self, room_id: &RoomId) -> Vec<OwnedServerName> {
+	self.partial_state(room_id)
+		.await
+		.map(|partial| partial.servers)
+		.unwrap_or_default()
+}
+
+/// Returns the count at which the full state of a room joined with partial
+/// state was fetched.
+#[implement(Service)]
+pub async fn resync_count(&self, room_id: &RoomId) -> Result<u64> {
+	self.db
+		.roomid_resynccount
+		.get(room_id)
+		.await
+		.deserialized()
+}
+
+/// Iterates the rooms awaiting their full state.
+#[implement(Service)]
+pub fn partial_rooms(&self) -> impl Stream<Item = (&RoomId, PartialState)> + Send + '_ {
+	self.db.roomid_partialstate.stream().ignore_err()
+}
+
+/// Records that the room was joined with partial state and wakes the worker
+/// to fetch the rest of it.
+#[implement(Service)]
+pub fn mark_partial(&self, room_id: &RoomId, partial: &PartialState) {
+	debug!(?room_id, servers = partial.servers.len(), "Room joined with partial state");
+	self.db
+		.roomid_partialstate
+		.raw_put(room_id, Json(partial));
+
+	self.pending.notify_one();
+}
+
+/// Holds back an event which failed authorization against the partial state
+/// of the room. It is authorized again once the full state has been fetched.
+#[implement(Service)]
+pub fn defer_event(&self, room_id: &RoomId, event_id: &EventId, origin: &ServerName) {
+	debug!(?room_id, ?event_id, "Deferring event until the full state is known");
+	self.db
+		.roomideventid_deferred
+		.put((room_id, event_id), origin);
+}
+
+#[implement(Service)]
+fn unmark_partial(&self, room_id: &RoomId) {
+	let count = self.services.globals.next_count();
+	self.db
+		.roomid_resynccount
+		.raw_put(room_id, *count);
+
+	self.db.roomid_partialstate.remove(room_id);
+	self.resynced.notify_waiters();
+}
+
+/// Waits up to `limit` for the full state of a partial-state room to be
+/// fetched. Returns whether the room has its full state.
+#[implement(Service)]
+pub async fn wait_full_state(&self, room_id: &RoomId, limit: Duration) -> bool {
+	let wait = async {
+		loop {
+			let resynced = self.resynced.notified();
+			pin_mut!(resynced);
+			resynced.as_mut().enable();
+			if !self.is_partial(room_id).await {
+				break;
+			}
+
+			resynced.await;
+		}
+	};
+
+	timeout(limit, wait).await.is_ok()
+}
+
+/// One pass of the worker over every partial-state room.
+#[implement(Service)]
+#[tracing::instrument(name = "partial_state", level = "debug", skip_all)]
+async fn resync_rooms(&self) {
+	let rooms: Vec<(OwnedRoomId, PartialState)> = self
+		.partial_rooms()
+		.map(|(room_id, partial)| (room_id.to_owned(), partial))
+		.collect()
+		.await;
+
+	for (room_id, partial) in rooms {
+		if !self.services.server.running() {
+			break;
+		}
+
+		if let Err(e) = self.resync_room(&room_id, &partial).await {
+			debug_warn!(%room_id, "Failed to fetch full state of room: {e}");
+		}
+	}
+}
+
+/// Fetches the state at our join event from the servers in the room and
+/// resolves it against the room's current state, completing the partial join.
+/// Events deferred while the state was partial are then authorized again.
+#[implement(Service)]
+#[tracing::instrument(skip(self, partial), level = "info")]
+pub async fn resync_room(&self, room_id: &RoomId, partial: &PartialState) -> Result {
+	let room_version = self
+		.services
+		.state
+		.get_room_version(room_id)
+		.await?;
+
+	let create_event_id = self
+		.services
+		.state_accessor
+		.room_state_get_id(room_id, &StateEventType::RoomCreate, "")
+		.await?;
+
+	let mut full_state = None;
+	for server in &partial.servers {
+		if self.services.globals.server_is_ours(server) {
+			continue;
+		}
+
+		match self
+			.services
+			.event_handler
+			.fetch_state(server, room_id, &partial.event_id, &room_version, &create_event_id)
+			.await
+		{
+			| Ok(Some(state)) => {
+				full_state = Some(state);
+				break;
+			},
+			| Ok(None) => debug_warn!(%server, "Server sent incomplete state"),
+			| Err(e) => debug_warn!(%server, "Failed to fetch state: {e}"),
+		}
+	}
+
+	let Some(state): Option<HashMap<ShortStateKey, OwnedEventId>> = full_state else {
+		return Err!("No server sent the full state at {}", partial.event_id);
+	};
+
+	let state_lock = self.services.state.mutex.lock(room_id).await;
+
+	// The current state was built from events since the join authorized against
+	// partial state; it is resolved against the full state at the join.
+	let new_state = self
+		.services
+		.event_handler
+		.resolve_state(room_id, &room_version, state)
+		.boxed()
+		.await?;
+
+	let gc_guard = self.services.state_compressor.gc_guard().await;
+	let HashSetCompressStateEvent { shortstatehash, added, removed } = self
+		.services
+		.state_compressor
+		.save_state(room_id, new_state)
+		.await?;
+
+	self.services
+		.state
+		.force_state(room_id, shortstatehash, added, removed, &state_lock)
+		.await?;
+
+	drop(gc_guard);
+
+	self.unmark_partial(room_id);
+	drop(state_lock);
+
+	let joined = self
+		.services
+		.state_cache
+		.room_joined_count(room_id)
+		.await
+		.unwrap_or(0);
+
+	info!(%room_id, joined, "Fetched full state of room");
+	self.replay_deferred(room_id).await;
+
+	Ok(())
+}
+
+/// Authorizes the events deferred while the room had partial state against
+/// its full state, adding those which pass to the timeline.
+#[implement(Service)]
+async fn replay_deferred(&self, room_id: &RoomId) {
+	let prefix = (room_id, Interfix);
+	let deferred: Vec<(OwnedEventId, OwnedServerName)> = self
+		.db
+		.roomideventid_deferred
+		.stream_prefix(&prefix)
+		.ignore_err()
+		.map(|((_, event_id), origin): ((Ignore, &EventId), &ServerName)| {
+			(event_id.to_owned(), origin.to_owned())
+		})
+		.collect()
+		.await;
+
+	let _lock = self
+		.services
+		.event_handler
+		.mutex_federation
+		.lock(room_id)
+		.await;
+
+	for (event_id, origin) in deferred {
+		self.db
+			.roomideventid_deferred
+			.del((room_id, &event_id));
+
+		let Ok(value) = self
+			.services
+			.timeline
+			.get_outlier_pdu_json(&event_id)
+			.await
+		else {
+			continue;
+		};
+
+		if let Err(e) = self
+			.services
+			.event_handler
+			.handle_incoming_pdu(&origin, room_id, &event_id, value, true)
+			.boxed()
+			.await
+		{
+			debug_warn!(%room_id, %event_id, "Deferred event failed: {e}");
+		}
+	}
+}
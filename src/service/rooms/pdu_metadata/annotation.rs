@@ -0,0 +1,71 @@
+use ruma::{EventId, UserId};
+use tuwunel_core::{debug, implement};
+
+/// Counts a reaction to the target event under its key. A user's reactions
+/// with the same key are counted once.
+#[implement(super::Service)]
+pub async fn add_annotation(
+	&self,
+	target: &EventId,
+	key: &str,
+	sender: &UserId,
+	event_id: &EventId,
+) {
+	if self.has_annotated(target, key, sender).await {
+		debug!(%target, ?key, %sender, "Reaction already counted");
+		return;
+	}
+
+	let count = self.db.annotation_count(target, key).await;
+	self.db
+		.set_annotation(target, key, sender, event_id);
+	self.db
+		.set_annotation_count(target, key, count.saturating_add(1));
+}
+
+/// Uncounts a reaction when it is redacted or purged.
+#[implement(super::Service)]
+pub async fn remove_annotation(
+	&self,
+	target: &EventId,
+	key: &str,
+	sender: &UserId,
+	event_id: &EventId,
+) {
+	let Ok(counted) = self.db.annotation(target, key, sender).await else {
+		return;
+	};
+
+	// Only the reaction which was counted is uncounted.
+	if counted != event_id.as_str() {
+		return;
+	}
+
+	let count = self.db.annotation_count(target, key).await;
+	self.db.del_annotation(target, key, sender);
+	self.db
+		.set_annotation_count(target, key, count.saturating_sub(1));
+}
+
+/// Whether the user has reacted to the target event with the key.
+#[implement(super::Service)]
+pub async fn has_annotated(&self, target: &EventId, key: &str, sender: &UserId) -> bool {
+	self.db
+		.annotation(target, key, sender)
+		.await
+		.is_ok()
+}
+
+/// Returns the keys of the reactions to the target event with their counts,
+/// most used first.
+#[implement(super::Service)]
+pub async fn annotations(&self, target: &EventId) -> Vec<(String, u64)> {
+	let mut annotations = self.db.annotation_counts(target).await;
+	annotations.sort_by(|(a_key, a_count), (b_key, b_count)| {
+		b_count
+			.cmp(a_count)
+			.then_with(|| a_key.cmp(b_key))
+	});
+
+	annotations
+}
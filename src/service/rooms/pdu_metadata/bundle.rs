@@ -0,0 +1,281 @@
+use std::sync::Arc;
+
+use futures::StreamExt;
+use ruma::{
+	UserId,
+	events::{AnyMessageLikeEvent, relation::BundledThread},
+	serde::Raw,
+};
+use serde::Deserialize;
+use serde_json::{Map, json};
+use tuwunel_core::{
+	Result, implement,
+	matrix::{
+		Event,
+		pdu::{PduCount, PduEvent, PduId, RawPduId},
+	},
+	utils::{
+		math::ruma_from_usize,
+		stream::{IterStream, ReadyExt},
+	},
+};
+
+use crate::rooms::short::ShortRoomId;
+
+/// The events relating to an event which are bundled into it, by relation
+/// type, as their counts, oldest first.
+#[derive(Default)]
+pub(super) struct Related {
+	replace: Vec<u64>,
+	thread: Vec<u64>,
+	reference: Vec<u64>,
+}
+
+#[derive(Deserialize)]
+struct ExtractRelType {
+	#[serde(rename = "m.relates_to")]
+	relates_to: RelType,
+}
+
+#[derive(Deserialize)]
+struct RelType {
+	rel_type: Option<String>,
+}
+
+/// Adds the latest edit, the thread summary and the references of an event
+/// to its `unsigned.m.relations`, as seen by the user.
+#[implement(super::Service)]
+pub async fn add_bundled_aggregations(
+	&self,
+	user_id: &UserId,
+	count: PduCount,
+	pdu: &mut PduEvent,
+) -> Result {
+	let PduCount::Normal(count) = count else {
+		return Ok(());
+	};
+
+	// Redacted events have their relations stripped.
+	if pdu.is_redacted() {
+		return Ok(());
+	}
+
+	let shortroomid = self
+		.services
+		.short
+		.get_shortroomid(pdu.room_id())
+		.await?;
+
+	let related = self.related(shortroomid, count).await;
+	let mut relations = Map::new();
+
+	if let Some(edit) = self
+		.latest_edit(shortroomid, pdu, &related.replace)
+		.await
+	{
+		let edit: Raw<AnyMessageLikeEvent> = edit.to_format();
+		relations.insert("m.replace".into(), serde_json::to_value(edit)?);
+	}
+
+	let latest = related
+		.thread
+		.iter()
+		.rev()
+		.stream()
+		.filter_map(async |&count| {
+			self.shown_related(user_id, shortroomid, count)
+				.await
+		})
+		.boxed()
+		.next()
+		.await;
+
+	if let Some(latest) = latest {
+		let current_user_participated = pdu.sender() == user_id
+			|| self
+				.services
+				.threads
+				.get_participants(&pdu_id(shortroomid, count))
+				.await
+				.is_ok_and(|users| users.iter().any(|user| user == user_id));
+
+		let thread = BundledThread {
+			latest_event: latest.to_format(),
+			count: ruma_from_usize(related.thread.len()),
+			current_user_participated,
+		};
+
+		relations.insert("m.thread".into(), serde_json::to_value(thread)?);
+	}
+
+	let chunk: Vec<_> = related
+		.reference
+		.iter()
+		.stream()
+		.filter_map(async |&count| {
+			self.shown_related(user_id, shortroomid, count)
+				.await
+		})
+		.map(|pdu| json!({ "event_id": pdu.event_id() }))
+		.collect()
+		.await;
+
+	if !chunk.is_empty() {
+		relations.insert("m.reference".into(), json!({ "chunk": chunk }));
+	}
+
+	if relations.is_empty() {
+		return Ok(());
+	}
+
+	pdu.set_relations(relations)
+}
+
+/// Returns a related event if it can be bundled for the user, as /messages
+/// would show it: visible to them and not sent by a user or server they ignore.
+#[implement(super::Service)]
+async fn shown_related(
+	&self,
+	user_id: &UserId,
+	shortroomid: ShortRoomId,
+	count: u64,
+) -> Option<PduEvent> {
+	let pdu = self
+		.services
+		.timeline
+		.get_pdu_from_id(&pdu_id(shortroomid, count))
+		.await
+		.ok()?;
+
+	let sender = pdu.sender();
+	if self
+		.services
+		.config
+		.forbidden_remote_server_names
+		.is_match(sender.server_name().host())
+	{
+		return None;
+	}
+
+	if self
+		.services
+		.users
+		.user_is_ignored(sender, user_id)
+		.await
+	{
+		return None;
+	}
+
+	self.services
+		.state_accessor
+		.user_can_see_event(user_id, pdu.room_id(), pdu.event_id())
+		.await
+		.then_some(pdu)
+}
+
+/// Returns the most recent edit of an event which is valid: sent by the same
+/// user, with the same type and not redacted.
+#[implement(super::Service)]
+async fn latest_edit(
+	&self,
+	shortroomid: ShortRoomId,
+	original: &PduEvent,
+	replace: &[u64],
+) -> Option<PduEvent> {
+	replace
+		.iter()
+		.stream()
+		.filter_map(async |&count| {
+			self.services
+				.timeline
+				.get_pdu_from_id(&pdu_id(shortroomid, count))
+				.await
+				.ok()
+		})
+		.ready_filter(|edit| {
+			edit.sender() == original.sender()
+				&& edit.kind() == original.kind()
+				&& edit.state_key().is_none()
+				&& !edit.is_redacted()
+		})
+		.collect::<Vec<_>>()
+		.await
+		.into_iter()
+		.max_by(|a, b| {
+			(a.origin_server_ts(), a.event_id()).cmp(&(b.origin_server_ts(), b.event_id()))
+		})
+}
+
+/// Returns the events relating to an event, from the cache when possible.
+#[implement(super::Service)]
+async fn related(&self, shortroomid: ShortRoomId, target: u64) -> Arc<Related> {
+	if let Some(related) = self
+		.related_cache
+		.lock()
+		.expect("locked")
+		.get_mut(&target)
+	{
+		return related.clone();
+	}
+
+	let related = self
+		.db
+		.relations_to(target)
+		.await
+		.into_iter()
+		.stream()
+		.then(async |(count, rel_type)| match rel_type {
+			| Some(rel_type) => (count, Some(rel_type)),
+			| None => (count, self.relation_type(shortroomid, count).await),
+		})
+		.ready_fold_default(|mut related: Related, (count, rel_type)| {
+			match rel_type.as_deref() {
+				| Some("m.replace") => related.replace.push(count),
+				| Some("m.thread") => related.thread.push(count),
+				| Some("m.reference") => related.reference.push(count),
+				| _ => {},
+			}
+
+			related
+		})
+		.await;
+
+	let related = Arc::new(related);
+	self.related_cache
+		.lock()
+		.expect("locked")
+		.insert(target, related.clone());
+
+	related
+}
+
+#[implement(super::Service)]
+pub(super) fn invalidate_related(&self, target: u64) {
+	self.related_cache
+		.lock()
+		.expect("locked")
+		.remove(&target);
+}
+
+/// Reads the relation type from the relating event, for relations recorded
+/// without it.
+#[implement(super::Service)]
+async fn relation_type(&self, shortroomid: ShortRoomId, count: u64) -> Option<String> {
+	self.services
+		.timeline
+		.get_pdu_from_id(&pdu_id(shortroomid, count))
+		.await
+		.ok()?
+		.get_content::<ExtractRelType>()
+		.ok()?
+		.relates_to
+		.rel_type
+}
+
+fn pdu_id(shortroomid: ShortRoomId, count: u64) -> RawPduId {
+	PduId {
+		shortroomid,
+		count: PduCount::Normal(count),
+	}
+	.into()
+}
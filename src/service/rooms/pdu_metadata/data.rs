@@ -14,7 +14,7 @@
 		u64_from_u8,
 	},
 };
-use tuwunel_database::{Interfix, Map};
+use tuwunel_database::{Deserialized, Interfix, Map};
 
 use crate::rooms::{
 	short::ShortRoomId,
@@ -25,6 +25,9 @@ pub(super) struct Data {
 	tofrom_relation: Arc<Map>,
 	referencedevents: Arc<Map>,
 	softfailedeventids: Arc<Map>,
+	eventid_rejection: Arc<Map>,
+	targetkey_annotationcount: Arc<Map>,
+	targetkeysender_annotation: Arc<Map>,
 	services: Arc<crate::services::OnceServices>,
 }
 
@@ -35,17 +38,61 @@ pub(super) fn new(args: &crate::Args<'_>) -> Self {
 			tofrom_relation: db["tofrom_relation"].clone(),
 			referencedevents: db["referencedevents"].clone(),
 			softfailedeventids: db["softfailedeventids"].clone(),
+			eventid_rejection: db["eventid_rejection"].clone(),
+			targetkey_annotationcount: db["targetkey_annotationcount"].clone(),
+			targetkeysender_annotation: db["targetkeysender_annotation"].clone(),
 			services: args.services.clone(),
 		}
 	}
 
 	#[inline]
-	pub(super) fn add_relation(&self, from: u64, to: u64) {
+	pub(super) fn add_relation(&self, from: u64, to: u64, rel_type: Option<&str>) {
 		const BUFSIZE: usize = size_of::<u64>() * 2;
 
 		let key: &[u64] = &[to, from];
 		self.tofrom_relation
-			.aput_raw::<BUFSIZE, _, _>(key, []);
+			.aput_raw::<BUFSIZE, _, _>(key, rel_type.unwrap_or_default());
+	}
+
+	#[inline]
+	pub(super) fn delete_relation(&self, from: u64, to: u64) {
+		const BUFSIZE: usize = size_of::<u64>() * 2;
+
+		let key: &[u64] = &[to, from];
+		self.tofrom_relation.adel::<BUFSIZE, _>(key);
+	}
+
+	pub(super) async fn delete_relations_to(&self, to: u64) {
+		let prefix = to.to_be_bytes();
+
+		self.tofrom_relation
+			.raw_keys_prefix(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| {
+				trace!("Removing key: {key:?}");
+				self.tofrom_relation.remove(key);
+			})
+			.await;
+	}
+
+	/// Returns the events relating to `to` with the type of their relation,
+	/// oldest first. The type is missing for replies and for relations
+	/// recorded before it was.
+	pub(super) async fn relations_to(&self, to: u64) -> Vec<(u64, Option<String>)> {
+		let prefix = to.to_be_bytes();
+
+		self.tofrom_relation
+			.raw_stream_prefix(&prefix)
+			.ignore_err()
+			.ready_filter_map(|(to_from, rel_type)| {
+				let from = u64_from_u8(to_from.get(8..16)?);
+				let rel_type = (!rel_type.is_empty())
+					.then(|| String::from_utf8_lossy(rel_type).into_owned());
+
+				Some((from, rel_type))
+			})
+			.collect()
+			.await
 	}
 
 	pub(super) fn get_relations<'a>(
@@ -129,6 +176,81 @@ pub(super) async fn is_event_soft_failed(&self, event_id: &EventId) -> bool {
 			.is_ok()
 	}
 
+	#[inline]
+	pub(super) fn mark_event_rejected(&self, event_id: &EventId, reason: &str) {
+		self.eventid_rejection.insert(event_id, reason);
+	}
+
+	#[inline]
+	pub(super) async fn event_rejection(&self, event_id: &EventId) -> Result<String> {
+		self.eventid_rejection
+			.get(event_id)
+			.await
+			.deserialized()
+	}
+
+	#[inline]
+	pub(super) fn set_annotation(
+		&self,
+		target: &EventId,
+		key: &str,
+		sender: &UserId,
+		event_id: &EventId,
+	) {
+		self.targetkeysender_annotation
+			.put_raw((target, key, sender), event_id.as_bytes());
+	}
+
+	#[inline]
+	pub(super) fn del_annotation(&self, target: &EventId, key: &str, sender: &UserId) {
+		self.targetkeysender_annotation
+			.del((target, key, sender));
+	}
+
+	#[inline]
+	pub(super) async fn annotation(
+		&self,
+		target: &EventId,
+		key: &str,
+		sender: &UserId,
+	) -> Result<String> {
+		self.targetkeysender_annotation
+			.qry(&(target, key, sender))
+			.await
+			.deserialized()
+	}
+
+	#[inline]
+	pub(super) async fn annotation_count(&self, target: &EventId, key: &str) -> u64 {
+		self.targetkey_annotationcount
+			.qry(&(target, key))
+			.await
+			.deserialized()
+			.unwrap_or(0)
+	}
+
+	#[inline]
+	pub(super) fn set_annotation_count(&self, target: &EventId, key: &str, count: u64) {
+		if count == 0 {
+			self.targetkey_annotationcount.del((target, key));
+		} else {
+			self.targetkey_annotationcount
+				.put((target, key), count);
+		}
+	}
+
+	pub(super) async fn annotation_counts(&self, target: &EventId) -> Vec<(String, u64)> {
+		type KeyVal<'a> = ((&'a EventId, &'a str), u64);
+
+		let prefix = (target, Interfix);
+		self.targetkey_annotationcount
+			.stream_prefix(&prefix)
+			.ignore_err()
+			.map(|((_, key), count): KeyVal<'_>| (key.to_owned(), count))
+			.collect()
+			.await
+	}
+
 	#[inline]
 	pub(super) async fn delete_all_referenced_for_room(&self, room_id: &RoomId) -> Result {
 		let prefix = (room_id, Interfix);
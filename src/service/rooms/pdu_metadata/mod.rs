@@ -1,42 +1,85 @@
+mod annotation;
+mod bundle;
 mod data;
-use std::sync::Arc;
+use std::{
+	fmt::Write,
+	sync::{Arc, Mutex},
+};
 
+use async_trait::async_trait;
 use futures::{StreamExt, future::try_join};
+use lru_cache::LruCache;
 use ruma::{EventId, RoomId, UserId, api::Direction};
 use tuwunel_core::{
 	Result,
 	matrix::{Event, PduCount},
+	utils::math::usize_from_f64,
 };
 
-use self::data::Data;
+use self::{bundle::Related, data::Data};
 
 pub struct Service {
 	services: Arc<crate::services::OnceServices>,
 	db: Data,
+	related_cache: Mutex<LruCache<u64, Arc<Related>>>,
 }
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+		let cache_size = f64::from(config.relations_cache_capacity);
+		let cache_size = cache_size * config.cache_capacity_modifier;
 		Ok(Arc::new(Self {
 			services: args.services.clone(),
 			db: Data::new(args),
+			related_cache: Mutex::new(LruCache::new(usize_from_f64(cache_size)?)),
 		}))
 	}
 
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let related_cache = self.related_cache.lock()?.len();
+		writeln!(out, "related_cache: {related_cache}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) { self.related_cache.lock().expect("locked").clear(); }
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 impl Service {
 	#[tracing::instrument(skip(self, from, to), level = "debug")]
-	pub fn add_relation(&self, from: PduCount, to: PduCount) {
+	pub fn add_relation(&self, from: PduCount, to: PduCount, rel_type: Option<&str>) {
 		match (from, to) {
-			| (PduCount::Normal(f), PduCount::Normal(t)) => self.db.add_relation(f, t),
+			| (PduCount::Normal(f), PduCount::Normal(t)) => {
+				self.db.add_relation(f, t, rel_type);
+				self.invalidate_related(t);
+			},
 			| _ => {
 				// TODO: Relations with backfilled pdus
 			},
 		}
 	}
 
+	/// Removes the relation from `from` to `to` along with every relation
+	/// targeting `from`.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn delete_relations(&self, from: PduCount, to: Option<PduCount>) {
+		let PduCount::Normal(f) = from else {
+			return;
+		};
+
+		if let Some(PduCount::Normal(t)) = to {
+			self.db.delete_relation(f, t);
+			self.invalidate_related(t);
+		}
+
+		self.db.delete_relations_to(f).await;
+		self.invalidate_related(f);
+	}
+
 	#[allow(clippy::too_many_arguments)]
 	pub async fn get_relations<'a>(
 		&'a self,
@@ -127,6 +170,24 @@ pub async fn is_event_soft_failed(&self, event_id: &EventId) -> bool {
 		self.db.is_event_soft_failed(event_id).await
 	}
 
+	/// Records that the event failed authorization, with the reason. Rejected
+	/// events are kept as outliers so they aren't fetched again.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub fn mark_event_rejected(&self, event_id: &EventId, reason: &str) {
+		self.db.mark_event_rejected(event_id, reason);
+	}
+
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn is_event_rejected(&self, event_id: &EventId) -> bool {
+		self.db.event_rejection(event_id).await.is_ok()
+	}
+
+	/// Returns why the event was rejected, or an error if it wasn't.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn event_rejection(&self, event_id: &EventId) -> Result<String> {
+		self.db.event_rejection(event_id).await
+	}
+
 	#[tracing::instrument(skip(self), level = "debug")]
 	pub async fn delete_all_referenced_for_room(&self, room_id: &RoomId) -> Result {
 		self.db
@@ -144,6 +144,21 @@ pub(super) async fn last_privateread_update(
 			.unwrap_or(0)
 	}
 
+	pub(super) async fn delete_read_receipts_until(&self, room_id: &RoomId, until: u64) {
+		type Key<'a> = (&'a RoomId, u64, &'a UserId);
+
+		let prefix = (room_id, Interfix);
+		self.readreceiptid_readreceipt
+			.keys_prefix(&prefix)
+			.ignore_err()
+			.ready_take_while(|(_, count, _): &Key<'_>| *count < until)
+			.ready_for_each(|key: Key<'_>| {
+				trace!("Removing key: {key:?}");
+				self.readreceiptid_readreceipt.del(key);
+			})
+			.await;
+	}
+
 	#[inline]
 	pub(super) async fn delete_all_read_receipts(&self, room_id: &RoomId) -> Result {
 		let prefix = (room_id, Interfix);
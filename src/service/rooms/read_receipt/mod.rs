@@ -161,6 +161,14 @@ pub async fn last_receipt_count(
 			.await
 	}
 
+	/// Removes the receipts in the room last updated before the exclusive
+	/// `until` count.
+	pub async fn delete_read_receipts_until(&self, room_id: &RoomId, until: u64) {
+		self.db
+			.delete_read_receipts_until(room_id, until)
+			.await;
+	}
+
 	pub async fn delete_all_read_receipts(&self, room_id: &RoomId) -> Result {
 		self.db.delete_all_read_receipts(room_id).await
 	}
@@ -188,6 +188,8 @@ pub async fn set_event_state(
 	const KEY_LEN: usize = size_of::<ShortEventId>();
 	const VAL_LEN: usize = size_of::<ShortStateHash>();
 
+	let _gc = self.services.state_compressor.gc_guard().await;
+
 	let shorteventid = self
 		.services
 		.short
@@ -247,6 +249,10 @@ pub async fn set_event_state(
 		.shorteventid_shortstatehash
 		.aput::<KEY_LEN, VAL_LEN, _, _>(shorteventid, shortstatehash);
 
+	self.services
+		.state_compressor
+		.gc_keep(shortstatehash);
+
 	Ok(shortstatehash)
 }
 
@@ -281,6 +287,8 @@ pub async fn append_to_state(&self, new_pdu: &PduEvent) -> Result<u64> {
 		self.db
 			.shorteventid_shortstatehash
 			.aput::<KEY_LEN, VAL_LEN, _, _>(shorteventid, p);
+
+		self.services.state_compressor.gc_keep(p);
 	}
 
 	match &new_pdu.state_key {
@@ -361,6 +369,10 @@ pub fn set_room_state(
 	self.db
 		.roomid_shortstatehash
 		.raw_aput::<BUFSIZE, _, _>(room_id, shortstatehash);
+
+	self.services
+		.state_compressor
+		.gc_keep(shortstatehash);
 }
 
 /// This fetches auth events from the current state.
@@ -525,6 +537,15 @@ pub async fn get_shortstatehash(&self, shorteventid: ShortEventId) -> Result<Sho
 		.deserialized()
 }
 
+#[implement(Service)]
+pub(super) fn delete_event_shortstatehash(&self, shorteventid: ShortEventId) {
+	const BUFSIZE: usize = size_of::<ShortEventId>();
+
+	self.db
+		.shorteventid_shortstatehash
+		.adel::<BUFSIZE, _>(shorteventid);
+}
+
 #[implement(Service)]
 pub(super) async fn delete_room_shortstatehash(
 	&self,
@@ -0,0 +1,200 @@
+use std::{
+	collections::{HashMap, HashSet},
+	mem::size_of,
+};
+
+use futures::StreamExt;
+use tuwunel_core::{
+	Result, debug, debug_info, defer, implement, info, utils,
+	utils::{ReadyExt, stream::TryIgnore, u64_from_u8},
+};
+
+use crate::rooms::short::ShortStateHash;
+
+#[derive(Debug, Default)]
+pub struct GcStats {
+	/// Number of states in the database when the collection started.
+	pub states: usize,
+
+	/// Number of states referenced by an event, a room, or another state.
+	pub referenced: usize,
+
+	/// Number of unreferenced states deleted.
+	pub deleted: usize,
+}
+
+/// Deletes states which are no longer referenced by any event, room or other
+/// state. When `dry_run` is set the database is not modified and the
+/// statistics describe what would have been done.
+///
+/// States are marked from a snapshot while the server keeps running; states
+/// written or referenced in the meantime are recorded with `gc_keep()`. Only
+/// the deletion waits for the holders of `gc_guard()`, after which those
+/// recorded states are spared.
+#[implement(super::Service)]
+#[tracing::instrument(name = "gc", level = "info", skip(self))]
+pub async fn collect_garbage(&self, dry_run: bool) -> Result<GcStats> {
+	let _run = self.gc_run.lock().await;
+
+	self.gc_kept
+		.lock()
+		.expect("locked")
+		.replace(HashSet::new());
+
+	defer! {{
+		self.gc_kept.lock().expect("locked").take();
+	}}
+
+	let parents: HashMap<ShortStateHash, ShortStateHash> = self
+		.db
+		.shortstatehash_statediff
+		.raw_stream()
+		.ignore_err()
+		.ready_filter_map(|(shortstatehash, diff)| {
+			let parent = utils::u64_from_bytes(diff.get(..size_of::<u64>())?).ok()?;
+			Some((u64_from_u8(shortstatehash), parent))
+		})
+		.collect()
+		.await;
+
+	let roots = self.referenced_roots().await;
+	let mut referenced = mark_referenced(roots, &parents);
+	let candidates = unreferenced_states(&parents, &referenced);
+	let state_hashes = self.state_hashes(&candidates).await;
+
+	debug_info!(
+		states = parents.len(),
+		referenced = referenced.len(),
+		candidates = candidates.len(),
+		"Marked states"
+	);
+
+	let _lock = self.gc_lock.write().await;
+	let kept = self
+		.gc_kept
+		.lock()
+		.expect("locked")
+		.take()
+		.unwrap_or_default();
+
+	referenced.extend(mark_referenced(kept, &parents));
+	let unreferenced = unreferenced_states(&parents, &referenced);
+
+	let stats = GcStats {
+		states: parents.len(),
+		referenced: referenced.len(),
+		deleted: unreferenced.len(),
+	};
+
+	if !dry_run {
+		self.delete_states(&unreferenced, &state_hashes);
+	}
+
+	self.stateinfo_cache
+		.lock()
+		.expect("locked")
+		.clear();
+
+	info!(?stats, dry_run, "State garbage collection complete");
+
+	Ok(stats)
+}
+
+/// Collects the states referenced by events and rooms.
+#[implement(super::Service)]
+async fn referenced_roots(&self) -> HashSet<ShortStateHash> {
+	let events = self
+		.db
+		.shorteventid_shortstatehash
+		.raw_stream()
+		.ignore_err()
+		.map(|(_, shortstatehash)| u64_from_u8(shortstatehash));
+
+	let rooms = self
+		.db
+		.roomid_shortstatehash
+		.raw_stream()
+		.ignore_err()
+		.map(|(_, shortstatehash)| u64_from_u8(shortstatehash));
+
+	events.chain(rooms).collect().await
+}
+
+/// Returns the referenced states along with every parent layer those states
+/// are based on. A parent of 0 is no parent.
+fn mark_referenced<I>(
+	roots: I,
+	parents: &HashMap<ShortStateHash, ShortStateHash>,
+) -> HashSet<ShortStateHash>
+where
+	I: IntoIterator<Item = ShortStateHash>,
+{
+	let mut referenced = HashSet::new();
+	for root in roots {
+		let mut next = Some(root);
+		while let Some(shortstatehash) = next {
+			if !referenced.insert(shortstatehash) {
+				break;
+			}
+
+			next = parents
+				.get(&shortstatehash)
+				.copied()
+				.filter(|&parent| parent != 0);
+		}
+	}
+
+	referenced
+}
+
+fn unreferenced_states(
+	parents: &HashMap<ShortStateHash, ShortStateHash>,
+	referenced: &HashSet<ShortStateHash>,
+) -> HashSet<ShortStateHash> {
+	parents
+		.keys()
+		.copied()
+		.filter(|shortstatehash| !referenced.contains(shortstatehash))
+		.collect()
+}
+
+/// Collects the state hashes of the candidate states. A state hash is only
+/// ever mapped to a newly created state, so these do not change until the
+/// candidates are deleted.
+#[implement(super::Service)]
+async fn state_hashes(
+	&self,
+	candidates: &HashSet<ShortStateHash>,
+) -> Vec<(Vec<u8>, ShortStateHash)> {
+	self.db
+		.statehash_shortstatehash
+		.raw_stream()
+		.ignore_err()
+		.map(|(state_hash, shortstatehash)| (state_hash.to_vec(), u64_from_u8(shortstatehash)))
+		.ready_filter(|(_, shortstatehash)| candidates.contains(shortstatehash))
+		.collect()
+		.await
+}
+
+#[implement(super::Service)]
+fn delete_states(
+	&self,
+	unreferenced: &HashSet<ShortStateHash>,
+	state_hashes: &[(Vec<u8>, ShortStateHash)],
+) {
+	state_hashes
+		.iter()
+		.filter(|(_, shortstatehash)| unreferenced.contains(shortstatehash))
+		.for_each(|(state_hash, _)| {
+			self.db
+				.statehash_shortstatehash
+				.remove(state_hash);
+		});
+
+	for shortstatehash in unreferenced {
+		debug!(?shortstatehash, "Deleting unreferenced state");
+		self.db
+			.shortstatehash_statediff
+			.remove(&shortstatehash.to_be_bytes());
+	}
+}
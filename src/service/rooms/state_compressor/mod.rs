@@ -1,32 +1,46 @@
+mod gc;
+
 use std::{
-	collections::{BTreeSet, HashMap},
+	collections::{BTreeSet, HashMap, HashSet},
 	fmt::{Debug, Write},
 	mem::size_of,
 	sync::{Arc, Mutex},
+	time::Duration,
 };
 
 use async_trait::async_trait;
 use futures::{Stream, StreamExt};
 use lru_cache::LruCache;
 use ruma::{EventId, RoomId};
+use tokio::{
+	sync::{Mutex as TokioMutex, RwLock, RwLockReadGuard},
+	time::sleep,
+};
 use tuwunel_core::{
 	Result,
 	arrayvec::ArrayVec,
 	at, checked, err, expected, implement, utils,
-	utils::{bytes, math::usize_from_f64, stream::IterStream},
+	utils::{bytes, math::usize_from_f64, result::LogErr, stream::IterStream},
 };
 use tuwunel_database::Map;
 
+pub use self::gc::GcStats;
 use crate::rooms::short::{ShortEventId, ShortId, ShortStateHash, ShortStateKey};
 
 pub struct Service {
 	pub stateinfo_cache: Mutex<StateInfoLruCache>,
+	gc_lock: RwLock<()>,
+	gc_run: TokioMutex<()>,
+	gc_kept: Mutex<Option<HashSet<ShortStateHash>>>,
 	db: Data,
 	services: Arc<crate::services::OnceServices>,
 }
 
 struct Data {
 	shortstatehash_statediff: Arc<Map>,
+	shorteventid_shortstatehash: Arc<Map>,
+	roomid_shortstatehash: Arc<Map>,
+	statehash_shortstatehash: Arc<Map>,
 }
 
 #[derive(Clone)]
@@ -66,13 +80,38 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 			f64::from(config.stateinfo_cache_capacity) * config.cache_capacity_modifier;
 		Ok(Arc::new(Self {
 			stateinfo_cache: LruCache::new(usize_from_f64(cache_capacity)?).into(),
+			gc_lock: RwLock::new(()),
+			gc_run: TokioMutex::new(()),
+			gc_kept: Mutex::new(None),
 			db: Data {
 				shortstatehash_statediff: args.db["shortstatehash_statediff"].clone(),
+				shorteventid_shortstatehash: args.db["shorteventid_shortstatehash"].clone(),
+				roomid_shortstatehash: args.db["roomid_shortstatehash"].clone(),
+				statehash_shortstatehash: args.db["statehash_shortstatehash"].clone(),
 			},
 			services: args.services.clone(),
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		let interval = self.services.server.config.state_gc_interval;
+		if interval == 0 {
+			return Ok(());
+		}
+
+		let interval = Duration::from_secs(interval);
+		while self.services.server.running() {
+			tokio::select! {
+				() = self.services.server.until_shutdown() => break,
+				() = sleep(interval) => {
+					self.collect_garbage(false).await.log_err().ok();
+				},
+			}
+		}
+
+		Ok(())
+	}
+
 	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
 		let (cache_len, ents) = {
 			let cache = self.stateinfo_cache.lock().expect("locked");
@@ -371,6 +410,23 @@ pub fn save_state_from_diff(
 	Ok(())
 }
 
+/// Defers the deletion of unreferenced states while held. Must be held from
+/// the creation or reuse of a state (`save_state()`, `append_to_state()`)
+/// until it is referenced by the room (`force_state()`, `set_room_state()`),
+/// otherwise the state can be collected in between. Do not take it again while
+/// held.
+#[implement(Service)]
+pub async fn gc_guard(&self) -> RwLockReadGuard<'_, ()> { self.gc_lock.read().await }
+
+/// Keeps a state written or referenced while a garbage collection is marking
+/// from being deleted by that collection.
+#[implement(Service)]
+pub(crate) fn gc_keep(&self, shortstatehash: ShortStateHash) {
+	if let Some(kept) = self.gc_kept.lock().expect("locked").as_mut() {
+		kept.insert(shortstatehash);
+	}
+}
+
 /// Returns the new shortstatehash, and the state diff from the previous
 /// room state
 #[implement(Service)]
@@ -521,6 +577,11 @@ fn save_statediff(&self, shortstatehash: ShortStateHash, diff: &StateDiff) {
 	self.db
 		.shortstatehash_statediff
 		.insert(&shortstatehash.to_be_bytes(), &value);
+
+	self.gc_keep(shortstatehash);
+	if let Some(parent) = diff.parent {
+		self.gc_keep(parent);
+	}
 }
 
 #[inline]
@@ -193,6 +193,10 @@ pub(super) async fn get_participants(&self, root_id: &RawPduId) -> Result<Vec<Ow
 			.deserialized()
 	}
 
+	pub(super) fn delete_thread(&self, root_id: &RawPduId) {
+		self.db.threadid_userids.remove(root_id);
+	}
+
 	pub(super) async fn delete_all_rooms_threads(&self, room_id: &RoomId) -> Result {
 		let prefix = (room_id, Interfix);
 
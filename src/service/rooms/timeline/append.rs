@@ -175,7 +175,8 @@ pub async fn append_pdu<'a, Leafs>(
 
 	self.services
 		.user
-		.reset_notification_counts(pdu.sender(), pdu.room_id());
+		.reset_notification_counts(pdu.sender(), pdu.room_id())
+		.await;
 
 	let count = PduCount::Normal(*next_count1);
 	let pdu_id: RawPduId = PduId { shortroomid, count }.into();
@@ -221,6 +222,12 @@ pub async fn append_pdu<'a, Leafs>(
 		}
 	}
 
+	// Events in a thread are also counted for the thread (MSC3773)
+	let thread_id = match pdu.get_content::<ExtractRelatesTo>() {
+		| Ok(ExtractRelatesTo { relates_to: Relation::Thread(thread) }) => Some(thread.event_id),
+		| _ => None,
+	};
+
 	let serialized = pdu.to_format();
 	for user in &push_target {
 		let rules_for_user = self
@@ -242,12 +249,13 @@ pub async fn append_pdu<'a, Leafs>(
 			.get_power_levels(pdu.room_id())
 			.await?;
 
-		for action in self
+		let actions = self
 			.services
 			.pusher
 			.get_actions(user, &rules_for_user, &power_levels, &serialized, pdu.room_id())
-			.await
-		{
+			.await;
+
+		for action in actions {
 			match action {
 				| Action::Notify => notify = true,
 				| Action::SetTweak(Tweak::Highlight(true)) => {
@@ -263,6 +271,11 @@ pub async fn append_pdu<'a, Leafs>(
 		}
 
 		if notify {
+			self.services
+				.pusher
+				.record_notification(user, count.into_unsigned(), pdu, actions, highlight)
+				.await;
+
 			notifies.push(user.clone());
 		}
 
@@ -282,7 +295,7 @@ pub async fn append_pdu<'a, Leafs>(
 			.await;
 	}
 
-	self.increment_notification_counts(pdu.room_id(), notifies, highlights);
+	self.increment_notification_counts(pdu.room_id(), thread_id.as_deref(), notifies, highlights);
 
 	match *pdu.kind() {
 		| TimelineEventType::RoomRedaction => {
@@ -340,6 +353,11 @@ pub async fn append_pdu<'a, Leafs>(
 					UserId::parse(state_key).expect("This state_key was previously validated");
 
 				let content: RoomMemberEventContent = pdu.get_content()?;
+				self.services
+					.remote_profile
+					.update_from_member(target_user_id, &content)
+					.await;
+
 				let stripped_state = match content.membership {
 					| MembershipState::Invite | MembershipState::Knock => self
 						.services
@@ -396,9 +414,11 @@ pub async fn append_pdu<'a, Leafs>(
 			.get_pdu_count(&content.relates_to.event_id)
 			.await
 		{
-			self.services
-				.pdu_metadata
-				.add_relation(count, related_pducount);
+			self.services.pdu_metadata.add_relation(
+				count,
+				related_pducount,
+				content.relates_to.rel_type.as_deref(),
+			);
 		}
 	}
 
@@ -410,7 +430,7 @@ pub async fn append_pdu<'a, Leafs>(
 				if let Ok(related_pducount) = self.get_pdu_count(&in_reply_to.event_id).await {
 					self.services
 						.pdu_metadata
-						.add_relation(count, related_pducount);
+						.add_relation(count, related_pducount, None);
 				}
 			},
 			| Relation::Thread(thread) => {
@@ -419,6 +439,17 @@ pub async fn append_pdu<'a, Leafs>(
 					.add_to_thread(&thread.event_id, pdu)
 					.await?;
 			},
+			| Relation::Annotation(annotation) if *pdu.kind() == TimelineEventType::Reaction => {
+				self.services
+					.pdu_metadata
+					.add_annotation(
+						&annotation.event_id,
+						&annotation.key,
+						pdu.sender(),
+						pdu.event_id(),
+					)
+					.await;
+			},
 			| _ => {}, // TODO: Aggregate other types
 		}
 	}
@@ -512,6 +543,7 @@ fn append_pdu_json(
 fn increment_notification_counts(
 	&self,
 	room_id: &RoomId,
+	thread_id: Option<&EventId>,
 	notifies: Vec<OwnedUserId>,
 	highlights: Vec<OwnedUserId>,
 ) {
@@ -522,6 +554,12 @@ fn increment_notification_counts(
 		userroom_id.push(0xFF);
 		userroom_id.extend_from_slice(room_id.as_bytes());
 		increment(&self.db.userroomid_notificationcount, &userroom_id);
+
+		if let Some(thread_id) = thread_id {
+			userroom_id.push(0xFF);
+			userroom_id.extend_from_slice(thread_id.as_bytes());
+			increment(&self.db.userroomthreadid_notificationcount, &userroom_id);
+		}
 	}
 
 	for user in highlights {
@@ -529,6 +567,12 @@ fn increment_notification_counts(
 		userroom_id.push(0xFF);
 		userroom_id.extend_from_slice(room_id.as_bytes());
 		increment(&self.db.userroomid_highlightcount, &userroom_id);
+
+		if let Some(thread_id) = thread_id {
+			userroom_id.push(0xFF);
+			userroom_id.extend_from_slice(thread_id.as_bytes());
+			increment(&self.db.userroomthreadid_highlightcount, &userroom_id);
+		}
 	}
 }
 
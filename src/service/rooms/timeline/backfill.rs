@@ -177,6 +177,15 @@ pub async fn backfill_pdu(
 
 	let ((_, event_id, value), mutex_lock) = try_join(parsed, mutex_lock).await?;
 
+	// Already in the timeline; prepending it again would duplicate it.
+	if self
+		.non_outlier_pdu_exists(&event_id)
+		.await
+		.is_ok()
+	{
+		return Ok(());
+	}
+
 	self.services
 		.event_handler
 		.handle_incoming_pdu(origin, room_id, &event_id, value, false)
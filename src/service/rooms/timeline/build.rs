@@ -126,6 +126,7 @@ pub async fn build_and_append_pdu(
 	// We append to state before appending the pdu, so we don't have a moment in
 	// time with the pdu without it's state. This is okay because append_pdu can't
 	// fail.
+	let gc_guard = self.services.state_compressor.gc_guard().await;
 	let statehashid = self.services.state.append_to_state(&pdu).await?;
 
 	let pdu_id = self
@@ -145,6 +146,8 @@ pub async fn build_and_append_pdu(
 		.state
 		.set_room_state(pdu.room_id(), statehashid, state_lock);
 
+	drop(gc_guard);
+
 	let mut servers: HashSet<OwnedServerName> = self
 		.services
 		.state_cache
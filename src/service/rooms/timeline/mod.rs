@@ -2,6 +2,7 @@
 mod backfill;
 mod build;
 mod create;
+mod purge;
 mod redact;
 
 use std::{borrow::Borrow, fmt::Write, sync::Arc};
@@ -51,6 +52,8 @@ struct Data {
 	pduid_pdu: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
 	userroomid_notificationcount: Arc<Map>,
+	userroomthreadid_highlightcount: Arc<Map>,
+	userroomthreadid_notificationcount: Arc<Map>,
 	db: Arc<Database>,
 }
 
@@ -64,6 +67,7 @@ struct ExtractRelatesTo {
 #[derive(Clone, Debug, Deserialize)]
 struct ExtractEventId {
 	event_id: OwnedEventId,
+	rel_type: Option<String>,
 }
 #[derive(Clone, Debug, Deserialize)]
 struct ExtractRelatesToEventId {
@@ -91,6 +95,10 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 				pduid_pdu: args.db["pduid_pdu"].clone(),
 				userroomid_highlightcount: args.db["userroomid_highlightcount"].clone(),
 				userroomid_notificationcount: args.db["userroomid_notificationcount"].clone(),
+				userroomthreadid_highlightcount: args.db["userroomthreadid_highlightcount"]
+					.clone(),
+				userroomthreadid_notificationcount: args.db["userroomthreadid_notificationcount"]
+					.clone(),
 				db: args.db.clone(),
 			},
 			mutex_insert: RoomMutexMap::new(),
@@ -442,11 +450,25 @@ pub async fn get_pdu_from_shorteventid(&self, shorteventid: ShortEventId) -> Res
 
 /// Returns the pdu.
 ///
-/// Checks the `eventid_outlierpdu` Tree if not found in the timeline.
+/// Checks the `eventid_outlierpdu` Tree if not found in the timeline. Rejected
+/// outliers are not found.
 #[implement(Service)]
 pub async fn get_pdu(&self, event_id: &EventId) -> Result<PduEvent> {
 	let accepted = self.get_non_outlier_pdu(event_id);
-	let outlier = self.get_outlier_pdu(event_id);
+	let outlier = self
+		.get_outlier_pdu(event_id)
+		.and_then(async |pdu| {
+			if self
+				.services
+				.pdu_metadata
+				.is_event_rejected(event_id)
+				.await
+			{
+				return Err!(Request(NotFound("Event was rejected.")));
+			}
+
+			Ok(pdu)
+		});
 
 	pin_mut!(accepted, outlier);
 	select_ok([Left(accepted), Right(outlier)])
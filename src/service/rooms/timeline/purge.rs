@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+
+use futures::{StreamExt, pin_mut};
+use ruma::{
+	EventId, MilliSecondsSinceUnixEpoch, OwnedEventId, RoomId,
+	events::{TimelineEventType, room::encrypted::Relation},
+};
+use tuwunel_core::{
+	Err, Result, at, debug, implement,
+	matrix::{
+		event::Event,
+		pdu::{PduCount, PduEvent, PduId, RawPduId},
+	},
+	trace,
+	utils::{ReadyExt, stream::TryIgnore},
+};
+
+use super::{ExtractBody, ExtractRelatesTo, ExtractRelatesToEventId};
+use crate::rooms::short::ShortRoomId;
+
+/// Returns the count of an event, which must be in the room.
+#[implement(super::Service)]
+pub async fn get_pdu_count_in_room(
+	&self,
+	room_id: &RoomId,
+	event_id: &EventId,
+) -> Result<PduCount> {
+	let shortroomid = self
+		.services
+		.short
+		.get_shortroomid(room_id)
+		.await?;
+	let pdu_id: PduId = self.get_pdu_id(event_id).await?.into();
+
+	if pdu_id.shortroomid != shortroomid {
+		return Err!(Request(NotFound("Event {event_id} is not in {room_id}")));
+	}
+
+	Ok(pdu_id.count)
+}
+
+/// Returns the count of the first PDU in the room sent at or after `ts`, or
+/// the end of the timeline when every PDU was sent before `ts`.
+#[implement(super::Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+pub async fn first_count_since(
+	&self,
+	room_id: &RoomId,
+	ts: MilliSecondsSinceUnixEpoch,
+) -> PduCount {
+	let pdus = self.pdus(None, room_id, None).ignore_err();
+
+	pin_mut!(pdus);
+	pdus.ready_find(|(_, pdu)| pdu.origin_server_ts() >= ts)
+		.await
+		.map_or_else(PduCount::max, at!(0))
+}
+
+/// Removes the non-state PDUs of a room preceding the exclusive `until` count,
+/// along with their search terms, relations and thread entries. State events
+/// and forward extremities are retained so the current state of the room and
+/// its ability to accept new events are unaffected. Returns the number of PDUs
+/// removed.
+#[implement(super::Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+pub async fn purge_pdus_until(&self, room_id: &RoomId, until: PduCount) -> Result<usize> {
+	let shortroomid = self
+		.services
+		.short
+		.get_shortroomid(room_id)
+		.await?;
+
+	let extremities: HashSet<OwnedEventId> = self
+		.services
+		.state
+		.get_forward_extremities(room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let purged = self
+		.pdus(None, room_id, None)
+		.ignore_err()
+		.ready_take_while(|(count, _)| *count < until)
+		.ready_filter(|(_, pdu)| pdu.state_key().is_none())
+		.ready_filter(|(_, pdu)| !extremities.contains(pdu.event_id()))
+		.fold(0_usize, async |purged, (count, pdu)| {
+			self.purge_pdu(shortroomid, count, &pdu).await;
+			purged.saturating_add(1)
+		})
+		.await;
+
+	debug!(?room_id, ?until, "Purged {purged} PDUs");
+
+	Ok(purged)
+}
+
+#[implement(super::Service)]
+async fn purge_pdu(&self, shortroomid: ShortRoomId, count: PduCount, pdu: &PduEvent) {
+	let pdu_id: RawPduId = PduId { shortroomid, count }.into();
+
+	trace!("Purging PDU {:?} {}", pdu_id, pdu.event_id());
+	if *pdu.kind() == TimelineEventType::RoomMessage {
+		if let Ok(ExtractBody { body: Some(body) }) = pdu.get_content() {
+			self.services
+				.search
+				.deindex_pdu(shortroomid, &pdu_id, &body);
+		}
+	}
+
+	let related = match pdu.get_content::<ExtractRelatesToEventId>() {
+		| Ok(content) => self
+			.get_pdu_count(&content.relates_to.event_id)
+			.await
+			.ok(),
+		| Err(_) => None,
+	};
+
+	self.services
+		.pdu_metadata
+		.delete_relations(count, related)
+		.await;
+
+	if let Ok(ExtractRelatesTo {
+		relates_to: Relation::Annotation(annotation),
+	}) = pdu.get_content()
+	{
+		self.services
+			.pdu_metadata
+			.remove_annotation(
+				&annotation.event_id,
+				&annotation.key,
+				pdu.sender(),
+				pdu.event_id(),
+			)
+			.await;
+	}
+
+	self.services.threads.delete_thread(&pdu_id);
+
+	if let Ok(shorteventid) = self
+		.services
+		.short
+		.get_shorteventid(pdu.event_id())
+		.await
+	{
+		self.services
+			.state
+			.delete_event_shortstatehash(shorteventid);
+	}
+
+	self.db.pduid_pdu.remove(&pdu_id);
+	self.db.eventid_pduid.remove(pdu.event_id());
+}
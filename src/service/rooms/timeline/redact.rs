@@ -1,11 +1,11 @@
-use ruma::EventId;
+use ruma::{EventId, events::room::encrypted::Relation};
 use tuwunel_core::{
 	Result, err, implement,
 	matrix::event::Event,
 	utils::{self},
 };
 
-use super::ExtractBody;
+use super::{ExtractBody, ExtractRelatesTo};
 use crate::rooms::short::ShortRoomId;
 
 /// Replace a PDU with the redacted form.
@@ -39,6 +39,21 @@ pub async fn redact_pdu<Pdu: Event + Send + Sync>(
 		}
 	}
 
+	if let Ok(ExtractRelatesTo {
+		relates_to: Relation::Annotation(annotation),
+	}) = pdu.get_content()
+	{
+		self.services
+			.pdu_metadata
+			.remove_annotation(
+				&annotation.event_id,
+				&annotation.key,
+				pdu.sender(),
+				pdu.event_id(),
+			)
+			.await;
+	}
+
 	let room_version_id = self
 		.services
 		.state
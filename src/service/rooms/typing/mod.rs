@@ -8,7 +8,7 @@
 use tokio::sync::{RwLock, broadcast};
 use tuwunel_core::{
 	Result, Server, debug_info, trace,
-	utils::{self, IterStream},
+	utils::{self, IterStream, ReadyExt, stream::BroadbandExt},
 };
 
 use crate::sending::EduBuf;
@@ -228,9 +228,19 @@ async fn federation_send(&self, room_id: &RoomId, user_id: &UserId, typing: bool
 		let mut buf = EduBuf::new();
 		serde_json::to_writer(&mut buf, &edu).expect("Serialized Edu::Typing");
 
+		let servers = self
+			.services
+			.state_cache
+			.room_servers(room_id)
+			.ready_filter(|server| !self.services.globals.server_is_ours(server))
+			.broad_filter_map(async |server| {
+				let policy = self.services.federation.policy(server).await;
+				(!policy.no_edus).then_some(server)
+			});
+
 		self.services
 			.sending
-			.send_edu_room(room_id, buf)
+			.send_edu_servers(servers, buf)
 			.await?;
 
 		Ok(())
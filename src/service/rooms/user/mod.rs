@@ -1,11 +1,12 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
-use ruma::{RoomId, UserId};
+use futures::StreamExt;
+use ruma::{EventId, OwnedEventId, RoomId, UserId, events::receipt::ReceiptThread};
 use tuwunel_core::{
 	Result, implement, trace,
 	utils::stream::{ReadyExt, TryIgnore},
 };
-use tuwunel_database::{Deserialized, Interfix, Map};
+use tuwunel_database::{Deserialized, Ignore, Interfix, Map};
 
 pub struct Service {
 	db: Data,
@@ -16,8 +17,13 @@ struct Data {
 	userroomid_notificationcount: Arc<Map>,
 	userroomid_highlightcount: Arc<Map>,
 	roomuserid_lastnotificationread: Arc<Map>,
+	userroomthreadid_notificationcount: Arc<Map>,
+	userroomthreadid_highlightcount: Arc<Map>,
 }
 
+/// Notification and highlight counts of a thread, by thread root.
+pub type ThreadCounts = BTreeMap<OwnedEventId, (u64, u64)>;
+
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
@@ -26,6 +32,10 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 				userroomid_highlightcount: args.db["userroomid_highlightcount"].clone(),
 				roomuserid_lastnotificationread: args.db["roomuserid_lastnotificationread"]
 					.clone(),
+				userroomthreadid_notificationcount: args.db["userroomthreadid_notificationcount"]
+					.clone(),
+				userroomthreadid_highlightcount: args.db["userroomthreadid_highlightcount"]
+					.clone(),
 			},
 			services: args.services.clone(),
 		}))
@@ -36,16 +46,89 @@ fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 
 #[implement(Service)]
 #[tracing::instrument(level = "debug", skip(self))]
-pub fn reset_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
+pub async fn reset_notification_counts(&self, user_id: &UserId, room_id: &RoomId) {
+	self.set_notification_counts(user_id, room_id, 0, 0);
+
+	let prefix = (user_id, room_id, Interfix);
+	for map in [
+		&self.db.userroomthreadid_notificationcount,
+		&self.db.userroomthreadid_highlightcount,
+	] {
+		map.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| map.remove(key))
+			.await;
+	}
+}
+
+/// Resets the counts read by a threaded read receipt (MSC3771). A receipt
+/// in the main timeline leaves the counts of threads; a receipt in a thread
+/// clears only that thread's counts.
+#[implement(Service)]
+#[tracing::instrument(level = "debug", skip(self))]
+pub async fn reset_thread_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread: &ReceiptThread,
+) {
+	let (notification_count, highlight_count) = match thread {
+		| ReceiptThread::Main => {
+			let threads = self
+				.thread_notification_counts(user_id, room_id)
+				.await;
+
+			thread_totals(&threads)
+		},
+		| ReceiptThread::Thread(thread_id) => {
+			let (thread_notifications, thread_highlights) = self
+				.thread_notification_count(user_id, room_id, thread_id)
+				.await;
+
+			let key = (user_id, room_id, thread_id);
+			self.db
+				.userroomthreadid_notificationcount
+				.del(key);
+			self.db.userroomthreadid_highlightcount.del(key);
+
+			let notification_count = self
+				.notification_count(user_id, room_id)
+				.await
+				.saturating_sub(thread_notifications);
+
+			let highlight_count = self
+				.highlight_count(user_id, room_id)
+				.await
+				.saturating_sub(thread_highlights);
+
+			(notification_count, highlight_count)
+		},
+		| _ =>
+			return self
+				.reset_notification_counts(user_id, room_id)
+				.await,
+	};
+
+	self.set_notification_counts(user_id, room_id, notification_count, highlight_count);
+}
+
+#[implement(Service)]
+fn set_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	notification_count: u64,
+	highlight_count: u64,
+) {
 	let count = self.services.globals.next_count();
 
 	let userroom_id = (user_id, room_id);
 	self.db
 		.userroomid_highlightcount
-		.put(userroom_id, 0_u64);
+		.put(userroom_id, highlight_count);
 	self.db
 		.userroomid_notificationcount
-		.put(userroom_id, 0_u64);
+		.put(userroom_id, notification_count);
 
 	let roomuser_id = (room_id, user_id);
 	self.db
@@ -77,6 +160,79 @@ pub async fn highlight_count(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
 		.unwrap_or(0)
 }
 
+/// Returns the unread counts of each thread of a room with notifications
+/// for the user. The room counts include these.
+#[implement(Service)]
+#[tracing::instrument(level = "debug", skip(self))]
+pub async fn thread_notification_counts(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+) -> ThreadCounts {
+	type KeyVal<'a> = ((Ignore, Ignore, &'a EventId), u64);
+
+	let prefix = (user_id, room_id, Interfix);
+	let notifications: Vec<(OwnedEventId, u64)> = self
+		.db
+		.userroomthreadid_notificationcount
+		.stream_prefix(&prefix)
+		.ignore_err()
+		.map(|((_, _, thread_id), count): KeyVal<'_>| (thread_id.to_owned(), count))
+		.collect()
+		.await;
+
+	let mut threads = ThreadCounts::new();
+	for (thread_id, notification_count) in notifications {
+		let key = (user_id, room_id, &thread_id);
+		let highlight_count = self
+			.db
+			.userroomthreadid_highlightcount
+			.qry(&key)
+			.await
+			.deserialized()
+			.unwrap_or(0);
+
+		threads.insert(thread_id, (notification_count, highlight_count));
+	}
+
+	threads
+}
+
+/// Sums the notification and highlight counts of all threads.
+#[must_use]
+pub fn thread_totals(threads: &ThreadCounts) -> (u64, u64) {
+	threads
+		.values()
+		.fold((0, 0), |(n, h), (tn, th)| (n.saturating_add(*tn), h.saturating_add(*th)))
+}
+
+#[implement(Service)]
+async fn thread_notification_count(
+	&self,
+	user_id: &UserId,
+	room_id: &RoomId,
+	thread_id: &EventId,
+) -> (u64, u64) {
+	let key = (user_id, room_id, thread_id);
+	let notification_count = self
+		.db
+		.userroomthreadid_notificationcount
+		.qry(&key)
+		.await
+		.deserialized()
+		.unwrap_or(0);
+
+	let highlight_count = self
+		.db
+		.userroomthreadid_highlightcount
+		.qry(&key)
+		.await
+		.deserialized()
+		.unwrap_or(0);
+
+	(notification_count, highlight_count)
+}
+
 #[implement(Service)]
 #[tracing::instrument(level = "debug", skip(self), ret)]
 pub async fn last_notification_read(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
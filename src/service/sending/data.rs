@@ -6,9 +6,9 @@
 	Error, Result, at, utils,
 	utils::{ReadyExt, stream::TryIgnore},
 };
-use tuwunel_database::{Database, Deserialized, Map};
+use tuwunel_database::{Database, Deserialized, Json, Map};
 
-use super::{Destination, SendingEvent};
+use super::{Destination, DestinationHealth, SendingEvent};
 
 pub(super) type OutgoingItem = (Key, SendingEvent, Destination);
 pub(super) type SendingItem = (Key, SendingEvent);
@@ -19,6 +19,8 @@ pub struct Data {
 	servercurrentevent_data: Arc<Map>,
 	servernameevent_data: Arc<Map>,
 	servername_educount: Arc<Map>,
+	servername_health: Arc<Map>,
+	senderkey_pushhealth: Arc<Map>,
 	pub(super) db: Arc<Database>,
 	services: Arc<crate::services::OnceServices>,
 }
@@ -30,6 +32,8 @@ pub(super) fn new(args: &crate::Args<'_>) -> Self {
 			servercurrentevent_data: db["servercurrentevent_data"].clone(),
 			servernameevent_data: db["servernameevent_data"].clone(),
 			servername_educount: db["servername_educount"].clone(),
+			servername_health: db["servername_health"].clone(),
+			senderkey_pushhealth: db["senderkey_pushhealth"].clone(),
 			db: args.db.clone(),
 			services: args.services.clone(),
 		}
@@ -176,6 +180,57 @@ pub async fn get_latest_educount(&self, server_name: &ServerName) -> u64 {
 			.deserialized()
 			.unwrap_or(0)
 	}
+
+	pub(super) fn set_health(&self, server_name: &ServerName, health: &DestinationHealth) {
+		self.servername_health
+			.raw_put(server_name, Json(health));
+	}
+
+	pub(super) fn del_health(&self, server_name: &ServerName) {
+		self.servername_health.remove(server_name);
+	}
+
+	pub async fn get_health(&self, server_name: &ServerName) -> Result<DestinationHealth> {
+		self.servername_health
+			.get(server_name)
+			.await
+			.deserialized()
+	}
+
+	pub fn all_health(&self) -> impl Stream<Item = (&ServerName, DestinationHealth)> + Send + '_ {
+		self.servername_health.stream().ignore_err()
+	}
+
+	pub(super) fn set_push_health(
+		&self,
+		user_id: &UserId,
+		pushkey: &str,
+		health: &DestinationHealth,
+	) {
+		self.senderkey_pushhealth
+			.put((user_id, pushkey), Json(health));
+	}
+
+	pub(super) fn del_push_health(&self, user_id: &UserId, pushkey: &str) {
+		self.senderkey_pushhealth.del((user_id, pushkey));
+	}
+
+	pub async fn get_push_health(
+		&self,
+		user_id: &UserId,
+		pushkey: &str,
+	) -> Result<DestinationHealth> {
+		self.senderkey_pushhealth
+			.qry(&(user_id, pushkey))
+			.await
+			.deserialized()
+	}
+
+	pub fn all_push_health(
+		&self,
+	) -> impl Stream<Item = ((&UserId, &str), DestinationHealth)> + Send + '_ {
+		self.senderkey_pushhealth.stream().ignore_err()
+	}
 }
 
 fn parse_servercurrentevent(key: &[u8], value: &[u8]) -> Result<(Destination, SendingEvent)> {
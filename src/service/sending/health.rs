@@ -0,0 +1,263 @@
+use std::{
+	sync::atomic::{AtomicU64, Ordering},
+	time::Duration,
+};
+
+use futures::Stream;
+use ruma::{MilliSecondsSinceUnixEpoch, ServerName, UInt, UserId};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{Error, Result, debug_info, implement};
+
+use super::{Destination, Msg, SendingEvent};
+
+/// Delivery health of a federation destination or pusher, persisted so backoff
+/// survives a restart.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DestinationHealth {
+	/// Number of consecutive failed transactions.
+	pub failures: u32,
+
+	/// Time of the first successful transaction after the last failure.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub last_success: Option<MilliSecondsSinceUnixEpoch>,
+
+	/// Time of the last failed transaction.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub last_failure: Option<MilliSecondsSinceUnixEpoch>,
+
+	/// No transaction is attempted before this time.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub next_retry: Option<MilliSecondsSinceUnixEpoch>,
+
+	/// Class of the error which caused the last failure.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub last_error: Option<String>,
+}
+
+/// Counters of delivery outcomes since startup.
+#[derive(Debug, Default)]
+pub struct HealthMetrics {
+	/// Transactions which were delivered.
+	pub succeeded: AtomicU64,
+
+	/// Transactions which failed and put their destination into backoff.
+	pub failed: AtomicU64,
+
+	/// Destinations which recovered after one or more failures.
+	pub recovered: AtomicU64,
+
+	/// Backoffs lifted early by an admin or by inbound traffic.
+	pub reset: AtomicU64,
+}
+
+impl DestinationHealth {
+	/// Whether transactions to the destination are currently held back.
+	#[must_use]
+	pub fn is_backing_off(&self) -> bool {
+		self.failures > 0
+			&& self
+				.next_retry
+				.is_some_and(|next_retry| next_retry > MilliSecondsSinceUnixEpoch::now())
+	}
+
+	/// Time elapsed since the last failure.
+	#[must_use]
+	pub fn since_failure(&self) -> Duration {
+		self.last_failure
+			.map(|ts| {
+				MilliSecondsSinceUnixEpoch::now()
+					.get()
+					.saturating_sub(ts.get())
+			})
+			.map(u64::from)
+			.map(Duration::from_millis)
+			.unwrap_or_default()
+	}
+}
+
+/// Returns the health of a federation destination. Servers we have never
+/// failed to reach have no entry.
+#[implement(super::Service)]
+pub async fn destination_health(&self, server: &ServerName) -> Result<DestinationHealth> {
+	self.db.get_health(server).await
+}
+
+/// Iterates the health of every federation destination with an entry.
+#[implement(super::Service)]
+pub fn destinations_health(
+	&self,
+) -> impl Stream<Item = (&ServerName, DestinationHealth)> + Send + '_ {
+	self.db.all_health()
+}
+
+/// Clears the backoff for a destination and flushes anything queued for it.
+#[implement(super::Service)]
+pub fn reset_backoff(&self, server: &ServerName) -> Result {
+	self.metrics.reset.fetch_add(1, Ordering::Relaxed);
+	self.db.del_health(server);
+	self.dispatch(Msg {
+		dest: Destination::Federation(server.to_owned()),
+		event: SendingEvent::Flush,
+		queue_id: Vec::new(),
+	})
+}
+
+/// Called on inbound traffic from a server; a server which is talking to us
+/// is reachable again, so any backoff against it is lifted.
+#[implement(super::Service)]
+pub async fn mark_reachable(&self, server: &ServerName) -> Result {
+	let Ok(health) = self.destination_health(server).await else {
+		return Ok(());
+	};
+
+	if health.failures == 0 {
+		return Ok(());
+	}
+
+	debug_info!(?server, failures = health.failures, "Lifting backoff on inbound traffic");
+	self.reset_backoff(server)
+}
+
+/// Returns the delivery health of a pusher. Pushers we have never sent to have
+/// no entry.
+#[implement(super::Service)]
+pub async fn pusher_health(&self, user_id: &UserId, pushkey: &str) -> Result<DestinationHealth> {
+	self.db.get_push_health(user_id, pushkey).await
+}
+
+/// Iterates the health of every pusher with an entry.
+#[implement(super::Service)]
+pub fn pushers_health(
+	&self,
+) -> impl Stream<Item = ((&UserId, &str), DestinationHealth)> + Send + '_ {
+	self.db.all_push_health()
+}
+
+#[implement(super::Service)]
+pub(super) async fn record_success(&self, server: &ServerName) {
+	let prev = self.destination_health(server).await;
+	let Some(health) = self.succeeded(prev) else {
+		return;
+	};
+
+	self.db.set_health(server, &health);
+}
+
+#[implement(super::Service)]
+pub(super) async fn record_failure(&self, server: &ServerName, failures: u32, e: &Error) {
+	let min = self.server.config.sender_timeout;
+	let max = self.server.config.sender_retry_backoff_limit;
+	let backoff = backoff(min, max, failures);
+
+	let prev = self.destination_health(server).await;
+	let health = prev
+		.unwrap_or_default()
+		.failed(failures, backoff, e);
+
+	self.metrics
+		.failed
+		.fetch_add(1, Ordering::Relaxed);
+	self.db.set_health(server, &health);
+}
+
+#[implement(super::Service)]
+pub(super) async fn record_push_success(&self, user_id: &UserId, pushkey: &str) {
+	let prev = self.pusher_health(user_id, pushkey).await;
+	let Some(health) = self.succeeded(prev) else {
+		return;
+	};
+
+	self.db.set_push_health(user_id, pushkey, &health);
+}
+
+#[implement(super::Service)]
+pub(super) async fn record_push_failure(
+	&self,
+	user_id: &UserId,
+	pushkey: &str,
+	failures: u32,
+	e: &Error,
+) {
+	let min = self.server.config.pusher_retry_backoff_min;
+	let max = self.server.config.pusher_retry_backoff_limit;
+	let backoff = backoff(min, max, failures);
+
+	let prev = self.pusher_health(user_id, pushkey).await;
+	let health = prev
+		.unwrap_or_default()
+		.failed(failures, backoff, e);
+
+	self.metrics
+		.failed
+		.fetch_add(1, Ordering::Relaxed);
+	self.db.set_push_health(user_id, pushkey, &health);
+}
+
+/// Counts a delivered transaction and returns the health to persist, if it
+/// changed. A healthy destination is only written once, so deliveries to it
+/// don't each cost a database write.
+#[implement(super::Service)]
+fn succeeded(&self, prev: Result<DestinationHealth>) -> Option<DestinationHealth> {
+	self.metrics
+		.succeeded
+		.fetch_add(1, Ordering::Relaxed);
+
+	match prev {
+		| Ok(prev) if prev.failures == 0 => None,
+		| Ok(prev) => {
+			self.metrics
+				.recovered
+				.fetch_add(1, Ordering::Relaxed);
+
+			Some(prev.succeeded())
+		},
+		| Err(_) => Some(DestinationHealth::default().succeeded()),
+	}
+}
+
+impl DestinationHealth {
+	fn succeeded(self) -> Self {
+		Self {
+			failures: 0,
+			last_success: Some(MilliSecondsSinceUnixEpoch::now()),
+			next_retry: None,
+			..self
+		}
+	}
+
+	fn failed(self, failures: u32, backoff: Duration, e: &Error) -> Self {
+		let now = MilliSecondsSinceUnixEpoch::now();
+		let backoff = UInt::try_from(backoff.as_millis()).unwrap_or(UInt::MAX);
+		let next_retry = MilliSecondsSinceUnixEpoch(now.get().saturating_add(backoff));
+
+		Self {
+			failures,
+			last_failure: Some(now),
+			next_retry: Some(next_retry),
+			last_error: Some(error_class(e).to_owned()),
+			..self
+		}
+	}
+}
+
+fn backoff(min: u64, max: u64, failures: u32) -> Duration {
+	let min = Duration::from_secs(min);
+	let max = Duration::from_secs(max);
+
+	min.saturating_mul(failures)
+		.saturating_mul(failures)
+		.min(max)
+}
+
+fn error_class(e: &Error) -> &'static str {
+	match e {
+		| Error::Reqwest(e) if e.is_timeout() => "timeout",
+		| Error::Reqwest(e) if e.is_connect() => "connect",
+		| Error::Reqwest(e) if e.is_decode() || e.is_body() => "response",
+		| Error::Reqwest(_) => "request",
+		| Error::Federation(..) if e.status_code().is_server_error() => "server",
+		| Error::Federation(..) => "rejected",
+		| Error::BadServerResponse(..) => "response",
+		| _ => "other",
+	}
+}
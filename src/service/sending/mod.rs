@@ -1,9 +1,11 @@
 mod appservice;
 mod data;
 mod dest;
+mod health;
 mod sender;
 
 use std::{
+	collections::BTreeSet,
 	fmt::Debug,
 	hash::{DefaultHasher, Hash, Hasher},
 	iter::once,
@@ -13,26 +15,30 @@
 use async_trait::async_trait;
 use futures::{FutureExt, Stream, StreamExt};
 use ruma::{
-	RoomId, ServerName, UserId,
+	OwnedServerName, RoomId, ServerName, UserId,
 	api::{OutgoingRequest, appservice::Registration},
 };
 use tokio::{task, task::JoinSet};
 use tuwunel_core::{
 	Result, Server, debug, debug_warn, err, error,
 	smallvec::SmallVec,
-	utils::{ReadyExt, TryReadyExt, available_parallelism, math::usize_from_u64_truncated},
+	utils::{
+		IterStream, ReadyExt, TryReadyExt, available_parallelism, math::usize_from_u64_truncated,
+	},
 	warn,
 };
 
 use self::data::Data;
 pub use self::{
 	dest::Destination,
+	health::{DestinationHealth, HealthMetrics},
 	sender::{EDU_LIMIT, PDU_LIMIT},
 };
 use crate::rooms::timeline::RawPduId;
 
 pub struct Service {
 	pub db: Data,
+	pub metrics: HealthMetrics,
 	server: Arc<Server>,
 	services: Arc<crate::services::OnceServices>,
 	channels: Vec<(loole::Sender<Msg>, loole::Receiver<Msg>)>,
@@ -65,6 +71,7 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		let num_senders = num_senders(args);
 		Ok(Arc::new(Self {
 			db: Data::new(args),
+			metrics: HealthMetrics::default(),
 			server: args.server.clone(),
 			services: args.services.clone(),
 			channels: (0..num_senders)
@@ -154,13 +161,10 @@ pub fn send_pdu_appservice(&self, appservice_id: String, pdu_id: RawPduId) -> Re
 
 	#[tracing::instrument(skip(self, room_id, pdu_id), level = "debug")]
 	pub async fn send_pdu_room(&self, room_id: &RoomId, pdu_id: &RawPduId) -> Result {
-		let servers = self
-			.services
-			.state_cache
-			.room_servers(room_id)
-			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name));
+		let servers = self.room_servers(room_id).await;
 
-		self.send_pdu_servers(servers, pdu_id).await
+		self.send_pdu_servers(servers.iter().map(AsRef::as_ref).stream(), pdu_id)
+			.await
 	}
 
 	#[tracing::instrument(skip(self, servers, pdu_id), level = "debug")]
@@ -205,13 +209,10 @@ pub fn send_edu_server(&self, server: &ServerName, serialized: EduBuf) -> Result
 
 	#[tracing::instrument(skip(self, room_id, serialized), level = "debug")]
 	pub async fn send_edu_room(&self, room_id: &RoomId, serialized: EduBuf) -> Result {
-		let servers = self
-			.services
-			.state_cache
-			.room_servers(room_id)
-			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name));
+		let servers = self.room_servers(room_id).await;
 
-		self.send_edu_servers(servers, serialized).await
+		self.send_edu_servers(servers.iter().map(AsRef::as_ref).stream(), serialized)
+			.await
 	}
 
 	#[tracing::instrument(skip(self, servers, serialized), level = "debug")]
@@ -243,13 +244,30 @@ pub async fn send_edu_servers<'a, S>(&self, servers: S, serialized: EduBuf) -> R
 
 	#[tracing::instrument(skip(self, room_id), level = "debug")]
 	pub async fn flush_room(&self, room_id: &RoomId) -> Result {
-		let servers = self
+		let servers = self.room_servers(room_id).await;
+
+		self.flush_servers(servers.iter().map(AsRef::as_ref).stream())
+			.await
+	}
+
+	/// Remote servers in the room. Servers in a room joined with partial state
+	/// are mostly not yet known from its state, so the servers it was joined
+	/// through are included.
+	async fn room_servers(&self, room_id: &RoomId) -> BTreeSet<OwnedServerName> {
+		let partial_servers = self
 			.services
+			.partial_state
+			.partial_servers(room_id)
+			.await;
+
+		self.services
 			.state_cache
 			.room_servers(room_id)
-			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name));
-
-		self.flush_servers(servers).await
+			.map(ToOwned::to_owned)
+			.chain(partial_servers.into_iter().stream())
+			.ready_filter(|server_name| !self.services.globals.server_is_ours(server_name))
+			.collect()
+			.await
 	}
 
 	#[tracing::instrument(skip(self, servers), level = "debug")]
@@ -339,6 +357,8 @@ pub async fn cleanup_events(
 					))
 					.await;
 
+				self.db.del_push_health(user_id, push_key);
+
 				Ok(())
 			},
 			| (Some(appservice_id), None, None) => {
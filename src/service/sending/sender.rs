@@ -20,6 +20,7 @@
 	UInt,
 	api::{
 		appservice::event::push_events::v1::EphemeralData,
+		client::push::PusherKind,
 		federation::transactions::{
 			edu::{
 				DeviceListUpdateContent, Edu, PresenceContent, PresenceUpdate, ReceiptContent,
@@ -51,8 +52,10 @@
 };
 
 use super::{
-	Destination, EduBuf, EduVec, Msg, SendingEvent, Service, appservice, data::QueueItem,
+	Destination, DestinationHealth, EduBuf, EduVec, Msg, SendingEvent, Service, appservice,
+	data::QueueItem,
 };
+use crate::federation::{Direction, TxnLogEntry};
 
 #[derive(Debug)]
 enum TransactionStatus {
@@ -137,16 +140,22 @@ async fn handle_response<'a>(
 		statuses: &mut CurTransactionStatus,
 	) {
 		match response {
-			| Err((dest, e)) => Self::handle_response_err(dest, statuses, &e),
+			| Err((dest, e)) => self.handle_response_err(dest, statuses, &e).await,
 			| Ok(dest) =>
 				self.handle_response_ok(&dest, futures, statuses)
 					.await,
 		}
 	}
 
-	fn handle_response_err(dest: Destination, statuses: &mut CurTransactionStatus, e: &Error) {
+	async fn handle_response_err(
+		&self,
+		dest: Destination,
+		statuses: &mut CurTransactionStatus,
+		e: &Error,
+	) {
 		debug!(dest = ?dest, "{e:?}");
-		statuses.entry(dest).and_modify(|e| {
+		let mut failures = None;
+		statuses.entry(dest.clone()).and_modify(|e| {
 			*e = match e {
 				| TransactionStatus::Running => TransactionStatus::Failed(1, Instant::now()),
 
@@ -156,8 +165,23 @@ fn handle_response_err(dest: Destination, statuses: &mut CurTransactionStatus, e
 				| TransactionStatus::Failed(..) => {
 					panic!("Request that was not even running failed?!")
 				},
+			};
+
+			if let TransactionStatus::Failed(n, _) = e {
+				failures = Some(*n);
 			}
 		});
+
+		match (&dest, failures) {
+			| (Destination::Federation(server), Some(failures)) => {
+				self.record_failure(server, failures, e).await;
+			},
+			| (Destination::Push(user_id, pushkey), Some(failures)) => {
+				self.record_push_failure(user_id, pushkey, failures, e)
+					.await;
+			},
+			| _ => {},
+		}
 	}
 
 	#[allow(clippy::needless_pass_by_ref_mut)]
@@ -167,6 +191,10 @@ async fn handle_response_ok<'a>(
 		futures: &mut SendingFutures<'a>,
 		statuses: &mut CurTransactionStatus,
 	) {
+		if let Destination::Federation(server) = dest {
+			self.record_success(server).await;
+		}
+
 		let _cork = self.db.db.cork();
 		self.db.delete_all_active_requests_for(dest).await;
 
@@ -275,6 +303,20 @@ async fn startup_netburst<'a>(
 		}
 
 		for (dest, events) in txns {
+			// Destinations still backing off from before the restart are retried
+			// when their backoff expires instead of in the burst.
+			let health = match &dest {
+				| Destination::Federation(server) => self.destination_health(server).await.ok(),
+				| Destination::Push(user_id, pushkey) =>
+					self.pusher_health(user_id, pushkey).await.ok(),
+				| Destination::Appservice(_) => None,
+			};
+
+			if let Some(health) = health.filter(DestinationHealth::is_backing_off) {
+				statuses.insert(dest.clone(), restored_status(&health));
+				continue;
+			}
+
 			if self.server.config.startup_netburst && !events.is_empty() {
 				statuses.insert(dest.clone(), TransactionStatus::Running);
 				futures.push(self.send_events(dest.clone(), events));
@@ -297,6 +339,10 @@ async fn select_events(
 		new_events: Vec<QueueItem>, // Events we want to send: event and full key
 		statuses: &mut CurTransactionStatus,
 	) -> Result<Option<Vec<SendingEvent>>> {
+		if let Destination::Federation(server) = dest {
+			self.sync_status(server, dest, statuses).await;
+		}
+
 		let (allow, retry) = self.select_events_current(dest, statuses)?;
 
 		// Nothing can be done for this remote, bail out.
@@ -341,6 +387,34 @@ async fn select_events(
 		Ok(Some(events))
 	}
 
+	/// Reconciles the in-memory status of a destination with its persisted
+	/// health. Backoff recorded before a restart is restored, and backoff which
+	/// was reset (by an admin or inbound traffic) is lifted.
+	async fn sync_status(
+		&self,
+		server: &ServerName,
+		dest: &Destination,
+		statuses: &mut CurTransactionStatus,
+	) {
+		let health = self.destination_health(server).await.ok();
+		let failures = health
+			.as_ref()
+			.map_or(0, |health| health.failures);
+
+		match (statuses.get_mut(dest), health) {
+			| (None, Some(health)) if failures > 0 => {
+				statuses.insert(dest.clone(), restored_status(&health));
+			},
+			| (Some(TransactionStatus::Failed(tries, time)), _) if failures == 0 => {
+				// Zero tries expires the backoff so the pending transaction is
+				// retried immediately.
+				*tries = 0;
+				*time = Instant::now();
+			},
+			| _ => {},
+		}
+	}
+
 	fn select_events_current(
 		&self,
 		dest: &Destination,
@@ -352,8 +426,16 @@ fn select_events_current(
 			.and_modify(|e| match e {
 				TransactionStatus::Failed(tries, time) => {
 					// Fail if a request has failed recently (exponential backoff)
-					let min = self.server.config.sender_timeout;
-					let max = self.server.config.sender_retry_backoff_limit;
+					let (min, max) = match dest {
+						| Destination::Push(..) => (
+							self.server.config.pusher_retry_backoff_min,
+							self.server.config.pusher_retry_backoff_limit,
+						),
+						| _ => (
+							self.server.config.sender_timeout,
+							self.server.config.sender_retry_backoff_limit,
+						),
+					};
 					if continue_exponential_backoff_secs(min, max, time.elapsed(), *tries)
 						&& !matches!(dest, Destination::Appservice(_))
 					{
@@ -386,6 +468,7 @@ async fn select_edus(&self, server_name: &ServerName) -> Result<(EduVec, u64)> {
 
 		let events_len = AtomicUsize::default();
 		let max_edu_count = AtomicU64::new(since);
+		let policy = self.services.federation.policy(server_name).await;
 
 		let device_changes =
 			self.select_edus_device_changes(server_name, batch, &max_edu_count, &events_len);
@@ -397,10 +480,8 @@ async fn select_edus(&self, server_name: &ServerName) -> Result<(EduVec, u64)> {
 			.then(|| self.select_edus_receipts(server_name, batch, &max_edu_count))
 			.into();
 
-		let presence: OptionFuture<_> = self
-			.server
-			.config
-			.allow_outgoing_presence
+		let presence: OptionFuture<_> = (self.server.config.allow_outgoing_presence
+			&& !policy.no_edus)
 			.then(|| self.select_edus_presence(server_name, batch, &max_edu_count))
 			.into();
 
@@ -790,20 +871,41 @@ async fn send_events_dest_push(
 		&self,
 		user_id: OwnedUserId,
 		pushkey: String,
-		events: Vec<SendingEvent>,
+		mut events: Vec<SendingEvent>,
 	) -> SendingResult {
+		let dest = Destination::Push(user_id.clone(), pushkey.clone());
 		let Ok(pusher) = self
 			.services
 			.pusher
 			.get_pusher(&user_id, &pushkey)
 			.await
 		else {
-			return Err((
-				Destination::Push(user_id.clone(), pushkey.clone()),
-				err!(Database(error!(?user_id, ?pushkey, "Missing pusher"))),
-			));
+			// The pusher was removed; nothing queued for it can be delivered.
+			debug!(?user_id, ?pushkey, "Dropping events for missing pusher");
+			return Ok(dest);
 		};
 
+		// Email pushers are served by the digest worker, not per event.
+		if !matches!(pusher.kind, PusherKind::Http(_)) {
+			return Ok(dest);
+		}
+
+		// Wait for more events to coalesce them into this batch.
+		let window = Duration::from_millis(self.server.config.pusher_coalesce_window_ms);
+		if !window.is_zero() {
+			tokio::time::sleep(window).await;
+
+			let queued = self
+				.db
+				.queued_requests(&dest)
+				.take(DEQUEUE_LIMIT)
+				.collect::<Vec<_>>()
+				.await;
+
+			self.db.mark_as_active(queued.iter());
+			events.extend(queued.into_iter().map(|(_, event)| event));
+		}
+
 		let mut pdus = Vec::with_capacity(
 			events
 				.iter()
@@ -819,7 +921,7 @@ async fn send_events_dest_push(
 						.get_pdu_from_id(pdu_id)
 						.await
 					{
-						pdus.push(pdu);
+						pdus.push((*pdu_id, pdu));
 					}
 				},
 				| SendingEvent::Edu(_) | SendingEvent::Flush => {
@@ -829,75 +931,131 @@ async fn send_events_dest_push(
 			}
 		}
 
-		for pdu in pdus {
-			// Redacted events are not notification targets (we don't send push for them)
-			if pdu.contains_unsigned_property("redacted_because", serde_json::Value::is_string) {
-				continue;
-			}
+		// optional suppression: heuristic combining presence age and recent sync
+		// activity.
+		if self.services.config.suppress_push_when_active
+			&& let Ok(presence) = self
+				.services
+				.presence
+				.get_presence(&user_id)
+				.await
+		{
+			let is_online = presence.content.presence == PresenceState::Online;
 
-			// optional suppression: heuristic combining presence age and recent sync
-			// activity.
-			if self.services.config.suppress_push_when_active
-				&& let Ok(presence) = self
-					.services
-					.presence
-					.get_presence(&user_id)
-					.await
-			{
-				let is_online = presence.content.presence == PresenceState::Online;
+			let presence_age_ms = presence
+				.content
+				.last_active_ago
+				.map(u64::from)
+				.unwrap_or(u64::MAX);
 
-				let presence_age_ms = presence
-					.content
-					.last_active_ago
-					.map(u64::from)
-					.unwrap_or(u64::MAX);
+			let sync_gap_ms = self
+				.services
+				.presence
+				.last_sync_gap_ms(&user_id)
+				.await;
 
-				let sync_gap_ms = self
-					.services
-					.presence
-					.last_sync_gap_ms(&user_id)
-					.await;
+			let considered_active = is_online
+				&& presence_age_ms < 65_000
+				&& sync_gap_ms.is_some_and(|gap| gap < 32_000);
 
-				let considered_active = is_online
-					&& presence_age_ms < 65_000
-					&& sync_gap_ms.is_some_and(|gap| gap < 32_000);
+			if considered_active {
+				trace!(
+					?user_id,
+					presence_age_ms, sync_gap_ms, "suppressing push: active heuristic"
+				);
+				return Ok(dest);
+			}
+		}
 
-				if considered_active {
-					trace!(
-						?user_id,
-						presence_age_ms, sync_gap_ms, "suppressing push: active heuristic"
-					);
-					continue;
-				}
+		let rules_for_user = self
+			.services
+			.account_data
+			.get_global(&user_id, GlobalAccountDataEventType::PushRules)
+			.await
+			.map_or_else(
+				|_| push::Ruleset::server_default(&user_id),
+				|ev: PushRulesEvent| ev.content.global,
+			);
+
+		// Coalesce to one notification per room: the latest event, unless an
+		// earlier one highlights and it doesn't.
+		let highlights = |tweaks: &[push::Tweak]| {
+			tweaks
+				.iter()
+				.any(|t| matches!(t, push::Tweak::Highlight(true)))
+		};
+
+		let mut notices = BTreeMap::<OwnedRoomId, (_, _, Vec<push::Tweak>)>::new();
+		for (pdu_id, pdu) in pdus {
+			// Redacted events are not notification targets (we don't send push for them)
+			if pdu.contains_unsigned_property("redacted_because", serde_json::Value::is_string) {
+				continue;
 			}
 
-			let rules_for_user = self
+			let Ok(Some(tweaks)) = self
 				.services
-				.account_data
-				.get_global(&user_id, GlobalAccountDataEventType::PushRules)
+				.pusher
+				.notify_tweaks(&user_id, &rules_for_user, &pdu)
 				.await
-				.map_or_else(
-					|_| push::Ruleset::server_default(&user_id),
-					|ev: PushRulesEvent| ev.content.global,
-				);
+			else {
+				continue;
+			};
+
+			match notices.get(pdu.room_id()) {
+				| Some((_, _, prev)) if highlights(prev) && !highlights(&tweaks) => {},
+				| _ => {
+					notices.insert(pdu.room_id().to_owned(), (pdu_id, pdu, tweaks));
+				},
+			}
+		}
 
+		// Every room is attempted; on failure only the rooms which failed are
+		// retried, so those already notified are not notified again.
+		let mut failed = Vec::new();
+		let mut error = None;
+		for (room_id, (pdu_id, pdu, tweaks)) in notices {
 			let unread: UInt = self
 				.services
 				.user
-				.notification_count(&user_id, pdu.room_id())
+				.notification_count(&user_id, &room_id)
 				.await
 				.try_into()
 				.expect("notification count can't go that high");
 
-			let _response = self
+			match self
 				.services
 				.pusher
-				.send_push_notice(&user_id, unread, &pusher, rules_for_user, &pdu)
+				.send_push_notice(&user_id, unread, &pusher, tweaks, &pdu)
 				.await
-				.map_err(|e| (Destination::Push(user_id.clone(), pushkey.clone()), e));
+			{
+				| Ok(true) => {},
+				| Ok(false) => return Ok(dest),
+				| Err(e) => {
+					debug!(?user_id, %room_id, "Failed to send push notice: {e}");
+					failed.push(pdu_id);
+					error = Some(e);
+				},
+			}
 		}
 
-		Ok(Destination::Push(user_id, pushkey))
+		if let Some(e) = error {
+			self.db
+				.active_requests_for(&dest)
+				.ready_filter(|(_, event)| match event {
+					| SendingEvent::Pdu(pdu_id) => !failed
+						.iter()
+						.any(|failed| failed.is_room_eq(*pdu_id)),
+					| _ => true,
+				})
+				.ready_for_each(|(key, _)| self.db.delete_active_request(&key))
+				.await;
+
+			return Err((dest, e));
+		}
+
+		self.record_push_success(&user_id, &pushkey).await;
+
+		Ok(dest)
 	}
 
 	async fn send_events_dest_federation(
@@ -955,12 +1113,28 @@ async fn send_events_dest_federation(
 			edus,
 		};
 
+		let (pdus_len, edus_len) = (request.pdus.len(), request.edus.len());
+		let started = Instant::now();
 		let result = self
 			.services
 			.federation
 			.execute_on(&self.services.client.sender, &server, request)
 			.await;
 
+		if self.services.federation.txn_log_enabled() {
+			let mut entry = TxnLogEntry::new(Direction::Outbound, &server, txn_id.into())
+				.latency(started.elapsed());
+
+			entry.pdus = pdus_len;
+			entry.edus = edus_len;
+			match &result {
+				| Ok(response) => entry.pdu_results.clone_from(&response.pdus),
+				| Err(e) => entry.error = Some(e.to_string()),
+			}
+
+			self.services.federation.log_txn(entry);
+		}
+
 		for (event_id, result) in result.iter().flat_map(|resp| resp.pdus.iter()) {
 			if let Err(e) = result {
 				warn!(
@@ -976,3 +1150,14 @@ async fn send_events_dest_federation(
 		}
 	}
 }
+
+/// Failed status resuming the backoff of a destination from its persisted
+/// health.
+fn restored_status(health: &DestinationHealth) -> TransactionStatus {
+	let now = Instant::now();
+	let time = now
+		.checked_sub(health.since_failure())
+		.unwrap_or(now);
+
+	TransactionStatus::Failed(health.failures, time)
+}
@@ -1,10 +1,14 @@
 use std::sync::Arc;
 
-use ruma::{api::federation::discovery::VerifyKey, serde::Base64, signatures::Ed25519KeyPair};
-use tuwunel_core::{Result, debug, debug_info, err, error, utils, utils::string_from_bytes};
-use tuwunel_database::Database;
+use ruma::{
+	api::federation::discovery::VerifyKey,
+	serde::{Base64, base64::Standard},
+	signatures::Ed25519KeyPair,
+};
+use tuwunel_core::{Err, Result, debug, debug_info, err, error, utils, utils::string_from_bytes};
+use tuwunel_database::{Database, Map};
 
-use super::VerifyKeys;
+use super::{OldVerifyKeys, VerifyKeys};
 
 pub(super) fn init(db: &Arc<Database>) -> Result<(Box<Ed25519KeyPair>, VerifyKeys)> {
 	let keypair = load(db).inspect_err(|_e| {
@@ -22,6 +26,89 @@ pub(super) fn init(db: &Arc<Database>) -> Result<(Box<Ed25519KeyPair>, VerifyKey
 	Ok((keypair, verify_keys))
 }
 
+/// Loads the previously active keys still published in `old_verify_keys`.
+pub(super) fn init_old(db: &Arc<Database>) -> Result<OldVerifyKeys> {
+	db["global"]
+		.get_blocking(b"old_verify_keys")
+		.map_or_else(
+			|e| {
+				assert!(e.is_not_found(), "unexpected error fetching old_verify_keys");
+				Ok(OldVerifyKeys::new())
+			},
+			|val| serde_json::from_slice(&val).map_err(Into::into),
+		)
+}
+
+/// Replaces the active keypair. The key is validated before it is stored.
+pub(super) fn store(
+	global: &Arc<Map>,
+	version: String,
+	der: Vec<u8>,
+) -> Result<Box<Ed25519KeyPair>> {
+	let keypair = Ed25519KeyPair::from_der(&der, version.clone())
+		.map_err(|e| err!("Failed to load ed25519 keypair from der: {e:?}"))?;
+
+	let value: (String, Vec<u8>) = (version, der);
+	global.raw_put(b"keypair", &value);
+
+	Ok(Box::new(keypair))
+}
+
+pub(super) fn store_old(global: &Arc<Map>, old_verify_keys: &OldVerifyKeys) -> Result {
+	global.insert(b"old_verify_keys", serde_json::to_vec(old_verify_keys)?);
+
+	Ok(())
+}
+
+/// Generates a new keypair returning its version and PKCS#8 document.
+pub(super) fn generate() -> Result<(String, Vec<u8>)> {
+	let keypair = Ed25519KeyPair::generate()
+		.map_err(|e| err!("Failed to generate new ed25519 keypair: {e:?}"))?;
+
+	let id = utils::rand::string(8);
+	debug_info!("Generated new Ed25519 keypair: {id:?}");
+
+	Ok((id, keypair))
+}
+
+/// Parses a Synapse-format signing key, i.e. a line of the form
+/// `ed25519 <version> <unpadded base64 seed>`, returning its version and
+/// PKCS#8 document.
+pub(super) fn parse_synapse(contents: &str) -> Result<(String, Vec<u8>)> {
+	// PKCS#8 v1 prefix for an ed25519 private key followed by its 32 byte seed.
+	const PKCS8_PREFIX: [u8; 16] = [
+		0x30, 0x2E, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2B, 0x65, 0x70, 0x04, 0x22, 0x04,
+		0x20,
+	];
+
+	let mut parts = contents
+		.lines()
+		.find(|line| !line.trim().is_empty())
+		.ok_or_else(|| err!("Signing key file is empty."))?
+		.split_whitespace();
+
+	let (Some("ed25519"), Some(version), Some(seed), None) =
+		(parts.next(), parts.next(), parts.next(), parts.next())
+	else {
+		return Err!("Expected a line of the form `ed25519 <version> <base64 key>`.");
+	};
+
+	let seed =
+		Base64::<Standard>::parse(seed).map_err(|e| err!("Failed to decode signing key: {e}"))?;
+
+	if seed.as_bytes().len() != 32 {
+		return Err!("Signing key must be 32 bytes; found {}.", seed.as_bytes().len());
+	}
+
+	let der = PKCS8_PREFIX
+		.iter()
+		.chain(seed.as_bytes())
+		.copied()
+		.collect();
+
+	Ok((version.to_owned(), der))
+}
+
 fn load(db: &Arc<Database>) -> Result<Box<Ed25519KeyPair>> {
 	let (version, key) = db["global"]
 		.get_blocking(b"keypair")
@@ -46,13 +133,7 @@ fn load(db: &Arc<Database>) -> Result<Box<Ed25519KeyPair>> {
 }
 
 fn create(db: &Arc<Database>) -> Result<(String, Vec<u8>)> {
-	let keypair = Ed25519KeyPair::generate()
-		.map_err(|e| err!("Failed to generate new ed25519 keypair: {e:?}"))?;
-
-	let id = utils::rand::string(8);
-	debug_info!("Generated new Ed25519 keypair: {id:?}");
-
-	let value: (String, Vec<u8>) = (id, keypair.to_vec());
+	let value = generate()?;
 	db["global"].raw_put(b"keypair", &value);
 
 	Ok(value)
@@ -1,44 +1,65 @@
 mod acquire;
 mod get;
 mod keypair;
+mod notary;
+mod refresh;
 mod request;
+mod rotate;
 mod sign;
 mod verify;
 
-use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use std::{
+	collections::BTreeMap,
+	sync::{Arc, Mutex, RwLock},
+	time::Duration,
+};
 
+use async_trait::async_trait;
 use futures::StreamExt;
 use ruma::{
-	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedServerSigningKeyId, ServerName,
-	ServerSigningKeyId,
-	api::federation::discovery::{ServerSigningKeys, VerifyKey},
+	CanonicalJsonObject, MilliSecondsSinceUnixEpoch, OwnedServerName, OwnedServerSigningKeyId,
+	ServerName, ServerSigningKeyId,
+	api::federation::discovery::{OldVerifyKey, ServerSigningKeys, VerifyKey},
 	room_version_rules::RoomVersionRules,
 	serde::Raw,
 	signatures::{Ed25519KeyPair, PublicKeyMap, PublicKeySet},
 };
 use serde_json::value::RawValue as RawJsonValue;
+use tokio::time::sleep;
 use tuwunel_core::{
-	Result, implement,
+	Result, debug_info, implement,
 	utils::{IterStream, timepoint_from_now},
 };
 use tuwunel_database::{Deserialized, Json, Map};
 
+pub use self::refresh::KeyStatus;
+
 pub struct Service {
-	keypair: Box<Ed25519KeyPair>,
-	verify_keys: VerifyKeys,
+	keys: RwLock<Keys>,
 	minimum_valid: Duration,
+	refresh_errors: Mutex<BTreeMap<OwnedServerName, String>>,
 	services: Arc<crate::services::OnceServices>,
 	db: Data,
 }
 
+struct Keys {
+	keypair: Arc<Ed25519KeyPair>,
+	verify_keys: VerifyKeys,
+	old_verify_keys: OldVerifyKeys,
+}
+
 struct Data {
+	global: Arc<Map>,
+	server_signedkeys: Arc<Map>,
 	server_signingkeys: Arc<Map>,
 }
 
 pub type VerifyKeys = BTreeMap<OwnedServerSigningKeyId, VerifyKey>;
+pub type OldVerifyKeys = BTreeMap<OwnedServerSigningKeyId, OldVerifyKey>;
 pub type PubKeyMap = PublicKeyMap;
 pub type PubKeys = PublicKeySet;
 
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		let minimum_valid = Duration::from_secs(3600);
@@ -46,42 +67,95 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		let (keypair, verify_keys) = keypair::init(args.db)?;
 		debug_assert!(verify_keys.len() == 1, "only one active verify_key supported");
 
+		let old_verify_keys = keypair::init_old(args.db)?;
+
 		Ok(Arc::new(Self {
-			keypair,
-			verify_keys,
+			keys: RwLock::new(Keys {
+				keypair: keypair.into(),
+				verify_keys,
+				old_verify_keys,
+			}),
 			minimum_valid,
+			refresh_errors: Mutex::default(),
 			services: args.services.clone(),
 			db: Data {
+				global: args.db["global"].clone(),
+				server_signedkeys: args.db["server_signedkeys"].clone(),
 				server_signingkeys: args.db["server_signingkeys"].clone(),
 			},
 		}))
 	}
 
+	async fn worker(self: Arc<Self>) -> Result {
+		let interval = self
+			.services
+			.server
+			.config
+			.signing_key_refresh_interval;
+
+		if interval == 0 || !self.services.server.config.allow_federation {
+			return Ok(());
+		}
+
+		let interval = Duration::from_secs(interval);
+		while self.services.server.running() {
+			tokio::select! {
+				() = self.services.server.until_shutdown() => break,
+				() = sleep(interval) => {
+					let refreshed = self.refresh_keys().await;
+					if refreshed > 0 {
+						debug_info!(refreshed, "Refreshed signing keys");
+					}
+				},
+			}
+		}
+
+		Ok(())
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
 #[implement(Service)]
 #[inline]
 #[must_use]
-pub fn keypair(&self) -> &Ed25519KeyPair { &self.keypair }
+pub fn keypair(&self) -> Arc<Ed25519KeyPair> {
+	self.keys
+		.read()
+		.expect("locked for reading")
+		.keypair
+		.clone()
+}
 
 #[implement(Service)]
 #[inline]
 #[must_use]
-pub fn active_key_id(&self) -> &ServerSigningKeyId { self.active_verify_key().0 }
+pub fn active_key_id(&self) -> OwnedServerSigningKeyId { self.active_verify_key().0 }
 
 #[implement(Service)]
-#[inline]
 #[must_use]
-pub fn active_verify_key(&self) -> (&ServerSigningKeyId, &VerifyKey) {
-	debug_assert!(self.verify_keys.len() <= 1, "more than one active verify_key");
-	self.verify_keys
+pub fn active_verify_key(&self) -> (OwnedServerSigningKeyId, VerifyKey) {
+	let keys = self.keys.read().expect("locked for reading");
+
+	debug_assert!(keys.verify_keys.len() <= 1, "more than one active verify_key");
+	keys.verify_keys
 		.iter()
 		.next()
-		.map(|(id, key)| (id.as_ref(), key))
+		.map(|(id, key)| (id.clone(), key.clone()))
 		.expect("missing active verify_key")
 }
 
+/// Our previously active keys, published with the time they were retired.
+#[implement(Service)]
+#[must_use]
+pub fn old_verify_keys(&self) -> OldVerifyKeys {
+	self.keys
+		.read()
+		.expect("locked for reading")
+		.old_verify_keys
+		.clone()
+}
+
 #[implement(Service)]
 async fn add_signing_keys(&self, new_keys: ServerSigningKeys) {
 	let origin = &new_keys.server_name;
@@ -98,6 +172,7 @@ async fn add_signing_keys(&self, new_keys: ServerSigningKeys) {
 			ServerSigningKeys::new(origin.to_owned(), MilliSecondsSinceUnixEpoch::now())
 		});
 
+	keys.valid_until_ts = keys.valid_until_ts.max(new_keys.valid_until_ts);
 	keys.verify_keys.extend(new_keys.verify_keys);
 	keys.old_verify_keys
 		.extend(new_keys.old_verify_keys);
@@ -165,7 +240,14 @@ pub async fn verify_keys_for(&self, origin: &ServerName) -> VerifyKeys {
 		.unwrap_or(BTreeMap::new());
 
 	if self.services.globals.server_is_ours(origin) {
-		keys.extend(self.verify_keys.clone().into_iter());
+		let ours = self.keys.read().expect("locked for reading");
+		keys.extend(
+			ours.old_verify_keys
+				.iter()
+				.map(|(key_id, old)| (key_id.clone(), VerifyKey::new(old.key.clone()))),
+		);
+
+		keys.extend(ours.verify_keys.clone());
 	}
 
 	keys
@@ -181,9 +263,10 @@ pub async fn signing_keys_for(&self, origin: &ServerName) -> Result<ServerSignin
 }
 
 #[implement(Service)]
-fn minimum_valid_ts(&self) -> MilliSecondsSinceUnixEpoch {
-	let timepoint =
-		timepoint_from_now(self.minimum_valid).expect("SystemTime should not overflow");
+fn minimum_valid_ts(&self) -> MilliSecondsSinceUnixEpoch { ts_from_now(self.minimum_valid) }
+
+fn ts_from_now(duration: Duration) -> MilliSecondsSinceUnixEpoch {
+	let timepoint = timepoint_from_now(duration).expect("SystemTime should not overflow");
 
 	MilliSecondsSinceUnixEpoch::from_system_time(timepoint).expect("UInt should not overflow")
 }
@@ -0,0 +1,95 @@
+use ruma::{
+	CanonicalJsonObject, CanonicalJsonValue, MilliSecondsSinceUnixEpoch, ServerName, UInt,
+	api::federation::discovery::ServerSigningKeys, serde::Raw,
+};
+use tuwunel_core::{Result, debug_warn, implement};
+use tuwunel_database::{Deserialized, Json};
+
+/// Returns the keys of a server as signed by it, to be served to other
+/// servers as a notary. Keys which expire before `minimum_valid_until_ts` are
+/// fetched from the server again; should that fail the keys we have are
+/// returned regardless.
+#[implement(super::Service)]
+pub async fn notary_keys(
+	&self,
+	server: &ServerName,
+	minimum_valid_until_ts: MilliSecondsSinceUnixEpoch,
+) -> Result<CanonicalJsonObject> {
+	let cached = self.signed_keys_for(server).await;
+	if cached
+		.as_ref()
+		.ok()
+		.and_then(valid_until_ts)
+		.is_some_and(|ts| ts >= minimum_valid_until_ts)
+	{
+		return cached;
+	}
+
+	match self.server_request(server).await {
+		| Ok(server_keys) => {
+			self.add_signing_keys(server_keys).await;
+			self.signed_keys_for(server).await
+		},
+		| Err(e) => {
+			debug_warn!(%server, "Failed to fetch keys for notary request: {e}");
+			cached.or(Err(e))
+		},
+	}
+}
+
+#[implement(super::Service)]
+async fn signed_keys_for(&self, server: &ServerName) -> Result<CanonicalJsonObject> {
+	self.db
+		.server_signedkeys
+		.get(server)
+		.await
+		.deserialized()
+}
+
+/// Keeps the keys of a server as received with only its own signature, so
+/// they can be verified by servers we serve them to. Older keys are not stored
+/// over newer ones.
+#[implement(super::Service)]
+pub(super) async fn add_signed_keys(
+	&self,
+	new_keys: &ServerSigningKeys,
+	raw: &Raw<ServerSigningKeys>,
+) {
+	let origin = &new_keys.server_name;
+	let Ok(mut signed) = serde_json::from_str::<CanonicalJsonObject>(raw.json().get()) else {
+		return;
+	};
+
+	let Some(CanonicalJsonValue::Object(signatures)) = signed.get_mut("signatures") else {
+		return;
+	};
+
+	signatures.retain(|server, _| server == origin.as_str());
+	if signatures.is_empty() {
+		return;
+	}
+
+	let stored = self
+		.signed_keys_for(origin)
+		.await
+		.ok()
+		.as_ref()
+		.and_then(valid_until_ts);
+
+	if stored.is_some_and(|ts| ts > new_keys.valid_until_ts) {
+		return;
+	}
+
+	self.db
+		.server_signedkeys
+		.raw_put(origin, Json(&signed));
+}
+
+fn valid_until_ts(keys: &CanonicalJsonObject) -> Option<MilliSecondsSinceUnixEpoch> {
+	match keys.get("valid_until_ts") {
+		| Some(CanonicalJsonValue::Integer(ts)) => UInt::try_from(*ts)
+			.ok()
+			.map(MilliSecondsSinceUnixEpoch),
+		| _ => None,
+	}
+}
@@ -0,0 +1,199 @@
+use std::{
+	borrow::Borrow,
+	collections::{BTreeMap, BTreeSet},
+	iter::empty,
+	time::Duration,
+};
+
+use futures::{StreamExt, pin_mut, stream::iter};
+use ruma::{MilliSecondsSinceUnixEpoch, OwnedServerName, ServerSigningKeyId};
+use tokio::time::timeout;
+use tuwunel_core::{debug, debug_info, debug_warn, implement};
+
+use super::ts_from_now;
+
+/// Signing keys of a server in rooms with local users.
+#[derive(Clone, Debug)]
+pub struct KeyStatus {
+	pub server: OwnedServerName,
+
+	/// Expiry of the keys we have for the server; none without any keys.
+	pub valid_until_ts: Option<MilliSecondsSinceUnixEpoch>,
+
+	/// Why the keys could not be refreshed on the last attempt.
+	pub error: Option<String>,
+}
+
+impl KeyStatus {
+	/// Whether we hold no keys for the server which are still valid.
+	#[must_use]
+	pub fn is_expired(&self) -> bool {
+		self.valid_until_ts
+			.is_none_or(|ts| ts < MilliSecondsSinceUnixEpoch::now())
+	}
+}
+
+/// Servers asked for their own keys at once by the refresh worker.
+const ORIGIN_CONCURRENCY: usize = 32;
+
+/// Time allowed for a server to respond with its own keys.
+const ORIGIN_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Fetches the keys of servers in rooms with local users which we don't have
+/// or which expire soon; first from the trusted servers in batches, then
+/// from the servers themselves. Returns the number of servers whose keys
+/// were refreshed.
+#[implement(super::Service)]
+#[tracing::instrument(name = "refresh", level = "debug", skip_all)]
+pub async fn refresh_keys(&self) -> usize {
+	let config = &self.services.server.config;
+	let window = Duration::from_secs(config.signing_key_refresh_window);
+	let threshold = ts_from_now(window);
+
+	let mut pending = BTreeSet::new();
+	for server in self.active_servers().await {
+		let valid_until_ts = self
+			.signing_keys_for(&server)
+			.await
+			.map(|keys| keys.valid_until_ts);
+
+		if valid_until_ts.is_ok_and(|ts| ts >= threshold) {
+			continue;
+		}
+
+		pending.insert(server);
+	}
+
+	if pending.is_empty() {
+		return 0;
+	}
+
+	debug!(servers = pending.len(), "Refreshing signing keys");
+	let mut refreshed = 0_usize;
+	let mut errors = BTreeMap::new();
+	for notary in &config.trusted_servers {
+		if pending.is_empty() {
+			break;
+		}
+
+		let batch = pending
+			.iter()
+			.map(|server| (server.borrow(), empty::<&ServerSigningKeyId>()));
+
+		match self.batch_notary_request(notary, batch).await {
+			| Err(e) => debug_warn!(%notary, "Failed to refresh signing keys: {e}"),
+			| Ok(results) =>
+				for server_keys in results {
+					let server = server_keys.server_name.clone();
+					let valid_until_ts = server_keys.valid_until_ts;
+					self.add_signing_keys(server_keys).await;
+					if valid_until_ts >= threshold && pending.remove(&server) {
+						refreshed = refreshed.saturating_add(1);
+					}
+				},
+		}
+	}
+
+	if config.only_query_trusted_key_servers {
+		errors.extend(
+			pending
+				.into_iter()
+				.map(|server| (server, "No trusted server returned the keys".to_owned())),
+		);
+	} else {
+		let mut reachable = Vec::with_capacity(pending.len());
+		for server in pending {
+			if self
+				.services
+				.sending
+				.destination_health(&server)
+				.await
+				.is_ok_and(|health| health.is_backing_off())
+			{
+				errors.insert(server, "Backing off after failed transactions".to_owned());
+			} else {
+				reachable.push(server);
+			}
+		}
+
+		let requests = iter(reachable)
+			.map(async |server| {
+				let result = timeout(ORIGIN_TIMEOUT, self.server_request(&server)).await;
+				(server, result)
+			})
+			.buffer_unordered(ORIGIN_CONCURRENCY);
+
+		pin_mut!(requests);
+		while let Some((server, result)) = requests.next().await {
+			match result {
+				| Err(_) => {
+					errors.insert(server, "Timed out".to_owned());
+				},
+				| Ok(Err(e)) => {
+					errors.insert(server, e.to_string());
+				},
+				| Ok(Ok(server_keys)) => {
+					self.add_signing_keys(server_keys).await;
+					refreshed = refreshed.saturating_add(1);
+				},
+			}
+		}
+	}
+
+	if !errors.is_empty() {
+		debug_info!(refreshed, failed = errors.len(), "Failed to refresh some signing keys");
+	}
+
+	*self.refresh_errors.lock().expect("locked") = errors;
+
+	refreshed
+}
+
+/// Reports the keys of every server in rooms with local users, with the
+/// error from the last refresh of those which failed.
+#[implement(super::Service)]
+pub async fn key_status(&self) -> Vec<KeyStatus> {
+	let mut statuses = Vec::new();
+	for server in self.active_servers().await {
+		let valid_until_ts = self
+			.signing_keys_for(&server)
+			.await
+			.map(|keys| keys.valid_until_ts)
+			.ok();
+
+		let error = self
+			.refresh_errors
+			.lock()
+			.expect("locked")
+			.get(&server)
+			.cloned();
+
+		statuses.push(KeyStatus { server, valid_until_ts, error });
+	}
+
+	statuses
+}
+
+/// Remote servers in rooms with local users.
+#[implement(super::Service)]
+async fn active_servers(&self) -> BTreeSet<OwnedServerName> {
+	let rooms = self
+		.services
+		.state_cache
+		.server_rooms(self.services.globals.server_name());
+
+	let mut servers = BTreeSet::new();
+	pin_mut!(rooms);
+	while let Some(room_id) = rooms.next().await {
+		let room_servers = self.services.state_cache.room_servers(room_id);
+
+		pin_mut!(room_servers);
+		while let Some(server) = room_servers.next().await {
+			if !self.services.globals.server_is_ours(server) {
+				servers.insert(server.to_owned());
+			}
+		}
+	}
+
+	servers
+}
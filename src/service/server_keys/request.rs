@@ -68,12 +68,14 @@ pub(super) async fn batch_notary_request<'a, S, K>(
 			.sending
 			.send_synapse_request(notary, request)
 			.await?
-			.server_keys
-			.into_iter()
-			.map(|key| key.deserialize())
-			.filter_map(Result::ok);
-
-		results.extend(response);
+			.server_keys;
+
+		for raw in response {
+			if let Ok(server_keys) = raw.deserialize() {
+				self.add_signed_keys(&server_keys, &raw).await;
+				results.push(server_keys);
+			}
+		}
 	}
 
 	Ok(results)
@@ -97,26 +99,31 @@ pub async fn notary_request(
 		.sending
 		.send_federation_request(notary, request)
 		.await?
-		.server_keys
-		.into_iter()
-		.map(|key| key.deserialize())
-		.filter_map(Result::ok);
+		.server_keys;
+
+	let mut results = Vec::with_capacity(response.len());
+	for raw in response {
+		if let Ok(server_keys) = raw.deserialize() {
+			self.add_signed_keys(&server_keys, &raw).await;
+			results.push(server_keys);
+		}
+	}
 
-	Ok(response)
+	Ok(results.into_iter())
 }
 
 #[implement(super::Service)]
 pub async fn server_request(&self, target: &ServerName) -> Result<ServerSigningKeys> {
 	use get_server_keys::v2::Request;
 
-	let server_signing_key = self
+	let raw = self
 		.services
 		.sending
 		.send_federation_request(target, Request::new())
-		.await
-		.map(|response| response.server_key)
-		.and_then(|key| key.deserialize().map_err(Into::into))?;
+		.await?
+		.server_key;
 
+	let server_signing_key = raw.deserialize()?;
 	if server_signing_key.server_name != target {
 		return Err!(BadServerResponse(debug_warn!(
 			requested = ?target,
@@ -125,5 +132,8 @@ pub async fn server_request(&self, target: &ServerName) -> Result<ServerSigningK
 		)));
 	}
 
+	self.add_signed_keys(&server_signing_key, &raw)
+		.await;
+
 	Ok(server_signing_key)
 }
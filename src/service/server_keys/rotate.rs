@@ -0,0 +1,65 @@
+use std::{mem::replace, path::Path};
+
+use ruma::{
+	MilliSecondsSinceUnixEpoch, OwnedServerSigningKeyId,
+	api::federation::discovery::{OldVerifyKey, VerifyKey},
+	serde::Base64,
+};
+use tuwunel_core::{Err, Result, err, implement, info};
+
+use super::{VerifyKeys, keypair};
+
+/// Generates a new signing key and makes it active. The previously active key
+/// is retired into `old_verify_keys` so signatures made with it can still be
+/// verified by other servers. Returns the id of the new key.
+#[implement(super::Service)]
+pub fn rotate_keypair(&self) -> Result<OwnedServerSigningKeyId> {
+	let (version, der) = keypair::generate()?;
+
+	self.replace_keypair(version, der)
+}
+
+/// Imports a signing key from a Synapse-format signing key file and makes it
+/// active, retiring the previously active key. Returns the id of the imported
+/// key.
+#[implement(super::Service)]
+pub async fn import_keypair(&self, path: &Path) -> Result<OwnedServerSigningKeyId> {
+	let contents = tokio::fs::read_to_string(path)
+		.await
+		.map_err(|e| err!("Failed to read signing key file {path:?}: {e}"))?;
+
+	let (version, der) = keypair::parse_synapse(&contents)?;
+
+	self.replace_keypair(version, der)
+}
+
+#[implement(super::Service)]
+fn replace_keypair(&self, version: String, der: Vec<u8>) -> Result<OwnedServerSigningKeyId> {
+	let key_id: OwnedServerSigningKeyId = format!("ed25519:{version}").try_into()?;
+
+	let mut keys = self.keys.write().expect("locked for writing");
+	if keys.verify_keys.contains_key(&key_id) || keys.old_verify_keys.contains_key(&key_id) {
+		return Err!("A signing key with id {key_id} has already been used by this server.");
+	}
+
+	let keypair = keypair::store(&self.db.global, version, der)?;
+	let verify_key = VerifyKey {
+		key: Base64::new(keypair.public_key().to_vec()),
+	};
+
+	let expired_ts = MilliSecondsSinceUnixEpoch::now();
+	let retired: VerifyKeys =
+		replace(&mut keys.verify_keys, [(key_id.clone(), verify_key)].into());
+	keys.old_verify_keys.extend(
+		retired
+			.into_iter()
+			.map(|(key_id, verify_key)| (key_id, OldVerifyKey::new(expired_ts, verify_key.key))),
+	);
+
+	keypair::store_old(&self.db.global, &keys.old_verify_keys)?;
+	keys.keypair = keypair.into();
+
+	info!(?key_id, "Activated new signing key");
+
+	Ok(key_id)
+}
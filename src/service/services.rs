@@ -9,16 +9,17 @@
 
 pub(crate) use crate::OnceServices;
 use crate::{
-	account_data, admin, appservice, client, config, deactivate, emergency, federation, globals,
-	key_backups,
+	account_data, acme, admin, appservice, client, config, deactivate, emergency, federation,
+	globals, key_backups,
 	manager::Manager,
-	media, membership, presence, pusher, resolver, rooms, sending, server_keys,
+	media, membership, presence, pusher, remote_profile, resolver, rooms, sending, server_keys,
 	service::{Args, Service},
 	sync, transaction_ids, uiaa, users,
 };
 
 pub struct Services {
 	pub account_data: Arc<account_data::Service>,
+	pub acme: Arc<acme::Service>,
 	pub admin: Arc<admin::Service>,
 	pub appservice: Arc<appservice::Service>,
 	pub config: Arc<config::Service>,
@@ -29,14 +30,17 @@ pub struct Services {
 	pub media: Arc<media::Service>,
 	pub presence: Arc<presence::Service>,
 	pub pusher: Arc<pusher::Service>,
+	pub remote_profile: Arc<remote_profile::Service>,
 	pub resolver: Arc<resolver::Service>,
 	pub alias: Arc<rooms::alias::Service>,
 	pub auth_chain: Arc<rooms::auth_chain::Service>,
+	pub backfill: Arc<rooms::backfill::Service>,
 	pub delete: Arc<rooms::delete::Service>,
 	pub directory: Arc<rooms::directory::Service>,
 	pub event_handler: Arc<rooms::event_handler::Service>,
 	pub lazy_loading: Arc<rooms::lazy_loading::Service>,
 	pub metadata: Arc<rooms::metadata::Service>,
+	pub partial_state: Arc<rooms::partial_state::Service>,
 	pub pdu_metadata: Arc<rooms::pdu_metadata::Service>,
 	pub read_receipt: Arc<rooms::read_receipt::Service>,
 	pub search: Arc<rooms::search::Service>,
@@ -77,6 +81,7 @@ pub async fn build(server: Arc<Server>) -> Result<Arc<Self>> {
 
 	let res = Arc::new(Self {
 		account_data: account_data::Service::build(&args)?,
+		acme: acme::Service::build(&args)?,
 		admin: admin::Service::build(&args)?,
 		appservice: appservice::Service::build(&args)?,
 		resolver: resolver::Service::build(&args)?,
@@ -88,13 +93,16 @@ pub async fn build(server: Arc<Server>) -> Result<Arc<Self>> {
 		media: media::Service::build(&args)?,
 		presence: presence::Service::build(&args)?,
 		pusher: pusher::Service::build(&args)?,
+		remote_profile: remote_profile::Service::build(&args)?,
 		alias: rooms::alias::Service::build(&args)?,
 		auth_chain: rooms::auth_chain::Service::build(&args)?,
+		backfill: rooms::backfill::Service::build(&args)?,
 		delete: rooms::delete::Service::build(&args)?,
 		directory: rooms::directory::Service::build(&args)?,
 		event_handler: rooms::event_handler::Service::build(&args)?,
 		lazy_loading: rooms::lazy_loading::Service::build(&args)?,
 		metadata: rooms::metadata::Service::build(&args)?,
+		partial_state: rooms::partial_state::Service::build(&args)?,
 		pdu_metadata: rooms::pdu_metadata::Service::build(&args)?,
 		read_receipt: rooms::read_receipt::Service::build(&args)?,
 		search: rooms::search::Service::build(&args)?,
@@ -136,6 +144,7 @@ macro_rules! cast {
 
 	[
 		cast!(self.account_data),
+		cast!(self.acme),
 		cast!(self.admin),
 		cast!(self.appservice),
 		cast!(self.resolver),
@@ -147,13 +156,16 @@ macro_rules! cast {
 		cast!(self.media),
 		cast!(self.presence),
 		cast!(self.pusher),
+		cast!(self.remote_profile),
 		cast!(self.alias),
 		cast!(self.auth_chain),
+		cast!(self.backfill),
 		cast!(self.delete),
 		cast!(self.directory),
 		cast!(self.event_handler),
 		cast!(self.lazy_loading),
 		cast!(self.metadata),
+		cast!(self.partial_state),
 		cast!(self.pdu_metadata),
 		cast!(self.read_receipt),
 		cast!(self.search),
@@ -38,6 +38,7 @@ pub struct Data {
 	readreceiptid_readreceipt: Arc<Map>,
 	userid_lastonetimekeyupdate: Arc<Map>,
 	roomuserid_lastnotificationread: Arc<Map>,
+	roomid_resynccount: Arc<Map>,
 }
 
 #[derive(Debug, Default)]
@@ -84,6 +85,7 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 				userid_lastonetimekeyupdate: args.db["userid_lastonetimekeyupdate"].clone(),
 				roomuserid_lastnotificationread: args.db["roomuserid_lastnotificationread"]
 					.clone(),
+				roomid_resynccount: args.db["roomid_resynccount"].clone(),
 			},
 			services: args.services.clone(),
 			connections: Default::default(),
@@ -110,6 +110,11 @@ pub async fn watch<'a, Rooms>(
 				.readreceiptid_readreceipt
 				.watch_prefix(&roomid_prefix)
 				.boxed(),
+			// Full state of a room joined with partial state
+			self.db
+				.roomid_resynccount
+				.watch_raw_prefix(room_id)
+				.boxed(),
 			// Typing
 			async move {
 				self.services
@@ -1,10 +1,26 @@
-use std::sync::Arc;
+use std::{
+	fmt::Write,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
 
-use ruma::{DeviceId, TransactionId, UserId};
-use tuwunel_core::{Result, implement};
+use async_trait::async_trait;
+use lru_cache::LruCache;
+use ruma::{
+	DeviceId, OwnedServerName, OwnedTransactionId, ServerName, TransactionId, UserId,
+	api::federation::transactions::send_transaction_message,
+};
+use tuwunel_core::{
+	Result, implement,
+	utils::{MutexMap, MutexMapGuard, math::usize_from_f64},
+};
 use tuwunel_database::{Handle, Map};
 
 pub struct Service {
+	/// Serializes inbound federation transactions per origin.
+	pub mutex_federation: OriginMutexMap,
+	federation_txns: Mutex<FederationTxnCache>,
+	federation_txn_lifetime: Duration,
 	db: Data,
 }
 
@@ -12,18 +28,85 @@ struct Data {
 	userdevicetxnid_response: Arc<Map>,
 }
 
+pub type OriginMutexMap = MutexMap<OwnedServerName, ()>;
+pub type OriginMutexGuard = MutexMapGuard<OwnedServerName, ()>;
+
+type FederationTxnCache = LruCache<(OwnedServerName, OwnedTransactionId), FederationTxn>;
+type FederationTxn = (Instant, send_transaction_message::v1::Response);
+
+const FEDERATION_TXN_CACHE_CAPACITY: f64 = 1024.0;
+
+#[async_trait]
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+		let cache_capacity = FEDERATION_TXN_CACHE_CAPACITY * config.cache_capacity_modifier;
+
 		Ok(Arc::new(Self {
+			mutex_federation: OriginMutexMap::new(),
+			federation_txns: LruCache::new(usize_from_f64(cache_capacity)?).into(),
+			federation_txn_lifetime: Duration::from_secs(config.federation_txn_cache_lifetime),
 			db: Data {
 				userdevicetxnid_response: args.db["userdevicetxnid_response"].clone(),
 			},
 		}))
 	}
 
+	async fn memory_usage(&self, out: &mut (dyn Write + Send)) -> Result {
+		let federation_txns = self.federation_txns.lock().expect("locked").len();
+		writeln!(out, "federation_txns_cache: {federation_txns}")?;
+
+		let mutex_federation = self.mutex_federation.len();
+		writeln!(out, "federation_mutex: {mutex_federation}")?;
+
+		Ok(())
+	}
+
+	async fn clear_cache(&self) {
+		self.federation_txns
+			.lock()
+			.expect("locked")
+			.clear();
+	}
+
 	fn name(&self) -> &str { crate::service::make_name(std::module_path!()) }
 }
 
+/// Remembers the response to an inbound federation transaction so a retry
+/// of it can be answered without processing it again.
+#[implement(Service)]
+pub fn add_federation_txnid(
+	&self,
+	origin: &ServerName,
+	txn_id: &TransactionId,
+	response: &send_transaction_message::v1::Response,
+) {
+	self.federation_txns
+		.lock()
+		.expect("locked")
+		.insert((origin.to_owned(), txn_id.to_owned()), (Instant::now(), response.clone()));
+}
+
+/// Returns the response to an inbound federation transaction processed
+/// within the configured lifetime. If there's none, this is a new
+/// transaction.
+#[implement(Service)]
+pub fn existing_federation_txnid(
+	&self,
+	origin: &ServerName,
+	txn_id: &TransactionId,
+) -> Option<send_transaction_message::v1::Response> {
+	let key = (origin.to_owned(), txn_id.to_owned());
+	let mut cache = self.federation_txns.lock().expect("locked");
+	let (time, response) = cache.get_mut(&key)?;
+	if time.elapsed() < self.federation_txn_lifetime {
+		return Some(response.clone());
+	}
+
+	cache.remove(&key);
+	None
+}
+
 #[implement(Service)]
 pub fn add_txnid(
 	&self,
@@ -31,6 +31,9 @@ struct Data {
 
 pub const SESSION_ID_LENGTH: usize = 32;
 
+/// Stage completed by approving a cross-signing reset out of band.
+pub const OAUTH_STAGE: &str = "m.oauth";
+
 impl crate::Service for Service {
 	fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
@@ -173,6 +176,25 @@ pub async fn try_auth(
 		| AuthData::Dummy(_) => {
 			uiaainfo.completed.push(AuthType::Dummy);
 		},
+		| auth if auth.auth_type() == Some(AuthType::from(OAUTH_STAGE)) => {
+			if !self
+				.services
+				.users
+				.cross_signing_reset_allowed(user_id)
+				.await
+			{
+				uiaainfo.auth_error = Some(StandardErrorBody {
+					kind: ErrorKind::forbidden(),
+					message: "Cross-signing reset has not been approved.".to_owned(),
+				});
+
+				return Ok((false, uiaainfo));
+			}
+
+			uiaainfo
+				.completed
+				.push(AuthType::from(OAUTH_STAGE));
+		},
 		| auth => error!("AuthData type not supported: {auth:?}"),
 	}
 
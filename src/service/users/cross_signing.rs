@@ -0,0 +1,143 @@
+use std::num::Saturating as Sat;
+
+use ruma::{OwnedUserId, UserId};
+use tuwunel_core::{
+	Err, Result, err, implement, trace,
+	utils::{self, ReadyExt, stream::TryIgnore},
+};
+use tuwunel_database::Deserialized;
+use url::Url;
+
+const RESET_TOKEN_LENGTH: usize = 32;
+
+/// Creates a link for the user to request replacing their cross-signing keys,
+/// for users who cannot complete the other authentication stages. LDAP users
+/// confirm the reset on the page with their password; the page also shows a
+/// code for a server admin to approve the reset with.
+#[implement(super::Service)]
+pub async fn cross_signing_reset_url(&self, user_id: &UserId) -> Result<Url> {
+	let base = match &self.services.server.config.well_known.client {
+		| Some(client) => client.to_string(),
+		| None => format!("https://{}", self.services.globals.server_name()),
+	};
+
+	self.remove_expired_cross_signing_reset_tokens()
+		.await;
+
+	let token = utils::random_string(RESET_TOKEN_LENGTH);
+	let url = Url::parse(&base)
+		.and_then(|base| base.join(&format!("/_tuwunel/cross_signing/reset/{token}")))
+		.map_err(|e| err!(Config("well_known.client", "Invalid client URL: {e}")))?;
+
+	let value = (self.cross_signing_reset_expiry(), user_id);
+	self.db
+		.crosssigningresettoken_userid
+		.raw_put(&token, value);
+
+	Ok(url)
+}
+
+/// Returns the user who requested a cross-signing reset link which is valid
+/// and awaiting approval.
+#[implement(super::Service)]
+pub async fn cross_signing_reset_user(&self, token: &str) -> Option<OwnedUserId> {
+	self.db
+		.crosssigningresettoken_userid
+		.get(token)
+		.await
+		.deserialized::<(u64, OwnedUserId)>()
+		.ok()
+		.filter(|(expires_at, _)| *expires_at >= utils::millis_since_unix_epoch())
+		.map(|(_, user_id)| user_id)
+}
+
+/// Removes a cross-signing reset link without approving it.
+#[implement(super::Service)]
+pub fn remove_cross_signing_reset_link(&self, token: &str) {
+	self.db
+		.crosssigningresettoken_userid
+		.remove(token);
+}
+
+/// Approves the cross-signing reset a link was created for. The link can only
+/// be used once.
+#[implement(super::Service)]
+pub async fn approve_cross_signing_reset(&self, token: &str) -> Result<OwnedUserId> {
+	let Ok(value) = self
+		.db
+		.crosssigningresettoken_userid
+		.get(token)
+		.await
+	else {
+		return Err!(Request(NotFound("Unknown cross-signing reset link.")));
+	};
+
+	self.db
+		.crosssigningresettoken_userid
+		.remove(token);
+
+	let (expires_at, user_id): (u64, OwnedUserId) = value.deserialized()?;
+	if expires_at < utils::millis_since_unix_epoch() {
+		trace!(?user_id, "Removed expired cross-signing reset link");
+		return Err!(Request(Forbidden("Cross-signing reset link is expired.")));
+	}
+
+	self.allow_cross_signing_reset(&user_id);
+
+	Ok(user_id)
+}
+
+/// Allows the user to replace their cross-signing keys without authentication
+/// for `cross_signing_reset_window` seconds.
+#[implement(super::Service)]
+pub fn allow_cross_signing_reset(&self, user_id: &UserId) {
+	self.db
+		.userid_crosssigningreset
+		.raw_put(user_id, self.cross_signing_reset_expiry());
+}
+
+/// Ends an approved cross-signing reset window once the keys were replaced.
+#[implement(super::Service)]
+pub fn clear_cross_signing_reset(&self, user_id: &UserId) {
+	self.db.userid_crosssigningreset.remove(user_id);
+}
+
+/// Whether the user is within an approved cross-signing reset window.
+#[implement(super::Service)]
+pub async fn cross_signing_reset_allowed(&self, user_id: &UserId) -> bool {
+	self.db
+		.userid_crosssigningreset
+		.get(user_id)
+		.await
+		.deserialized::<u64>()
+		.is_ok_and(|expires_at| expires_at >= utils::millis_since_unix_epoch())
+}
+
+#[implement(super::Service)]
+async fn remove_expired_cross_signing_reset_tokens(&self) {
+	type KeyVal<'a> = (&'a str, (u64, &'a UserId));
+
+	let now = utils::millis_since_unix_epoch();
+	self.db
+		.crosssigningresettoken_userid
+		.stream()
+		.ignore_err()
+		.ready_filter(|(_, (expires_at, _)): &KeyVal<'_>| *expires_at < now)
+		.ready_for_each(|(token, _)| {
+			self.db
+				.crosssigningresettoken_userid
+				.remove(token);
+		})
+		.await;
+}
+
+#[implement(super::Service)]
+fn cross_signing_reset_expiry(&self) -> u64 {
+	let window = self
+		.services
+		.server
+		.config
+		.cross_signing_reset_window;
+
+	(Sat(utils::millis_since_unix_epoch()) + Sat(window) * Sat(1000)).0
+}
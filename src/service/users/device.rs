@@ -6,11 +6,14 @@
 use futures::{FutureExt, Stream, StreamExt, future::join};
 use ruma::{
 	DeviceId, MilliSecondsSinceUnixEpoch, OwnedDeviceId, OwnedUserId, UserId,
-	api::client::device::Device, events::AnyToDeviceEvent, serde::Raw,
+	api::client::{dehydrated_device::DehydratedDeviceData, device::Device},
+	events::AnyToDeviceEvent,
+	serde::Raw,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tuwunel_core::{
-	Err, Result, at, implement,
+	Err, Result, at, err, implement,
 	utils::{
 		self, ReadyExt,
 		stream::{IterStream, TryIgnore},
@@ -22,6 +25,14 @@
 /// generated user access token length
 pub const TOKEN_LENGTH: usize = 32;
 
+/// The dehydrated device of a user, which keeps receiving to-device events
+/// while the user has no other device.
+#[derive(Deserialize, Serialize)]
+pub struct DehydratedDevice {
+	pub device_id: OwnedDeviceId,
+	pub device_data: Raw<DehydratedDeviceData>,
+}
+
 /// Adds a new device to a user.
 #[implement(super::Service)]
 #[tracing::instrument(level = "debug", skip(self))]
@@ -87,6 +98,27 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 
 	// TODO: Remove onetimekeys
 
+	// Remove fallback keys
+	self.db
+		.userdevicealgorithm_fallbackkey
+		.keys_prefix_raw(&prefix)
+		.ignore_err()
+		.ready_for_each(|key| {
+			self.db
+				.userdevicealgorithm_fallbackkey
+				.remove(key);
+		})
+		.await;
+
+	// Remove the dehydrated device data
+	if self
+		.dehydrated_device(user_id)
+		.await
+		.is_ok_and(|dehydrated| dehydrated.device_id == device_id)
+	{
+		self.db.userid_dehydrateddevice.remove(user_id);
+	}
+
 	increment(&self.db.userid_devicelistversion, user_id.as_bytes());
 
 	let userdeviceid = (user_id, device_id);
@@ -94,6 +126,85 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 	self.mark_device_key_update(user_id).await;
 }
 
+/// Replaces the dehydrated device of a user. The device has metadata like
+/// any other device, so it is listed and can have keys, but no access token.
+#[implement(super::Service)]
+#[tracing::instrument(level = "debug", skip(self, device_data))]
+pub async fn set_dehydrated_device(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	display_name: Option<String>,
+	device_data: Raw<DehydratedDeviceData>,
+) -> Result {
+	if !self.exists(user_id).await {
+		return Err!(Request(InvalidParam(error!(
+			"Called set_dehydrated_device for non-existent user {user_id}"
+		))));
+	}
+
+	let old = self.dehydrated_device(user_id).await.ok();
+
+	// The device being replaced may reuse its ID; any other device may not be.
+	let replacing = old
+		.as_ref()
+		.is_some_and(|old| old.device_id == device_id);
+
+	if !replacing
+		&& self
+			.get_device_metadata(user_id, device_id)
+			.await
+			.is_ok()
+	{
+		return Err!(Request(InvalidParam("A device with this ID already exists.")));
+	}
+
+	if let Some(old) = old {
+		self.remove_device(user_id, &old.device_id).await;
+	}
+
+	let device = Device {
+		device_id: device_id.into(),
+		display_name,
+		last_seen_ip: None,
+		last_seen_ts: Some(MilliSecondsSinceUnixEpoch::now()),
+	};
+
+	let dehydrated = DehydratedDevice { device_id: device_id.into(), device_data };
+
+	increment(&self.db.userid_devicelistversion, user_id.as_bytes());
+	self.db
+		.userdeviceid_metadata
+		.put((user_id, device_id), Json(device));
+	self.db
+		.userid_dehydrateddevice
+		.raw_put(user_id, Json(&dehydrated));
+
+	Ok(())
+}
+
+/// Removes the dehydrated device of a user, returning its ID.
+#[implement(super::Service)]
+pub async fn remove_dehydrated_device(&self, user_id: &UserId) -> Result<OwnedDeviceId> {
+	let dehydrated = self.dehydrated_device(user_id).await?;
+	self.remove_device(user_id, &dehydrated.device_id)
+		.await;
+
+	Ok(dehydrated.device_id)
+}
+
+#[implement(super::Service)]
+pub async fn dehydrated_device(&self, user_id: &UserId) -> Result<DehydratedDevice> {
+	let val = self
+		.db
+		.userid_dehydrateddevice
+		.get(user_id)
+		.await?;
+
+	serde_json::from_slice(&val)
+		.map_err(|e| err!(Database("DehydratedDevice in db is invalid. {e}")))
+}
+
 /// Returns an iterator over all device ids of this user.
 #[implement(super::Service)]
 pub fn all_device_ids<'a>(
@@ -8,11 +8,20 @@
 	encryption::{CrossSigningKey, DeviceKeys, OneTimeKey},
 	serde::Raw,
 };
+use serde::{Deserialize, Serialize};
 use tuwunel_core::{
 	Err, Error, Result, err, implement,
 	utils::{ReadyExt, stream::TryIgnore, string::Unquoted},
 };
-use tuwunel_database::{Deserialized, Ignore, Json};
+use tuwunel_database::{Deserialized, Ignore, Interfix, Json};
+
+/// The fallback key of a device for one algorithm.
+#[derive(Deserialize, Serialize)]
+struct FallbackKey {
+	key_id: OwnedKeyId<OneTimeKeyAlgorithm, OneTimeKeyName>,
+	key: Raw<OneTimeKey>,
+	used: bool,
+}
 
 #[implement(super::Service)]
 pub async fn add_one_time_key(
@@ -121,7 +130,97 @@ pub async fn take_one_time_key(
 		.next()
 		.await;
 
-	one_time_key.ok_or_else(|| err!(Request(NotFound("No one-time-key found"))))
+	match one_time_key {
+		| Some(one_time_key) => Ok(one_time_key),
+		| None => self
+			.take_fallback_key(user_id, device_id, key_algorithm)
+			.await
+			.map_err(|_| err!(Request(NotFound("No one-time-key found")))),
+	}
+}
+
+/// Stores the fallback key of a device for an algorithm, replacing any
+/// previous one. Uploading the current key again keeps it marked as used.
+#[implement(super::Service)]
+pub async fn add_fallback_key(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	key_id: &KeyId<OneTimeKeyAlgorithm, OneTimeKeyName>,
+	key: &Raw<OneTimeKey>,
+) {
+	let algorithm = key_id.algorithm();
+	let dbkey = (user_id, device_id, algorithm.as_str());
+	if let Ok(existing) = self.fallback_key(&dbkey).await {
+		if *existing.key_id == *key_id && existing.key.json().get() == key.json().get() {
+			return;
+		}
+	}
+
+	let fallback_key = FallbackKey {
+		key_id: key_id.to_owned(),
+		key: key.clone(),
+		used: false,
+	};
+
+	let count = self.services.globals.next_count();
+	self.db
+		.userdevicealgorithm_fallbackkey
+		.put(dbkey, Json(&fallback_key));
+	self.db
+		.userid_lastonetimekeyupdate
+		.raw_put(user_id, *count);
+}
+
+/// Hands out the fallback key of a device once its one-time keys have run
+/// out. The key stays available, but is reported as used until replaced.
+#[implement(super::Service)]
+async fn take_fallback_key(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+	key_algorithm: &OneTimeKeyAlgorithm,
+) -> Result<(OwnedKeyId<OneTimeKeyAlgorithm, OneTimeKeyName>, Raw<OneTimeKey>)> {
+	let dbkey = (user_id, device_id, key_algorithm.as_str());
+	let mut fallback_key = self.fallback_key(&dbkey).await?;
+	if !fallback_key.used {
+		fallback_key.used = true;
+		self.db
+			.userdevicealgorithm_fallbackkey
+			.put(dbkey, Json(&fallback_key));
+	}
+
+	Ok((fallback_key.key_id, fallback_key.key))
+}
+
+/// Algorithms for which the device has a fallback key not yet handed out.
+#[implement(super::Service)]
+pub async fn unused_fallback_key_types(
+	&self,
+	user_id: &UserId,
+	device_id: &DeviceId,
+) -> Vec<OneTimeKeyAlgorithm> {
+	let prefix = (user_id, device_id, Interfix);
+	self.db
+		.userdevicealgorithm_fallbackkey
+		.stream_prefix_raw(&prefix)
+		.ignore_err()
+		.ready_filter_map(|(_, val)| serde_json::from_slice::<FallbackKey>(val).ok())
+		.ready_filter(|fallback_key| !fallback_key.used)
+		.map(|fallback_key| fallback_key.key_id.algorithm())
+		.collect()
+		.await
+}
+
+#[implement(super::Service)]
+async fn fallback_key(&self, dbkey: &(&UserId, &DeviceId, &str)) -> Result<FallbackKey> {
+	let val = self
+		.db
+		.userdevicealgorithm_fallbackkey
+		.qry(dbkey)
+		.await?;
+
+	serde_json::from_slice(&val).map_err(|e| err!(Database("FallbackKey in db is invalid. {e}")))
 }
 
 #[implement(super::Service)]
@@ -1,9 +1,11 @@
+mod cross_signing;
 pub mod device;
 mod keys;
 mod ldap;
 mod profile;
+mod threepid;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use futures::{FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt, future::join3};
 use ruma::{
@@ -29,9 +31,14 @@
 pub struct Service {
 	services: Arc<crate::services::OnceServices>,
 	db: Data,
+	recent_sends: Mutex<threepid::RecentSends>,
 }
 
 struct Data {
+	crosssigningresettoken_userid: Arc<Map>,
+	emailexpiresatsid: Arc<Map>,
+	emailsecretaddress_sid: Arc<Map>,
+	emailsid_validation: Arc<Map>,
 	keychangeid_userid: Arc<Map>,
 	keyid_key: Arc<Map>,
 	onetimekeyid_onetimekeys: Arc<Map>,
@@ -39,12 +46,15 @@ struct Data {
 	logintoken_expiresatuserid: Arc<Map>,
 	todeviceid_events: Arc<Map>,
 	token_userdeviceid: Arc<Map>,
+	userdevicealgorithm_fallbackkey: Arc<Map>,
 	userdeviceid_metadata: Arc<Map>,
 	userdeviceid_token: Arc<Map>,
 	userdeviceid_refresh: Arc<Map>,
 	userfilterid_filter: Arc<Map>,
 	userid_avatarurl: Arc<Map>,
 	userid_blurhash: Arc<Map>,
+	userid_crosssigningreset: Arc<Map>,
+	userid_dehydrateddevice: Arc<Map>,
 	userid_devicelistversion: Arc<Map>,
 	userid_displayname: Arc<Map>,
 	userid_lastonetimekeyupdate: Arc<Map>,
@@ -53,6 +63,7 @@ struct Data {
 	userid_origin: Arc<Map>,
 	userid_selfsigningkeyid: Arc<Map>,
 	userid_usersigningkeyid: Arc<Map>,
+	useridaddress_threepid: Arc<Map>,
 	useridprofilekey_value: Arc<Map>,
 }
 
@@ -61,6 +72,10 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			services: args.services.clone(),
 			db: Data {
+				crosssigningresettoken_userid: args.db["crosssigningresettoken_userid"].clone(),
+				emailexpiresatsid: args.db["emailexpiresatsid"].clone(),
+				emailsecretaddress_sid: args.db["emailsecretaddress_sid"].clone(),
+				emailsid_validation: args.db["emailsid_validation"].clone(),
 				keychangeid_userid: args.db["keychangeid_userid"].clone(),
 				keyid_key: args.db["keyid_key"].clone(),
 				onetimekeyid_onetimekeys: args.db["onetimekeyid_onetimekeys"].clone(),
@@ -68,12 +83,16 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 				logintoken_expiresatuserid: args.db["logintoken_expiresatuserid"].clone(),
 				todeviceid_events: args.db["todeviceid_events"].clone(),
 				token_userdeviceid: args.db["token_userdeviceid"].clone(),
+				userdevicealgorithm_fallbackkey: args.db["userdevicealgorithm_fallbackkey"]
+					.clone(),
 				userdeviceid_metadata: args.db["userdeviceid_metadata"].clone(),
 				userdeviceid_token: args.db["userdeviceid_token"].clone(),
 				userdeviceid_refresh: args.db["userdeviceid_refresh"].clone(),
 				userfilterid_filter: args.db["userfilterid_filter"].clone(),
 				userid_avatarurl: args.db["userid_avatarurl"].clone(),
 				userid_blurhash: args.db["userid_blurhash"].clone(),
+				userid_crosssigningreset: args.db["userid_crosssigningreset"].clone(),
+				userid_dehydrateddevice: args.db["userid_dehydrateddevice"].clone(),
 				userid_devicelistversion: args.db["userid_devicelistversion"].clone(),
 				userid_displayname: args.db["userid_displayname"].clone(),
 				userid_lastonetimekeyupdate: args.db["userid_lastonetimekeyupdate"].clone(),
@@ -82,8 +101,10 @@ fn build(args: &crate::Args<'_>) -> Result<Arc<Self>> {
 				userid_origin: args.db["userid_origin"].clone(),
 				userid_selfsigningkeyid: args.db["userid_selfsigningkeyid"].clone(),
 				userid_usersigningkeyid: args.db["userid_usersigningkeyid"].clone(),
+				useridaddress_threepid: args.db["useridaddress_threepid"].clone(),
 				useridprofilekey_value: args.db["useridprofilekey_value"].clone(),
 			},
+			recent_sends: Mutex::default(),
 		}))
 	}
 
@@ -145,7 +166,8 @@ pub async fn deactivate_account(&self, user_id: &UserId) -> Result {
 		// account is deactivated.
 		self.set_password(user_id, None).await?;
 
-		// TODO: Unhook 3PID
+		self.remove_threepids(user_id).await;
+
 		Ok(())
 	}
 
@@ -224,6 +246,24 @@ pub async fn password_hash(&self, user_id: &UserId) -> Result<String> {
 			.deserialized()
 	}
 
+	/// Whether the user can authenticate with a local password. Users
+	/// provisioned through LDAP or SSO have none.
+	pub async fn has_password(&self, user_id: &UserId) -> bool {
+		let is_ldap = self
+			.db
+			.userid_origin
+			.get(user_id)
+			.await
+			.deserialized::<String>()
+			.is_ok_and(is_equal_to!("ldap"));
+
+		!is_ldap
+			&& self
+				.password_hash(user_id)
+				.await
+				.is_ok_and(|hash| !hash.is_empty())
+	}
+
 	/// Hash and set the user's password to the Argon2 hash
 	pub async fn set_password(&self, user_id: &UserId, password: Option<&str>) -> Result {
 		// Cannot change the password of a LDAP user. There are two special cases :
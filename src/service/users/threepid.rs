@@ -0,0 +1,411 @@
+use std::{
+	collections::VecDeque,
+	net::IpAddr,
+	time::{Duration, Instant},
+};
+
+use futures::StreamExt;
+use ruma::{
+	ClientSecret, MilliSecondsSinceUnixEpoch, OwnedClientSecret, OwnedSessionId, SessionId, UInt,
+	UserId,
+	api::client::error::{ErrorKind, RetryAfter},
+	thirdparty::{Medium, ThirdPartyIdentifier, ThirdPartyIdentifierInit},
+};
+use serde::{Deserialize, Serialize};
+use tuwunel_core::{
+	Err, Error, Result, debug, err,
+	http::StatusCode,
+	implement,
+	utils::{self, ReadyExt, stream::TryIgnore, time::now_millis},
+};
+use tuwunel_database::{Deserialized, Ignore, Interfix, Json};
+use url::Url;
+
+use crate::pusher::{is_email_address, smtp};
+
+/// Time for which a validation email can be confirmed.
+const VALIDATION_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// Period over which validation emails are counted against the limits below.
+const SEND_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Validation emails sent to one address within the window.
+const SENDS_PER_ADDRESS: usize = 3;
+
+/// Validation emails requested from one client address within the window.
+const SENDS_PER_CLIENT: usize = 10;
+
+/// Validation emails sent by the whole server within the window.
+const SENDS_PER_SERVER: usize = 100;
+
+const TOKEN_LENGTH: usize = 32;
+
+/// Validation emails sent within the last window, oldest first.
+pub(super) type RecentSends = VecDeque<(Instant, String, IpAddr)>;
+
+/// An email address being validated before it is added to an account.
+#[derive(Deserialize, Serialize)]
+struct EmailValidation {
+	client_secret: OwnedClientSecret,
+	email: String,
+	send_attempt: UInt,
+	token: String,
+	expires_at: u64,
+
+	#[serde(skip_serializing_if = "Option::is_none")]
+	validated_at: Option<MilliSecondsSinceUnixEpoch>,
+}
+
+/// An email address added to an account.
+#[derive(Deserialize, Serialize)]
+struct Threepid {
+	validated_at: MilliSecondsSinceUnixEpoch,
+	added_at: MilliSecondsSinceUnixEpoch,
+}
+
+/// Emails a link to validate an address, returning the session the client
+/// adds the address with once validated. Asking again with the same secret,
+/// address and attempt returns the same session without another email, and a
+/// later attempt resends the same link. Emails are limited per address, per
+/// client and for the whole server, as anyone can ask for them.
+#[implement(super::Service)]
+pub async fn request_email_validation(
+	&self,
+	client_secret: &ClientSecret,
+	email: &str,
+	send_attempt: UInt,
+	client: IpAddr,
+) -> Result<OwnedSessionId> {
+	let config = &self.services.server.config;
+	let Some(mailer) = self.services.pusher.mailer() else {
+		return Err!(Request(ThreepidDenied("Email is not enabled on this server.")));
+	};
+
+	if !is_email_address(email) {
+		return Err!(Request(InvalidParam("Not a valid email address.")));
+	}
+
+	self.remove_expired_validations().await;
+
+	let existing = self
+		.validation_session(client_secret, email)
+		.await;
+
+	let expires_at = now_millis().saturating_add(VALIDATION_LIFETIME.as_millis().try_into()?);
+
+	let (sid, validation, resent) = match existing {
+		| Some((sid, validation)) if validation.send_attempt >= send_attempt => {
+			return Ok(sid);
+		},
+		| Some((sid, validation)) => {
+			let resent = Some(validation.expires_at);
+			let validation = EmailValidation { send_attempt, expires_at, ..validation };
+
+			(sid, validation, resent)
+		},
+		| None => {
+			let validation = EmailValidation {
+				client_secret: client_secret.to_owned(),
+				email: email.to_owned(),
+				send_attempt,
+				token: utils::random_string(TOKEN_LENGTH),
+				expires_at,
+				validated_at: None,
+			};
+
+			(utils::random_string(TOKEN_LENGTH).try_into()?, validation, None)
+		},
+	};
+
+	self.reserve_send(email, client)?;
+
+	let server_name = self.services.globals.server_name();
+	let from = config
+		.email_from
+		.clone()
+		.unwrap_or_else(|| format!("noreply@{server_name}"));
+
+	let url = self.email_validation_url(sid.as_str(), &validation.token)?;
+	let headers = smtp::origin_headers(server_name)?;
+	let message = format!(
+		"{headers}From: {from}\r\nTo: {email}\r\nSubject: Confirm your email address on \
+		 {server_name}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; \
+		 charset=utf-8\r\n\r\nSomeone asked to add this address to an account on \
+		 {server_name}.\r\n\r\nFollow this link to confirm it was you:\r\n\r\n{url}\r\n\r\nIf \
+		 it was not, ignore this email.\r\n"
+	);
+
+	smtp::send_mail(mailer, &from, email, &message).await?;
+	debug!(?sid, "Sent email validation");
+
+	if let Some(expires_at) = resent {
+		self.db
+			.emailexpiresatsid
+			.del((expires_at, sid.as_str()));
+	}
+
+	self.put_validation(&sid, &validation);
+
+	Ok(sid)
+}
+
+/// Confirms an address from the link in the validation email.
+#[implement(super::Service)]
+pub async fn validate_email(&self, sid: &str, token: &str) -> Result {
+	let mut validation: EmailValidation = self
+		.db
+		.emailsid_validation
+		.get(sid)
+		.await
+		.deserialized()
+		.map_err(|_| err!(Request(NotFound("Unknown validation link."))))?;
+
+	if validation.expires_at < now_millis() {
+		return Err!(Request(Forbidden("Validation link is expired.")));
+	}
+
+	if validation.token != token {
+		return Err!(Request(Forbidden("Invalid validation link.")));
+	}
+
+	validation.validated_at = Some(MilliSecondsSinceUnixEpoch::now());
+	self.db
+		.emailsid_validation
+		.raw_put(sid, Json(&validation));
+
+	Ok(())
+}
+
+/// Adds the address validated in a session to the user's account.
+#[implement(super::Service)]
+pub async fn add_threepid(
+	&self,
+	user_id: &UserId,
+	client_secret: &ClientSecret,
+	sid: &SessionId,
+) -> Result {
+	let validation: EmailValidation = self
+		.db
+		.emailsid_validation
+		.get(sid.as_str())
+		.await
+		.deserialized()
+		.map_err(|_| err!(Request(ThreepidAuthFailed("Unknown validation session."))))?;
+
+	if validation.client_secret.as_str() != client_secret.as_str() {
+		return Err!(Request(ThreepidAuthFailed("Unknown validation session.")));
+	}
+
+	let Some(validated_at) = validation.validated_at else {
+		return Err!(Request(ThreepidAuthFailed("Email address has not been validated.")));
+	};
+
+	let threepid = Threepid {
+		validated_at,
+		added_at: MilliSecondsSinceUnixEpoch::now(),
+	};
+
+	self.remove_validation(sid.as_str(), &validation);
+	self.db
+		.useridaddress_threepid
+		.put((user_id, validation.email.as_str()), Json(&threepid));
+
+	Ok(())
+}
+
+/// Removes an address from the user's account.
+#[implement(super::Service)]
+pub async fn remove_threepid(&self, user_id: &UserId, address: &str) -> Result {
+	if !self.has_threepid(user_id, address).await {
+		return Err!(Request(NotFound("The address is not on this account.")));
+	}
+
+	self.db
+		.useridaddress_threepid
+		.del((user_id, address));
+
+	Ok(())
+}
+
+/// Whether the user has added the validated address to their account.
+#[implement(super::Service)]
+pub async fn has_threepid(&self, user_id: &UserId, address: &str) -> bool {
+	self.db
+		.useridaddress_threepid
+		.qry(&(user_id, address))
+		.await
+		.is_ok()
+}
+
+/// Returns the validated addresses of the user's account.
+#[implement(super::Service)]
+pub async fn threepids(&self, user_id: &UserId) -> Vec<ThirdPartyIdentifier> {
+	type KeyVal<'a> = ((Ignore, &'a str), Threepid);
+
+	let prefix = (user_id, Interfix);
+	self.db
+		.useridaddress_threepid
+		.stream_prefix(&prefix)
+		.ignore_err()
+		.map(|((_, address), threepid): KeyVal<'_>| {
+			ThirdPartyIdentifier::from(ThirdPartyIdentifierInit {
+				address: address.to_owned(),
+				medium: Medium::Email,
+				validated_at: threepid.validated_at,
+				added_at: threepid.added_at,
+			})
+		})
+		.collect()
+		.await
+}
+
+/// Removes all addresses from the user's account.
+#[implement(super::Service)]
+pub(super) async fn remove_threepids(&self, user_id: &UserId) {
+	let prefix = (user_id, Interfix);
+	self.db
+		.useridaddress_threepid
+		.keys_prefix_raw(&prefix)
+		.ignore_err()
+		.ready_for_each(|key| self.db.useridaddress_threepid.remove(key))
+		.await;
+}
+
+#[implement(super::Service)]
+async fn validation_session(
+	&self,
+	client_secret: &ClientSecret,
+	email: &str,
+) -> Option<(OwnedSessionId, EmailValidation)> {
+	let sid: OwnedSessionId = self
+		.db
+		.emailsecretaddress_sid
+		.qry(&(client_secret.as_str(), email))
+		.await
+		.deserialized()
+		.ok()?;
+
+	let validation = self
+		.db
+		.emailsid_validation
+		.get(sid.as_str())
+		.await
+		.deserialized()
+		.ok()?;
+
+	Some((sid, validation))
+}
+
+#[implement(super::Service)]
+fn put_validation(&self, sid: &SessionId, validation: &EmailValidation) {
+	let sid = sid.as_str();
+	let index = (validation.client_secret.as_str(), validation.email.as_str());
+	self.db
+		.emailsid_validation
+		.raw_put(sid, Json(validation));
+	self.db.emailsecretaddress_sid.put_raw(index, sid);
+	self.db
+		.emailexpiresatsid
+		.put_raw((validation.expires_at, sid), []);
+}
+
+#[implement(super::Service)]
+fn remove_validation(&self, sid: &str, validation: &EmailValidation) {
+	let index = (validation.client_secret.as_str(), validation.email.as_str());
+	self.db.emailsid_validation.remove(sid);
+	self.db.emailsecretaddress_sid.del(index);
+	self.db
+		.emailexpiresatsid
+		.del((validation.expires_at, sid));
+}
+
+/// Removes the validations which expired, which are the first keys of the
+/// expiry map.
+#[implement(super::Service)]
+async fn remove_expired_validations(&self) {
+	let now = now_millis();
+	let expired: Vec<(u64, String)> = self
+		.db
+		.emailexpiresatsid
+		.keys()
+		.ignore_err()
+		.ready_take_while(|&(expires_at, _): &(u64, &str)| expires_at < now)
+		.map(|(expires_at, sid)| (expires_at, sid.to_owned()))
+		.collect()
+		.await;
+
+	for (expires_at, sid) in expired {
+		let validation: Result<EmailValidation> = self
+			.db
+			.emailsid_validation
+			.get(&sid)
+			.await
+			.deserialized();
+
+		match validation {
+			| Ok(validation) if validation.expires_at == expires_at =>
+				self.remove_validation(&sid, &validation),
+			| _ => self
+				.db
+				.emailexpiresatsid
+				.del((expires_at, sid.as_str())),
+		}
+	}
+}
+
+/// Counts an email to the address requested by the client against the send
+/// limits, failing when any of them is reached.
+#[implement(super::Service)]
+fn reserve_send(&self, email: &str, client: IpAddr) -> Result {
+	let now = Instant::now();
+	let mut sends = self.recent_sends.lock()?;
+	while sends
+		.front()
+		.is_some_and(|(sent, ..)| now.duration_since(*sent) >= SEND_WINDOW)
+	{
+		sends.pop_front();
+	}
+
+	let to_address = sends
+		.iter()
+		.filter(|(_, address, _)| address.eq_ignore_ascii_case(email))
+		.count();
+
+	let from_client = sends
+		.iter()
+		.filter(|(.., ip)| *ip == client)
+		.count();
+
+	if to_address >= SENDS_PER_ADDRESS
+		|| from_client >= SENDS_PER_CLIENT
+		|| sends.len() >= SENDS_PER_SERVER
+	{
+		let retry_after = sends.front().map_or(SEND_WINDOW, |(sent, ..)| {
+			SEND_WINDOW.saturating_sub(now.duration_since(*sent))
+		});
+
+		return Err(Error::Request(
+			ErrorKind::LimitExceeded {
+				retry_after: Some(RetryAfter::Delay(retry_after)),
+			},
+			"Too many validation emails were requested, try again later.".into(),
+			StatusCode::TOO_MANY_REQUESTS,
+		));
+	}
+
+	sends.push_back((now, email.to_owned(), client));
+
+	Ok(())
+}
+
+#[implement(super::Service)]
+fn email_validation_url(&self, sid: &str, token: &str) -> Result<Url> {
+	let base = match &self.services.server.config.well_known.client {
+		| Some(client) => client.to_string(),
+		| None => format!("https://{}", self.services.globals.server_name()),
+	};
+
+	Url::parse(&base)
+		.and_then(|base| base.join(&format!("/_tuwunel/email/validate/{sid}/{token}")))
+		.map_err(|e| err!(Config("well_known.client", "Invalid client URL: {e}")))
+}